@@ -1,15 +1,16 @@
 
 const redisCache = new azure.cache.Redis("myRedisCache", {
-    location: "EastUS",
-    resourceGroupName: "myResourceGroup",
+    location: resourceGroup.location,
+    tags,
+    resourceGroupName: resourceGroup.name,
     sku: {
-        name: "Standard",
-        family: "C",
-        capacity: 1, // 1 = 1 GB cache size
+        name: {{param:skuName}},
+        family: {{param:skuFamily}},
+        capacity: {{param:capacity}}, // 1 = 1 GB cache size
     },
     enableNonSslPort: false,
     minimumTlsVersion: "1.2",
-});
+}, resourceOptions);
 
 export const redisHostName = redisCache.hostname;
 export const redisPrimaryKey = redisCache.primaryKey;
@@ -0,0 +1,40 @@
+// @ts-ignore
+import * as pulumi from "@pulumi/pulumi";
+// @ts-ignore
+import * as azure from "@pulumi/azure-native";
+{{args_interfaces}}
+export interface {{class_name}}Args {
+    location?: pulumi.Input<string>;
+    /** Regions that get a copy of each region-scoped resource. */
+    regions?: string[];
+    resourceGroupName?: pulumi.Input<string>;
+    /** Tags applied to every taggable resource. */
+    tags?: Record<string, string>;
+{{args_fields}}}
+
+// All of the application's resources, grouped under a single parent in the resource tree
+export class {{class_name}} extends pulumi.ComponentResource {
+    public readonly resourceGroupName: pulumi.Output<string>;
+{{output_fields}}
+    constructor(name: string, args: {{class_name}}Args = {}, opts?: pulumi.ComponentResourceOptions) {
+        super("{{project_name}}:index:{{class_name}}", name, {}, opts);
+
+        const resourceOptions: pulumi.CustomResourceOptions = { parent: this };
+        const naming = (suffix: string) => `${name}-${suffix}`;
+        const regions = args.regions ?? [];
+        const tags = args.tags;
+
+        const resourceGroup = new azure.resources.ResourceGroup(naming("rg"), {
+            resourceGroupName: args.resourceGroupName,
+            location: args.location,
+            tags,
+        }, resourceOptions);
+
+{{body}}
+        this.resourceGroupName = resourceGroup.name;
+{{output_assignments}}
+        this.registerOutputs({
+            resourceGroupName: this.resourceGroupName,
+{{registered_outputs}}        });
+    }
+}
@@ -1,12 +1,13 @@
 
 const containerRegistry = new azure.containerregistry.Registry("myContainerRegistry", {
-    resourceGroupName: "myResourceGroup",
-    location: "EastUS",
+    resourceGroupName: resourceGroup.name,
+    location: resourceGroup.location,
+    tags,
     sku: {
-        name: "Basic", // Options: Basic, Standard, Premium
+        name: {{param:skuName}}, // Options: Basic, Standard, Premium
     },
-    adminUserEnabled: true,
-});
+    adminUserEnabled: {{param:adminUserEnabled}},
+}, resourceOptions);
 
 export const registryName = containerRegistry.name;
 export const registryLoginServer = containerRegistry.loginServer;
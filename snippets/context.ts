@@ -0,0 +1,14 @@
+// @ts-ignore
+import * as pulumi from "@pulumi/pulumi";
+// @ts-ignore
+import * as azure from "@pulumi/azure-native";
+
+// Shared values handed to every resource module
+export interface ResourceContext {
+    resourceGroup: azure.resources.ResourceGroup;
+    config: pulumi.Config;
+    regions: string[];
+    tags: Record<string, string>;
+    naming: (suffix: string) => string;
+    resourceOptions: pulumi.CustomResourceOptions;
+}
@@ -1,19 +1,20 @@
 
 const postgresServer = new azure.dbforpostgresql.FlexibleServer("myPostgres", {
-    resourceGroupName: "myResourceGroup",
-    location: "EastUS",
-    administratorLogin: "adminUser",
-    administratorLoginPassword: "strongPassword123!",
-    version: "13", // PostgreSQL version
+    resourceGroupName: resourceGroup.name,
+    location: resourceGroup.location,
+    tags,
+    administratorLogin: {{param:administratorLogin}},
+    administratorLoginPassword: {{secret:administratorLoginPassword}},
+    version: {{param:version}}, // PostgreSQL version
     sku: {
-        name: "Standard_D2s_v3",
-        tier: "GeneralPurpose",
+        name: {{param:skuName}},
+        tier: {{param:skuTier}},
         capacity: 2,
     },
     storage: {
-        storageSizeGb: 32,
+        storageSizeGb: {{param:storageSizeGb}},
     },
-});
+}, resourceOptions);
 
 export const postgresHost = postgresServer.fqdn;
 export const postgresAdmin = postgresServer.administratorLogin;
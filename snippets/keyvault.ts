@@ -1,12 +1,14 @@
 
 const keyVault = new azure.keyvault.Vault("myKeyVault", {
-    resourceGroupName: "myResourceGroup",
-    location: "EastUS",
+    resourceGroupName: resourceGroup.name,
+    location: resourceGroup.location,
+    tags,
     properties: {
-        sku: { name: "standard", family: "A" },
-        tenantId: "YOUR_TENANT_ID", // Replace with your Azure tenant ID
+        sku: { name: {{param:skuName}}, family: "A" },
+        tenantId: {{param:tenantId}}, // Replace with your Azure tenant ID
         accessPolicies: [], // Add access policies here
     },
-});
+}, resourceOptions);
 
+export const keyVaultName = keyVault.name;
 export const keyVaultUri = keyVault.properties?.vaultUri;
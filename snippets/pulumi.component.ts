@@ -0,0 +1,20 @@
+// @ts-ignore
+import * as pulumi from "@pulumi/pulumi";
+import { {{class_name}} } from "./component";
+
+// Configuration variables (optional customization via Pulumi config)
+const config = new pulumi.Config();
+const location = config.require("location"); // Primary region, from the subscription registry
+const drRegion = config.get("drRegion");
+const activeRegions = config.getObject<string[]>("regions") ?? [location];
+
+const app = new {{class_name}}("{{project_name}}", {
+    location,
+    regions: drRegion ? [...activeRegions, drRegion] : activeRegions,
+    resourceGroupName: config.get("resourceGroupName"),
+    tags: config.requireObject<Record<string, string>>("tags"),
+{{component_args}}});
+
+// Export outputs
+export const resourceGroupName = app.resourceGroupName;
+{{component_exports}}
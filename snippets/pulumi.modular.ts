@@ -0,0 +1,39 @@
+// @ts-ignore
+import * as pulumi from "@pulumi/pulumi";
+// @ts-ignore
+import * as azure from "@pulumi/azure-native";
+import { ResourceContext } from "./resources/context";
+{{module_imports}}
+// Configuration variables (optional customization via Pulumi config)
+const config = new pulumi.Config();
+const location = config.require("location"); // Primary region, from the subscription registry
+const resourceGroupName = config.get("resourceGroupName") || "test-rg";
+const tags = config.requireObject<Record<string, string>>("tags"); // From the tag policy, applied to every taggable resource
+
+// Region-scoped resources are created in every active region plus the DR region, if any
+const drRegion = config.get("drRegion");
+const activeRegions = config.getObject<string[]>("regions") ?? [location];
+const regions: string[] = drRegion ? [...activeRegions, drRegion] : activeRegions;
+
+// Create an Azure Resource Group
+const resourceGroup = new azure.resources.ResourceGroup("example-rg", {
+    resourceGroupName: resourceGroupName,
+    location: location,
+    tags,
+});
+
+// Context shared by every resource module
+const ctx: ResourceContext = {
+    resourceGroup,
+    config,
+    regions,
+    tags,
+    naming: (suffix: string) => `{{project_name}}-${pulumi.getStack()}-${suffix}`,
+    resourceOptions: {},
+};
+
+// Create resources in dependency order
+{{module_calls}}
+// Export outputs
+export const rgName = resourceGroup.name;
+{{module_exports}}
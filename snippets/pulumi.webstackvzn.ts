@@ -5,19 +5,30 @@ import * as azure from "@pulumi/azure-native";
 
 // Configuration variables (optional customization via Pulumi config)
 const config = new pulumi.Config();
-const location = config.get("location") || "eastus2"; // Default location
+const location = config.require("location"); // Primary region, from the subscription registry
 const resourceGroupName = config.get("resourceGroupName") || "test-rg";
+const tags = config.requireObject<Record<string, string>>("tags"); // From the tag policy, applied to every taggable resource
+
+// Region-scoped resources are created in every active region plus the DR region, if any
+const drRegion = config.get("drRegion");
+const activeRegions = config.getObject<string[]>("regions") ?? [location];
+const regions: string[] = drRegion ? [...activeRegions, drRegion] : activeRegions;
 
 // Create an Azure Resource Group
 const resourceGroup = new azure.resources.ResourceGroup("example-rg", {
     resourceGroupName: resourceGroupName,
     location: location,
+    tags,
 });
 
+// Options applied to every resource added from the snippet catalog
+const resourceOptions: pulumi.CustomResourceOptions = {};
+
 // Create an Azure Storage Account
 const storageAccount = new azure.storage.StorageAccount("examplestorage", {
     resourceGroupName: resourceGroup.name,
     location: resourceGroup.location,
+    tags,
     sku: {
         name: "Standard_LRS", // Local-redundant storage
     },
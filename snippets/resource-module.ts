@@ -0,0 +1,12 @@
+// @ts-ignore
+import * as pulumi from "@pulumi/pulumi";
+// @ts-ignore
+import * as azure from "@pulumi/azure-native";
+import { ResourceContext } from "./context";
+{{dependency_imports}}
+export function {{factory_name}}(ctx: ResourceContext{{dependency_params}}) {
+    const { resourceGroup, config, regions, tags, naming, resourceOptions } = ctx;
+{{dependency_locals}}
+{{body}}
+    return { {{outputs}} };
+}
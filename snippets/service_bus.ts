@@ -1,20 +1,19 @@
 
-import * as azure from "@pulumi/azure-native";
-
 const serviceBusNamespace = new azure.servicebus.Namespace("myServiceBusNamespace", {
-    resourceGroupName: "myResourceGroup",
-    location: "EastUS",
+    resourceGroupName: resourceGroup.name,
+    location: resourceGroup.location,
+    tags,
     sku: {
-        name: "Standard",
-        tier: "Standard",
+        name: {{param:skuName}},
+        tier: {{param:skuName}},
     },
-});
+}, resourceOptions);
 
 const serviceBusQueue = new azure.servicebus.Queue("myQueue", {
-    resourceGroupName: "myResourceGroup",
+    resourceGroupName: resourceGroup.name,
     namespaceName: serviceBusNamespace.name,
-    enablePartitioning: true,
-});
+    enablePartitioning: {{param:enablePartitioning}},
+}, resourceOptions);
 
 export const serviceBusNamespaceName = serviceBusNamespace.name;
 export const serviceBusQueueName = serviceBusQueue.name;
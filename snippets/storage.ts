@@ -1,18 +1,19 @@
 // @ts-ignore
 const storageAccount = new azure.storage.StorageAccount("myStorageAccount", {
-    resourceGroupName: "myResourceGroup",
-    location: "EastUS",
+    resourceGroupName: resourceGroup.name,
+    location: resourceGroup.location,
+    tags,
     sku: {
-        name: "Standard_LRS",
+        name: {{param:skuName}},
     },
     kind: "StorageV2",
-});
+}, resourceOptions);
 
 const storageContainer = new azure.storage.BlobContainer("myContainer", {
-    resourceGroupName: "myResourceGroup",
+    resourceGroupName: resourceGroup.name,
     accountName: storageAccount.name,
-    publicAccess: "Blob", // Allow public access to blobs
-});
+    publicAccess: {{param:publicAccess}}, // Allow public access to blobs
+}, resourceOptions);
 
 export const storageAccountName = storageAccount.name;
 export const containerName = storageContainer.name;
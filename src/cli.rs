@@ -1,6 +1,11 @@
 use clap::{Parser, Subcommand};
+use std::path::PathBuf;
 
 use crate::commands;
+use crate::commands::project::ProjectOverrides;
+use crate::helpers::config_diff::DiffFormat;
+use crate::helpers::cost_diff::CostDiffFormat;
+use crate::helpers::price_catalog::parse_captured;
 
 #[derive(Parser)]
 #[command(name = "pulumimi")]
@@ -26,6 +31,8 @@ pub enum Commands {
     Interactive {
         #[arg(long, help = "Suppress file output")]
         suppress_file: bool,
+        #[command(flatten)]
+        overrides: ProjectOverrides,
     },
 
     /// Create a new project
@@ -33,6 +40,8 @@ pub enum Commands {
         project_name: String,
         #[arg(long, help = "Type of the resource")]
         resource: Option<String>,
+        #[command(flatten)]
+        overrides: ProjectOverrides,
     },
 
     /// Snippet management (also available as 'snippets')
@@ -42,11 +51,124 @@ pub enum Commands {
         subcommand: SnippetSubcommand,
     },
 
-    /// Open configuration editor
+    /// Stack configuration management
     Config {
+        #[command(subcommand)]
+        subcommand: ConfigSubcommand,
+    },
+
+    /// Deployment pipeline management
+    Pipeline {
+        #[command(subcommand)]
+        subcommand: PipelineSubcommand,
+    },
+
+    /// Monthly cost estimates
+    Cost {
+        #[command(subcommand)]
+        subcommand: CostSubcommand,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum CostSubcommand {
+    /// Estimate the monthly cost of a project per environment and resource
+    Estimate {
+        #[arg(
+            default_value = ".",
+            help = "Project directory, or project configuration file (YAML or JSON)"
+        )]
+        path: String,
+        #[arg(
+            long = "env",
+            value_name = "ENV",
+            help = "Environment to estimate (repeatable, default: all)"
+        )]
+        environments: Vec<String>,
+        #[arg(
+            long,
+            help = "Fetch current prices from the Azure Retail Prices API instead of the price catalog"
+        )]
+        online: bool,
+    },
+
+    /// Compare the monthly cost of two versions of a project per environment and resource
+    Diff {
+        #[arg(help = "Project directory or configuration file before the change")]
+        before: String,
+        #[arg(help = "Project directory or configuration file after the change")]
+        after: String,
+        #[arg(
+            long = "env",
+            value_name = "ENV",
+            help = "Environment to compare (repeatable, default: all)"
+        )]
+        environments: Vec<String>,
+        #[arg(long, value_enum, default_value_t = CostDiffFormat::Table, help = "Output format")]
+        format: CostDiffFormat,
+    },
+
+    /// Add saved Azure Retail Prices API pages to the offline price catalog
+    Import {
+        #[arg(
+            required = true,
+            help = "Saved API responses (JSON with Items and NextPageLink)"
+        )]
+        files: Vec<PathBuf>,
+        #[arg(
+            long,
+            value_name = "FILE",
+            help = "Catalog file to update (default: the user catalog)"
+        )]
+        output: Option<PathBuf>,
+        #[arg(
+            long,
+            value_name = "DATE",
+            value_parser = parse_captured,
+            help = "Date the pages were saved, as YYYY-MM-DD (default: today)"
+        )]
+        captured: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum PipelineSubcommand {
+    /// Check a project's azure-pipelines.yaml without running it
+    Validate {
+        #[arg(default_value = ".", help = "Project directory")]
+        dir: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ConfigSubcommand {
+    /// Edit the stack files of a project side by side
+    Edit {
+        #[arg(default_value = ".", help = "Project directory")]
+        dir: String,
         #[arg(long, help = "Resource to configure")]
         resource: Option<String>,
     },
+
+    /// Check every stack file of a project against the config schema of its resources
+    Validate {
+        #[arg(default_value = ".", help = "Project directory")]
+        dir: String,
+    },
+
+    /// Compare the stack files of a project side by side
+    Diff {
+        #[arg(default_value = ".", help = "Project directory")]
+        dir: String,
+        #[arg(
+            long = "env",
+            value_name = "ENV",
+            help = "Environment or stack file to compare (repeatable, default: all)"
+        )]
+        environments: Vec<String>,
+        #[arg(long, value_enum, default_value_t = DiffFormat::Table, help = "Output format")]
+        format: DiffFormat,
+    },
 }
 
 #[derive(Subcommand)]
@@ -69,19 +191,24 @@ pub enum SnippetSubcommand {
 pub async fn run(command: Commands) {
     match command {
         // Handle the 'Interactive' subcommand
-        Commands::Interactive { suppress_file } => {
+        Commands::Interactive {
+            suppress_file,
+            overrides,
+        } => {
             // TODO: Implement file output suppression
             if suppress_file {
                 println!("Suppressing file output...");
             }
-            commands::project::new(None, None, Some(true))
-                .await
-                .unwrap(); // TODO: no unwrap
+            if let Err(err) = commands::project::new(None, None, Some(true), overrides).await {
+                eprintln!("{}", err);
+                std::process::exit(1);
+            }
         }
         // Handle the 'New' subcommand
         Commands::New {
             project_name,
             resource,
+            overrides,
         } => {
             match resource {
                 Some(resource_type) => {
@@ -94,9 +221,12 @@ pub async fn run(command: Commands) {
                     .unwrap(); // TODO: no unwrap
                 }
                 _ => {
-                    commands::project::new(Some(&project_name), None, None)
-                        .await
-                        .unwrap(); // TODO: no unwrap (error handling here)
+                    if let Err(err) =
+                        commands::project::new(Some(&project_name), None, None, overrides).await
+                    {
+                        eprintln!("{}", err);
+                        std::process::exit(1);
+                    }
                 }
             }
         }
@@ -129,10 +259,75 @@ pub async fn run(command: Commands) {
             }
         }
         // Handle the 'Config' subcommand
-        Commands::Config { resource } => {
-            commands::config::open_editor(resource.as_deref())
-                .await
-                .unwrap(); // TODO: no unwrap
-        }
+        Commands::Config { subcommand } => match subcommand {
+            ConfigSubcommand::Edit { dir, resource } => {
+                if let Err(err) = commands::config::open_editor(&dir, resource.as_deref()).await {
+                    eprintln!("{}", err);
+                    std::process::exit(1);
+                }
+            }
+            ConfigSubcommand::Validate { dir } => {
+                if let Err(err) = commands::config::validate(&dir).await {
+                    eprintln!("{}", err);
+                    std::process::exit(1);
+                }
+            }
+            ConfigSubcommand::Diff {
+                dir,
+                environments,
+                format,
+            } => {
+                if let Err(err) = commands::config::diff(&dir, &environments, format).await {
+                    eprintln!("{}", err);
+                    std::process::exit(1);
+                }
+            }
+        },
+        // Handle the 'Pipeline' subcommand
+        Commands::Pipeline { subcommand } => match subcommand {
+            PipelineSubcommand::Validate { dir } => {
+                if let Err(err) = commands::pipeline::validate(&dir).await {
+                    eprintln!("{}", err);
+                    std::process::exit(1);
+                }
+            }
+        },
+        // Handle the 'Cost' subcommand
+        Commands::Cost { subcommand } => match subcommand {
+            CostSubcommand::Estimate {
+                path,
+                environments,
+                online,
+            } => {
+                if let Err(err) = commands::cost::estimate(&path, &environments, online).await {
+                    eprintln!("{}", err);
+                    std::process::exit(1);
+                }
+            }
+            CostSubcommand::Diff {
+                before,
+                after,
+                environments,
+                format,
+            } => {
+                if let Err(err) = commands::cost::diff(&before, &after, &environments, format).await
+                {
+                    eprintln!("{}", err);
+                    std::process::exit(1);
+                }
+            }
+            CostSubcommand::Import {
+                files,
+                output,
+                captured,
+            } => {
+                if let Err(err) =
+                    commands::cost::import(&files, output.as_deref(), captured.as_deref()).await
+                {
+                    eprintln!("{}", err);
+                    std::process::exit(1);
+                }
+            }
+        },
     }
 }
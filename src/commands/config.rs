@@ -1,6 +1,81 @@
-// use crate::tui::snippet_select::run_table;
+use std::path::Path;
 
-pub async fn open_editor(_resource: Option<&str>) -> Result<(), anyhow::Error> {
-    // run_table().await?;
+use anyhow::anyhow;
+use colored::*;
+
+use crate::helpers::config_diff::{environment_label, ConfigDiff, DiffFormat};
+use crate::helpers::config_helper::load_stack_files;
+use crate::helpers::config_schema::{infer_project, ConfigSchema};
+use crate::tui::config_editor::run_config_editor;
+
+/// Opens the stack-config editor on a project directory.
+///
+/// # Arguments
+/// - `dir`: The project directory.
+/// - `resource`: Only show the config keys of this resource, e.g. `cache`.
+pub async fn open_editor(dir: &str, resource: Option<&str>) -> Result<(), anyhow::Error> {
+    run_config_editor(Path::new(dir), resource)
+}
+
+/// Validates every stack file in a project directory against the config schema
+/// of the project's resources, printing the issues found in each file.
+///
+/// # Returns
+/// - An error if the project cannot be read or any stack file has issues.
+pub async fn validate(dir: &str) -> Result<(), anyhow::Error> {
+    let dir = Path::new(dir);
+    let stack_files = load_stack_files(dir)?;
+    let schema = ConfigSchema::for_project(&infer_project(dir, &stack_files)?)?;
+
+    let mut issue_count = 0;
+    for stack_file in &stack_files {
+        let issues = schema.validate(&stack_file.settings);
+        if issues.is_empty() {
+            println!("{} {}", "✔".green(), stack_file.file_name);
+            continue;
+        }
+        println!("{} {}", "✘".red(), stack_file.file_name);
+        for issue in &issues {
+            println!("    {}", issue.to_string().yellow());
+        }
+        issue_count += issues.len();
+    }
+
+    if issue_count > 0 {
+        return Err(anyhow!(
+            "{} issue(s) found in {} stack file(s)",
+            issue_count,
+            stack_files.len()
+        ));
+    }
+    Ok(())
+}
+
+/// Prints a per-key matrix of the stack config values of a project's environments.
+///
+/// # Arguments
+/// - `dir`: The project directory.
+/// - `environments`: Environments (or stack file names) to compare; all stack files when empty.
+/// - `format`: Terminal table, Markdown or JSON.
+pub async fn diff(
+    dir: &str,
+    environments: &[String],
+    format: DiffFormat,
+) -> Result<(), anyhow::Error> {
+    let stack_files = load_stack_files(Path::new(dir))?;
+
+    let mut selected = Vec::new();
+    if environments.is_empty() {
+        selected.extend(stack_files.iter());
+    }
+    for env in environments {
+        let stack_file = stack_files
+            .iter()
+            .find(|file| &environment_label(file) == env || &file.file_name == env)
+            .ok_or_else(|| anyhow!("No stack file for environment '{}' in {}", env, dir))?;
+        selected.push(stack_file);
+    }
+
+    print!("{}", ConfigDiff::new(&selected)?.render(format));
     Ok(())
 }
@@ -0,0 +1,180 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::anyhow;
+use colored::*;
+
+use crate::helpers::budget::check_budgets;
+use crate::helpers::cost_diff::{CostDiff, CostDiffFormat};
+use crate::helpers::cost_helper::{
+    fetch_prices, usage_from_config, usage_from_project, CostEstimate, EnvironmentUsage, CURRENCY,
+};
+use crate::helpers::price_catalog::{import_pages, user_catalog_path, PriceCatalog};
+use crate::helpers::subscription_registry::SubscriptionRegistry;
+use crate::tui::app::ProjectConfig;
+
+/// Prints the estimated monthly cost of a project per environment and resource.
+///
+/// # Arguments
+/// - `path`: A project directory, priced from its stack files, or a project
+///   configuration (answers) file in YAML or JSON.
+/// - `environments`: Environments to estimate; all when empty.
+/// - `online`: Fetch current prices from the Retail Prices API instead of the price catalog.
+pub async fn estimate(
+    path: &str,
+    environments: &[String],
+    online: bool,
+) -> Result<(), anyhow::Error> {
+    let registry = SubscriptionRegistry::load()?;
+    let (mut usage, budgets) = load_usage(Path::new(path), &registry)?;
+    if let Some(env) = missing_environment(environments, &[&usage]) {
+        return Err(anyhow!("No environment '{}' in {}", env, path));
+    }
+    select_environments(&mut usage, environments);
+
+    let catalog = match online {
+        true => PriceCatalog::from_items(
+            "Azure Retail Prices API",
+            &today(),
+            fetch_prices(&usage).await?,
+        ),
+        false => PriceCatalog::load()?,
+    };
+    let estimate = CostEstimate::new(&usage, &catalog);
+
+    println!(
+        "Estimated monthly cost in {}, prices captured {}",
+        CURRENCY,
+        catalog.captured()
+    );
+    print!("{}", estimate.render_table());
+    for meter in estimate.unpriced() {
+        println!("{}", format!("No price found for {}", meter).yellow());
+    }
+    for check in check_budgets(&budgets, &registry, &estimate)? {
+        print!("{}", check.render());
+    }
+    Ok(())
+}
+
+/// Compares the monthly cost of two versions of a project per environment and resource,
+/// with the reserved-instance alternatives the price catalog has for the new version.
+///
+/// # Arguments
+/// - `before`, `after`: Project directories or project configuration files, as for `estimate`.
+/// - `environments`: Environments to compare; all when empty.
+/// - `format`: Table for the terminal, or Markdown or CSV for change requests.
+pub async fn diff(
+    before: &str,
+    after: &str,
+    environments: &[String],
+    format: CostDiffFormat,
+) -> Result<(), anyhow::Error> {
+    let registry = SubscriptionRegistry::load()?;
+    let (mut before_usage, _) = load_usage(Path::new(before), &registry)?;
+    let (mut after_usage, _) = load_usage(Path::new(after), &registry)?;
+    if let Some(env) = missing_environment(environments, &[&before_usage, &after_usage]) {
+        return Err(anyhow!(
+            "No environment '{}' in {} or {}",
+            env,
+            before,
+            after
+        ));
+    }
+    select_environments(&mut before_usage, environments);
+    select_environments(&mut after_usage, environments);
+
+    let catalog = PriceCatalog::load()?;
+    let diff = CostDiff::new(&before_usage, &after_usage, &catalog);
+    if format == CostDiffFormat::Table {
+        println!(
+            "Monthly cost in {} of {} (before) and {} (after), prices captured {}",
+            CURRENCY,
+            before,
+            after,
+            catalog.captured()
+        );
+    }
+    print!("{}", diff.render(format));
+    for meter in diff.unpriced() {
+        eprintln!("{}", format!("No price found for {}", meter).yellow());
+    }
+    Ok(())
+}
+
+/// The first requested environment that none of the usages has.
+fn missing_environment<'a>(
+    environments: &'a [String],
+    usages: &[&Vec<EnvironmentUsage>],
+) -> Option<&'a String> {
+    environments.iter().find(|env| {
+        !usages
+            .iter()
+            .flat_map(|usage| usage.iter())
+            .any(|environment| &environment.env == *env)
+    })
+}
+
+/// Keeps the requested environments, or all when none are requested.
+fn select_environments(usage: &mut Vec<EnvironmentUsage>, environments: &[String]) {
+    if !environments.is_empty() {
+        usage.retain(|environment| environments.contains(&environment.env));
+    }
+}
+
+/// Reads the resources to price, and the project budgets, from a project directory or
+/// a project configuration file. Project directories only have the registry budgets.
+fn load_usage(
+    path: &Path,
+    registry: &SubscriptionRegistry,
+) -> Result<(Vec<EnvironmentUsage>, BTreeMap<String, f64>), anyhow::Error> {
+    if path.is_dir() {
+        return Ok((usage_from_project(path)?, BTreeMap::new()));
+    }
+    let content = fs::read_to_string(path)
+        .map_err(|err| anyhow!("Failed to read {}: {}", path.display(), err))?;
+    let config: ProjectConfig = serde_yaml::from_str(&content)
+        .map_err(|err| anyhow!("Invalid project configuration {}: {}", path.display(), err))?;
+    Ok((usage_from_config(&config, registry)?, config.budgets))
+}
+
+/// Adds saved Azure Retail Prices API pages to a price catalog file.
+///
+/// # Arguments
+/// - `files`: Saved API responses, e.g. from `curl "https://prices.azure.com/api/retail/prices?$filter=..."`.
+/// - `output`: The catalog file to update; defaults to the user catalog.
+/// - `captured`: Date the pages were saved; defaults to today.
+pub async fn import(
+    files: &[PathBuf],
+    output: Option<&Path>,
+    captured: Option<&str>,
+) -> Result<(), anyhow::Error> {
+    let output = match output {
+        Some(path) => path.to_path_buf(),
+        None => user_catalog_path()?,
+    };
+    let captured = captured.map(str::to_string).unwrap_or_else(today);
+    let summary = import_pages(files, &output, &captured)?;
+
+    println!(
+        "{} Imported {} price(s) from {} page(s) into {} (captured {})",
+        "✔".green(),
+        summary.prices,
+        summary.pages,
+        output.display(),
+        captured
+    );
+    if summary.pages_with_next == summary.pages {
+        println!(
+            "{}",
+            "Every page links to a next page; save and import the remaining pages too".yellow()
+        );
+    }
+    Ok(())
+}
+
+/// Today's date, e.g. `2026-10-19`.
+fn today() -> String {
+    chrono::Local::now().format("%Y-%m-%d").to_string()
+}
@@ -0,0 +1,40 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::anyhow;
+use colored::*;
+
+use crate::helpers::pipeline_helper::pipeline_file;
+use crate::helpers::pipeline_schema::{PipelineReferences, PipelineSchema};
+use crate::helpers::subscription_registry::SubscriptionRegistry;
+use crate::helpers::variable_group::load_variable_group_names;
+use crate::tui::app::CiTarget;
+
+/// Validates a project's `azure-pipelines.yaml` against the bundled schema, its
+/// stage and job dependencies, the service connections and variable groups of the
+/// subscription registry and the project's own variable groups, printing the issues found.
+///
+/// # Returns
+/// - An error if the pipeline cannot be read or has issues.
+pub async fn validate(dir: &str) -> Result<(), anyhow::Error> {
+    let file_name = pipeline_file(CiTarget::AzurePipelines);
+    let path = Path::new(dir).join(file_name);
+    let content = fs::read_to_string(&path)
+        .map_err(|err| anyhow!("Failed to read {}: {}", path.display(), err))?;
+
+    let mut references = PipelineReferences::from_registry(&SubscriptionRegistry::load()?);
+    references
+        .variable_groups
+        .extend(load_variable_group_names(Path::new(dir))?);
+    let issues = PipelineSchema::load()?.validate(&content, &references);
+    if issues.is_empty() {
+        println!("{} {}", "✔".green(), file_name);
+        return Ok(());
+    }
+
+    println!("{} {}", "✘".red(), file_name);
+    for issue in &issues {
+        println!("    {}", issue.to_string().yellow());
+    }
+    Err(anyhow!("{} issue(s) found in {}", issues.len(), file_name))
+}
@@ -1,20 +1,214 @@
 use anyhow::Ok;
+use std::collections::BTreeMap;
 use std::env;
 use std::fs;
 use std::sync::Arc;
 
-use crate::helpers::config_helper::create_config_files;
+use crate::helpers::bicep_helper::create_bicep_files;
+use crate::helpers::budget::{guard_budgets, parse_budget};
+use crate::helpers::config_helper::{create_config_files, region_plan, validate_stack_settings};
 use crate::helpers::entrypoint_helper::create_entry_point;
-use crate::helpers::pipeline_helper::create_pipeline_yaml;
-use crate::tui::app::ProjectConfig;
+use crate::helpers::network_plan::{plan_network, NetworkRegistry};
+use crate::helpers::pipeline_helper::{create_pipeline_yaml, deploy_stages, drift_stages};
+use crate::helpers::price_catalog::PriceCatalog;
+use crate::helpers::readme_helper::create_readme;
+use crate::helpers::subscription_registry::SubscriptionRegistry;
+use crate::helpers::tag_policy::{parse_tag, TagPolicy};
+use crate::helpers::variable_group::create_variable_groups;
+use crate::tui::app::{
+    BudgetPolicy, CiTarget, DriftDetection, DriftNotify, OutputLayout, ProjectConfig, SecretMode,
+};
 use crate::tui::tui_main::tui_main;
 
+/// Generation choices passed on the command line that override the answers
+/// from the TUI or the defaults.
+#[derive(clap::Args, Debug, Default)]
+pub struct ProjectOverrides {
+    #[arg(long, value_enum, help = "Layout of the generated Pulumi program")]
+    pub layout: Option<OutputLayout>,
+    #[arg(long, help = "Also export the project as Bicep")]
+    pub bicep: bool,
+    #[arg(
+        long,
+        value_enum,
+        help = "How secret resource inputs such as passwords are supplied"
+    )]
+    pub secrets: Option<SecretMode>,
+    #[arg(
+        long = "env",
+        value_name = "ENV",
+        help = "Environment to generate, from the subscription registry (repeatable)"
+    )]
+    pub environments: Vec<String>,
+    #[arg(
+        long = "org",
+        help = "Pulumi organization used for {org} in stack names"
+    )]
+    pub organization: Option<String>,
+    #[arg(
+        long,
+        value_name = "PATTERN",
+        help = "Stack name pattern using {org}, {app} and {env} (default: {env})"
+    )]
+    pub stack_pattern: Option<String>,
+    #[arg(
+        long = "esc-env",
+        value_name = "NAME",
+        help = "ESC environment imported by every stack, may use {app} and {env} (repeatable)"
+    )]
+    pub esc_environments: Vec<String>,
+    #[arg(
+        long,
+        help = "Deploy region-scoped resources to several regions and a DR region"
+    )]
+    pub multi_region: bool,
+    #[arg(
+        long = "region",
+        value_name = "REGION",
+        help = "Extra region next to each environment's primary location (repeatable, implies --multi-region)"
+    )]
+    pub regions: Vec<String>,
+    #[arg(
+        long,
+        value_name = "REGION",
+        help = "DR region, defaults to the Azure pair of the primary location (implies --multi-region)"
+    )]
+    pub dr_region: Option<String>,
+    #[arg(
+        long = "owner",
+        value_name = "EMAIL",
+        help = "Owner email of the project"
+    )]
+    pub owner_email: Option<String>,
+    #[arg(
+        long = "tag",
+        value_name = "NAME=VALUE",
+        value_parser = parse_tag,
+        help = "Tag value applied to every resource, overriding the tag policy (repeatable)"
+    )]
+    pub tags: Vec<(String, String)>,
+    #[arg(
+        long = "approval-above",
+        value_name = "TIER",
+        help = "Deployments to environments above this tier wait for manual approval (default: silver)"
+    )]
+    pub approval_tier: Option<String>,
+    #[arg(
+        long,
+        value_enum,
+        help = "CI system the deployment pipeline is generated for"
+    )]
+    pub ci: Option<CiTarget>,
+    #[arg(
+        long,
+        help = "Add a scheduled job that checks each environment for drift"
+    )]
+    pub drift: bool,
+    #[arg(
+        long,
+        value_name = "CRON",
+        help = "Schedule of the drift check, in UTC (default: 0 3 * * *, implies --drift)"
+    )]
+    pub drift_schedule: Option<String>,
+    #[arg(
+        long = "drift-env",
+        value_name = "ENV",
+        help = "Environment checked for drift (repeatable, default: all, implies --drift)"
+    )]
+    pub drift_environments: Vec<String>,
+    #[arg(
+        long,
+        value_enum,
+        help = "How drift is reported (default: fail, implies --drift)"
+    )]
+    pub drift_notify: Option<DriftNotify>,
+    #[arg(
+        long = "budget",
+        value_name = "ENV_OR_TIER=AMOUNT",
+        value_parser = parse_budget,
+        help = "Monthly budget of an environment or tier, overriding the subscription registry (repeatable)"
+    )]
+    pub budgets: Vec<(String, f64)>,
+    #[arg(
+        long,
+        value_enum,
+        help = "Whether an environment over budget only warns or stops generation (default: warn)"
+    )]
+    pub budget_policy: Option<BudgetPolicy>,
+}
+
+impl ProjectOverrides {
+    fn apply(&self, config: &mut ProjectConfig) {
+        if let Some(layout) = self.layout {
+            config.layout = layout;
+        }
+        if self.bicep {
+            config.export_bicep = true;
+        }
+        if let Some(secret_mode) = self.secrets {
+            config.secret_mode = secret_mode;
+        }
+        if !self.environments.is_empty() {
+            config.environments = self.environments.clone();
+        }
+        if self.organization.is_some() {
+            config.organization = self.organization.clone();
+        }
+        if self.stack_pattern.is_some() {
+            config.stack_pattern = self.stack_pattern.clone();
+        }
+        if !self.esc_environments.is_empty() {
+            config.esc_environments = self.esc_environments.clone();
+        }
+        if !self.regions.is_empty() {
+            config.regions = self.regions.clone();
+        }
+        if self.dr_region.is_some() {
+            config.dr_region = self.dr_region.clone();
+        }
+        if self.multi_region || !self.regions.is_empty() || self.dr_region.is_some() {
+            config.multi_region = true;
+        }
+        if let Some(owner_email) = &self.owner_email {
+            config.owner_email = owner_email.clone();
+        }
+        config.tags.extend(self.tags.iter().cloned());
+        if self.approval_tier.is_some() {
+            config.approval_tier = self.approval_tier.clone();
+        }
+        if let Some(ci) = self.ci {
+            config.ci = ci;
+        }
+        if self.drift
+            || self.drift_schedule.is_some()
+            || !self.drift_environments.is_empty()
+            || self.drift_notify.is_some()
+        {
+            let drift = config.drift.get_or_insert_with(DriftDetection::default);
+            if let Some(schedule) = &self.drift_schedule {
+                drift.schedule = schedule.clone();
+            }
+            if !self.drift_environments.is_empty() {
+                drift.environments = self.drift_environments.clone();
+            }
+            if let Some(notify) = self.drift_notify {
+                drift.notify = notify;
+            }
+        }
+        config.budgets.extend(self.budgets.iter().cloned());
+        if let Some(budget_policy) = self.budget_policy {
+            config.budget_policy = budget_policy;
+        }
+    }
+}
+
 /// Initializes a new Pulumi project by copying and modifying a template.
 ///
 /// # Arguments
 /// * `template_path` - Path to the custom template on disk (optional).
 /// * `output_dir` - The directory where the generated Pulumi files should be written.
 /// * `app_name` - The name of the project.
+/// * `overrides` - Command line choices applied on top of the project configuration.
 ///
 /// # Returns
 /// Result<(), Box<dyn std::error::Error>>
@@ -22,12 +216,43 @@ pub async fn new(
     name: Option<&str>,
     output_dir: Option<&str>,
     interactive: Option<bool>,
+    overrides: ProjectOverrides,
 ) -> Result<(), anyhow::Error> {
-    let config = match interactive {
-        Some(true) => tui_main()?,
+    let mut config = match interactive {
+        Some(true) => {
+            // The TUI has no tag fields, so check the tags only `--tag` can set up front
+            let tags: BTreeMap<String, String> = overrides.tags.iter().cloned().collect();
+            let subscriptions = SubscriptionRegistry::load()?.subscriptions().to_vec();
+            let missing = TagPolicy::load()?.missing_inputs(&tags, &subscriptions);
+            if !missing.is_empty() {
+                return Err(anyhow::anyhow!(
+                    "Required tags have no value: {}. Set them with --tag <name>=<value>",
+                    missing.join(", ")
+                ));
+            }
+            tui_main()?
+        }
         Some(false) => ProjectConfig::default(name.ok_or(anyhow::Error::msg("Name is required"))?),
         _ => ProjectConfig::default(name.ok_or(anyhow::Error::msg("Name is required"))?),
     };
+    overrides.apply(&mut config);
+
+    // Fail before writing anything if the stack settings are invalid, an environment
+    // is missing from the registry, its regions or subnets cannot be worked out or a
+    // required tag has no value, the approval tier or drift settings are invalid, or an
+    // environment is over budget under the enforce policy
+    validate_stack_settings(&config)?;
+    let registry = SubscriptionRegistry::load()?;
+    drift_stages(&config, &deploy_stages(&config, &registry)?)?;
+    let network_registry = NetworkRegistry::load()?;
+    let tag_policy = TagPolicy::load()?;
+    for env in &config.environments {
+        let azure_subscription = registry.get(env)?;
+        region_plan(&config, &azure_subscription)?;
+        plan_network(&config, &azure_subscription, &network_registry)?;
+        tag_policy.resolve(&config, &azure_subscription)?;
+    }
+    guard_budgets(&config, &registry, &PriceCatalog::load()?)?;
 
     // Default the output directory to a subdirectory named after the app
     let output_dir: String = match output_dir {
@@ -48,21 +273,41 @@ pub async fn new(
         create_pipeline_yaml(&config, Some(&output_dir_arc)),
         create_entry_point(&config, Some(&output_dir_arc)),
         create_config_files(&config, Some(&output_dir_arc)),
+        create_readme(&config, Some(&output_dir_arc)),
     )?;
 
+    if config.export_bicep {
+        create_bicep_files(&config, Some(&output_dir_arc)).await?;
+    }
+    if config.ci == CiTarget::AzurePipelines {
+        create_variable_groups(&config, Some(&output_dir_arc)).await?;
+    }
+
     println!("Pulumi project initialized successfully at {}.", output_dir);
     Ok(())
 }
 
 #[tokio::test]
 async fn test_new_with_default_output_dir() -> Result<(), anyhow::Error> {
+    use crate::helpers::subscription_registry::isolate_user_config;
+
+    isolate_user_config();
     // Arrange
     let app_name = "default_test_app";
     let current_dir = env::current_dir()?.to_string_lossy().to_string();
     let expected_output_dir = format!("{}/{}", current_dir, app_name);
 
+    let overrides = ProjectOverrides {
+        owner_email: Some("owner@example.com".to_string()),
+        tags: vec![
+            ("cost-center".to_string(), "1234".to_string()),
+            ("data-classification".to_string(), "internal".to_string()),
+        ],
+        ..Default::default()
+    };
+
     // Act
-    new(Some(app_name), None, Some(false)).await?;
+    new(Some(app_name), None, Some(false), overrides).await?;
 
     // Assert
     assert!(
@@ -0,0 +1,443 @@
+use anyhow::Ok;
+use std::{env, fs};
+
+use crate::helpers::config_helper::get_subscription_info;
+use crate::helpers::snippet_catalog::{resolve_in_dependency_order, SnippetDefinition};
+use crate::helpers::tag_policy::TagPolicy;
+use crate::tui::app::{ProjectConfig, SecretMode};
+
+/// Default embedded `main.bicep` template
+const BICEP_MAIN_TEMPLATE: &str = include_str!("../../snippets/bicep/main.bicep");
+
+/// Module parameters that `main.bicep` supplies itself instead of exposing them.
+const SHARED_MODULE_PARAMS: &[&str] = &["namePrefix", "location", "tags", "secrets"];
+
+/// Default of a generated secret: a fresh GUID, padded with upper-case letters and a
+/// symbol to meet password complexity rules.
+const GENERATED_SECRET_DEFAULT: &str = "'${newGuid()}${toUpper(uniqueString(newGuid()))}!'";
+
+/// A `param` declaration read from a Bicep module.
+#[derive(Debug, PartialEq)]
+struct BicepParam {
+    name: String,
+    bicep_type: String,
+    description: Option<String>,
+    secure: bool,
+}
+
+/// Exports the project as Bicep: `main.bicep`, the modules it references and one
+/// `main.<env>.bicepparam` file per selected environment, written to `<output_dir>/bicep`.
+///
+/// # Arguments
+/// - `config`: The project configuration the Pulumi program was generated from.
+/// - `output_dir`: An optional project directory. If not provided, it defaults to a subdirectory named after the app.
+///
+/// # Returns
+/// - `Result<(), anyhow::Error>`: Returns a result indicating success or failure.
+pub async fn create_bicep_files(
+    config: &ProjectConfig,
+    output_dir: Option<&str>,
+) -> Result<(), anyhow::Error> {
+    println!("Exporting Bicep for {}", &config.resource_name);
+
+    // Default the output directory to a subdirectory named after the app if not provided
+    let output_dir: String = match output_dir {
+        Some(dir) => dir.to_string(),
+        None => {
+            let current_dir = env::current_dir()?.to_string_lossy().to_string();
+            format!("{}/{}", current_dir, &config.resource_name)
+        }
+    };
+    let bicep_dir = format!("{}/bicep", output_dir);
+    fs::create_dir_all(format!("{}/modules", bicep_dir))?;
+
+    let snippets = resolve_in_dependency_order(&config.additional_resources, config.secret_mode)?;
+    for def in &snippets {
+        fs::write(
+            format!("{}/modules/{}.bicep", bicep_dir, def.module_name),
+            def.bicep_template,
+        )?;
+    }
+
+    fs::write(
+        format!("{}/main.bicep", bicep_dir),
+        render_main(config, &snippets),
+    )?;
+
+    for env in &config.environments {
+        let param_path = format!("{}/main.{}.bicepparam", bicep_dir, env);
+        fs::write(&param_path, render_param_file(config, env, &snippets)?)
+            .map_err(|err| anyhow::anyhow!("Failed to write to {}: {}", param_path, err))?;
+    }
+
+    Ok(())
+}
+
+/// Renders `main.bicep`, exposing every module parameter as a top-level parameter
+/// named after the Pulumi config key (e.g. `cacheSkuName`).
+fn render_main(config: &ProjectConfig, snippets: &[&SnippetDefinition]) -> String {
+    let generated = generated_secrets(config, snippets);
+    let mut params = String::new();
+    let mut modules = String::new();
+    let mut outputs = String::new();
+
+    for def in snippets {
+        let instance = def.instance_name();
+        modules.push_str(&format!(
+            "\nmodule {} 'modules/{}.bicep' = {{\n  name: '${{namePrefix}}-{}'\n  params: {{\n    namePrefix: namePrefix\n    location: location\n    tags: tags\n",
+            instance, def.module_name, def.module_name
+        ));
+
+        for param in module_params(def.bicep_template) {
+            let main_param = def.config_key(&param.name);
+            let parameter = def.parameters.iter().find(|p| p.name == param.name);
+
+            params.push('\n');
+            if param.secure {
+                params.push_str("@secure()\n");
+            }
+            let is_generated = generated.iter().any(|(name, _)| name == &main_param);
+            match (&param.description, is_generated) {
+                (Some(description), true) => params.push_str(&format!(
+                    "@description('{} Generated on every deployment unless supplied, and stored in Key Vault.')\n",
+                    description
+                )),
+                (Some(description), false) => {
+                    params.push_str(&format!("@description('{}')\n", description))
+                }
+                (None, _) => {}
+            }
+            match parameter {
+                _ if is_generated => params.push_str(&format!(
+                    "param {} {} = {}\n",
+                    main_param, param.bicep_type, GENERATED_SECRET_DEFAULT
+                )),
+                Some(parameter) if !param.secure => params.push_str(&format!(
+                    "param {} {} = {}\n",
+                    main_param,
+                    param.bicep_type,
+                    bicep_literal(&param.bicep_type, parameter.default)
+                )),
+                _ => params.push_str(&format!("param {} {}\n", main_param, param.bicep_type)),
+            }
+
+            modules.push_str(&format!("    {}: {}\n", param.name, main_param));
+        }
+        if def.key == "keyvault" && !generated.is_empty() {
+            modules.push_str("    secrets: {\n");
+            for (main_param, secret_name) in &generated {
+                modules.push_str(&format!("      '{}': {}\n", secret_name, main_param));
+            }
+            modules.push_str("    }\n");
+        }
+        modules.push_str("  }\n}\n");
+
+        for (name, bicep_type) in module_outputs(def.bicep_template) {
+            outputs.push_str(&format!(
+                "output {} {} = {}.outputs.{}\n",
+                name, bicep_type, instance, name
+            ));
+        }
+    }
+
+    BICEP_MAIN_TEMPLATE
+        .replace("{{project_name}}", &config.resource_name)
+        .replace("{{params}}", &params)
+        .replace("{{modules}}", &modules)
+        .replace("{{outputs}}", &outputs)
+}
+
+/// Secrets that `main.bicep` generates and stores in Key Vault in `SecretMode::KeyVault`,
+/// as (main parameter, Key Vault secret name) pairs.
+fn generated_secrets(
+    config: &ProjectConfig,
+    snippets: &[&SnippetDefinition],
+) -> Vec<(String, String)> {
+    if config.secret_mode != SecretMode::KeyVault {
+        return Vec::new();
+    }
+    snippets
+        .iter()
+        .flat_map(|def| {
+            def.secrets.iter().map(|secret| {
+                (
+                    def.config_key(secret.name),
+                    def.key_vault_secret_name(secret.name),
+                )
+            })
+        })
+        .collect()
+}
+
+/// Renders the parameter file for one environment. Secure parameters are read from
+/// environment variables at deployment time rather than written to disk, unless
+/// `main.bicep` generates them.
+fn render_param_file(
+    config: &ProjectConfig,
+    env: &str,
+    snippets: &[&SnippetDefinition],
+) -> Result<String, anyhow::Error> {
+    let subscription = get_subscription_info(env)?;
+    let mut tags = subscription.tags.clone();
+    tags.extend(TagPolicy::load()?.resolve(config, &subscription)?);
+
+    let mut content = format!(
+        "// Subscription: {} ({}), tier: {}\n\
+         // Deploy with:\n\
+         //   az deployment group create --subscription {} --resource-group <resource-group> --parameters main.{}.bicepparam\n\
+         using './main.bicep'\n\n\
+         param environment = '{}'\n\
+         param location = '{}'\n",
+        subscription.name,
+        subscription.id,
+        subscription.tier,
+        subscription.id,
+        env,
+        subscription.env,
+        subscription.location
+    );
+    content.push_str("param tags = {\n");
+    for (name, value) in &tags {
+        content.push_str(&format!(
+            "  {}: {}\n",
+            bicep_literal("string", name),
+            bicep_literal("string", value)
+        ));
+    }
+    content.push_str("}\n");
+
+    let generated = generated_secrets(config, snippets);
+    for def in snippets {
+        for param in module_params(def.bicep_template) {
+            let main_param = def.config_key(&param.name);
+            let default = def.parameters.iter().find(|p| p.name == param.name);
+            match default {
+                _ if generated.iter().any(|(name, _)| name == &main_param) => {
+                    content.push_str(&format!(
+                        "// {} is generated by main.bicep and stored in Key Vault\n",
+                        main_param
+                    ))
+                }
+                Some(parameter) if !param.secure => content.push_str(&format!(
+                    "param {} = {}\n",
+                    main_param,
+                    bicep_literal(&param.bicep_type, parameter.default)
+                )),
+                _ => content.push_str(&format!(
+                    "param {} = readEnvironmentVariable('{}')\n",
+                    main_param,
+                    env_var_name(&main_param)
+                )),
+            }
+        }
+    }
+
+    Ok(content)
+}
+
+/// Reads the `param` declarations of a Bicep module, skipping the ones shared by every module.
+fn module_params(template: &str) -> Vec<BicepParam> {
+    let mut params = Vec::new();
+    let mut secure = false;
+    let mut description = None;
+
+    for line in template.lines().map(str::trim) {
+        if line == "@secure()" {
+            secure = true;
+            continue;
+        }
+        if let Some(text) = line
+            .strip_prefix("@description('")
+            .and_then(|rest| rest.strip_suffix("')"))
+        {
+            description = Some(text.to_string());
+            continue;
+        }
+        if let Some(rest) = line.strip_prefix("param ") {
+            let mut parts = rest.split_whitespace();
+            if let (Some(name), Some(bicep_type)) = (parts.next(), parts.next()) {
+                if !SHARED_MODULE_PARAMS.contains(&name) {
+                    params.push(BicepParam {
+                        name: name.to_string(),
+                        bicep_type: bicep_type.to_string(),
+                        description: description.clone(),
+                        secure,
+                    });
+                }
+            }
+        }
+        if !line.starts_with('@') {
+            secure = false;
+            description = None;
+        }
+    }
+
+    params
+}
+
+/// Reads the `output` declarations of a Bicep module as (name, type) pairs.
+fn module_outputs(template: &str) -> Vec<(String, String)> {
+    template
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("output "))
+        .filter_map(|rest| {
+            let mut parts = rest.split_whitespace();
+            Some((parts.next()?.to_string(), parts.next()?.to_string()))
+        })
+        .collect()
+}
+
+/// Environment variable a secure parameter is read from, e.g. `DATABASE_ADMINISTRATOR_LOGIN_PASSWORD`.
+fn env_var_name(param: &str) -> String {
+    let mut name = String::new();
+    for c in param.chars() {
+        if c.is_uppercase() && !name.is_empty() {
+            name.push('_');
+        }
+        name.push(c.to_ascii_uppercase());
+    }
+    name
+}
+
+/// Renders a raw default value as a Bicep literal of the given type.
+fn bicep_literal(bicep_type: &str, value: &str) -> String {
+    match bicep_type {
+        "string" => format!("'{}'", value.replace('\'', "\\'")),
+        _ => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::snippet_catalog::{find_snippet, SNIPPET_CATALOG};
+    use crate::helpers::subscription_registry::isolate_user_config;
+    use std::collections::BTreeMap;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_every_parameter_has_a_bicep_param() {
+        for def in SNIPPET_CATALOG {
+            let params = module_params(def.bicep_template);
+            for parameter in def.parameters {
+                assert!(
+                    params.iter().any(|p| p.name == parameter.name),
+                    "{}.bicep is missing param {}",
+                    def.module_name,
+                    parameter.name
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_module_params_marks_secure_params() {
+        let database = find_snippet("database").unwrap();
+        let params = module_params(database.bicep_template);
+
+        let password = params
+            .iter()
+            .find(|p| p.name == "administratorLoginPassword")
+            .unwrap();
+        assert!(password.secure);
+        assert!(!params.iter().any(|p| p.name == "namePrefix"));
+        assert!(params
+            .iter()
+            .any(|p| p.name == "storageSizeGb" && p.bicep_type == "int" && !p.secure));
+    }
+
+    #[tokio::test]
+    async fn test_create_bicep_files() -> Result<(), anyhow::Error> {
+        isolate_user_config();
+        // Arrange
+        let config = ProjectConfig {
+            resource_name: "myapp".to_string(),
+            environments: vec!["dev".to_string(), "prod".to_string()],
+            additional_resources: vec!["database".to_string(), "cache".to_string()],
+            owner_email: "owner@example.com".to_string(),
+            tags: BTreeMap::from([
+                ("cost-center".to_string(), "1234".to_string()),
+                ("data-classification".to_string(), "internal".to_string()),
+            ]),
+            ..Default::default()
+        };
+        let temp_dir = tempdir()?;
+        let output_dir = temp_dir.path().to_str().unwrap();
+
+        // Act
+        create_bicep_files(&config, Some(output_dir)).await?;
+
+        // Assert
+        let main = fs::read_to_string(format!("{}/bicep/main.bicep", output_dir))?;
+        assert!(main.contains("param cacheCapacity int = 1"));
+        assert!(main.contains(
+            "@secure()\n@description('Administrator password for the server.')\nparam databaseAdministratorLoginPassword string\n"
+        ));
+        assert!(main.contains("module cache 'modules/cache.bicep' = {"));
+        assert!(main.contains("    skuName: cacheSkuName\n"));
+        assert!(main.contains("    tags: tags\n"));
+        assert!(main.contains("output redisHostName string = cache.outputs.redisHostName"));
+        assert!(
+            !main.contains("{{"),
+            "main.bicep has unresolved placeholders"
+        );
+        assert!(fs::metadata(format!("{}/bicep/modules/database.bicep", output_dir)).is_ok());
+
+        let prod = fs::read_to_string(format!("{}/bicep/main.prod.bicepparam", output_dir))?;
+        assert!(prod.contains("// Subscription: prod01 (1234-5678-91011), tier: platinum"));
+        assert!(prod.contains("param environment = 'prod'"));
+        assert!(prod.contains("  'cost-center': '1234'\n  'data-classification': 'internal'\n"));
+        assert!(prod.contains("param databaseSkuName = 'Standard_D2s_v3'"));
+        assert!(prod.contains(
+            "param databaseAdministratorLoginPassword = readEnvironmentVariable('DATABASE_ADMINISTRATOR_LOGIN_PASSWORD')"
+        ));
+        assert!(fs::metadata(format!("{}/bicep/main.test.bicepparam", output_dir)).is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_create_bicep_files_with_key_vault_secrets() -> Result<(), anyhow::Error> {
+        isolate_user_config();
+        // Arrange
+        let config = ProjectConfig {
+            resource_name: "myapp".to_string(),
+            environments: vec!["dev".to_string()],
+            additional_resources: vec!["database".to_string()],
+            owner_email: "owner@example.com".to_string(),
+            tags: BTreeMap::from([
+                ("cost-center".to_string(), "1234".to_string()),
+                ("data-classification".to_string(), "internal".to_string()),
+            ]),
+            secret_mode: SecretMode::KeyVault,
+            ..Default::default()
+        };
+        let temp_dir = tempdir()?;
+        let output_dir = temp_dir.path().to_str().unwrap();
+
+        // Act
+        create_bicep_files(&config, Some(output_dir)).await?;
+
+        // Assert
+        let main = fs::read_to_string(format!("{}/bicep/main.bicep", output_dir))?;
+        assert!(main.contains(&format!(
+            "param databaseAdministratorLoginPassword string = {}\n",
+            GENERATED_SECRET_DEFAULT
+        )));
+        assert!(main.contains("module keyvault 'modules/keyvault.bicep' = {"));
+        assert!(main.contains(
+            "    secrets: {\n      'database-administrator-login-password': databaseAdministratorLoginPassword\n    }\n"
+        ));
+        assert!(!main.contains("keyvaultSecrets"));
+        let key_vault = fs::read_to_string(format!("{}/bicep/modules/keyvault.bicep", output_dir))?;
+        assert!(key_vault.contains("'Microsoft.KeyVault/vaults/secrets@2023-07-01'"));
+
+        let dev = fs::read_to_string(format!("{}/bicep/main.dev.bicepparam", output_dir))?;
+        assert!(!dev.contains("readEnvironmentVariable"));
+        assert!(dev.contains(
+            "// databaseAdministratorLoginPassword is generated by main.bicep and stored in Key Vault\n"
+        ));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,341 @@
+use anyhow::{anyhow, Error};
+use colored::*;
+use std::collections::BTreeMap;
+
+use crate::helpers::cost_helper::{
+    format_amount, usage_from_config, CostEstimate, EnvironmentCost, ResourceCost, CURRENCY,
+};
+use crate::helpers::price_catalog::PriceCatalog;
+use crate::helpers::subscription_registry::{SubscriptionRegistry, TIERS};
+use crate::tui::app::{BudgetPolicy, ProjectConfig};
+
+/// The estimated monthly cost of an environment against its budget.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BudgetCheck {
+    pub env: String,
+    pub budget: f64,
+    /// Cost of the priced meters; a lower bound while `unpriced` is not empty.
+    pub estimate: f64,
+    /// The most expensive resources whose cost covers the overage, most expensive first.
+    pub drivers: Vec<ResourceCost>,
+    /// Meters without a price, as `<resource>: <meter>`.
+    pub unpriced: Vec<String>,
+}
+
+impl BudgetCheck {
+    fn new(environment: &EnvironmentCost, budget: f64) -> Self {
+        let estimate = environment.total();
+        let mut resources = environment.resources.clone();
+        resources.sort_by(|a, b| b.monthly.total_cmp(&a.monthly));
+
+        let mut drivers = Vec::new();
+        let mut covered = 0.0;
+        for resource in resources {
+            if covered >= estimate - budget {
+                break;
+            }
+            covered += resource.monthly;
+            drivers.push(resource);
+        }
+        Self {
+            env: environment.env.clone(),
+            budget,
+            estimate,
+            drivers,
+            unpriced: environment.unpriced(),
+        }
+    }
+
+    pub fn overage(&self) -> f64 {
+        self.estimate - self.budget
+    }
+
+    /// Whether the priced meters alone already exceed the budget.
+    pub fn is_over(&self) -> bool {
+        self.overage() > 0.0
+    }
+
+    /// Whether meters without a price leave the estimate, and so the check, unknown.
+    pub fn is_incomplete(&self) -> bool {
+        !self.unpriced.is_empty()
+    }
+
+    /// One line for the environment, followed by the resources that drive an overage.
+    pub fn render(&self) -> String {
+        if !self.is_over() && self.is_incomplete() {
+            return format!(
+                "{} {}: estimate incomplete, at least {} {} a month against its budget of {}\n",
+                "?".yellow(),
+                self.env,
+                format_amount(self.estimate),
+                CURRENCY,
+                format_amount(self.budget)
+            );
+        }
+        if !self.is_over() {
+            return format!(
+                "{} {}: estimated {} {} a month, within its budget of {}\n",
+                "✔".green(),
+                self.env,
+                format_amount(self.estimate),
+                CURRENCY,
+                format_amount(self.budget)
+            );
+        }
+        let mut text = format!(
+            "{} {}: estimated {} {} a month, {} over its budget of {}\n",
+            "✘".red(),
+            self.env,
+            format_amount(self.estimate),
+            CURRENCY,
+            format_amount(self.overage()),
+            format_amount(self.budget)
+        );
+        for resource in &self.drivers {
+            let line = format!(
+                "{} ({}, {}): {} ({:.0}%)",
+                resource.resource,
+                resource.region,
+                resource.sku,
+                format_amount(resource.monthly),
+                resource.monthly / self.estimate * 100.0
+            );
+            text.push_str(&format!("    {}\n", line.yellow()));
+        }
+        text
+    }
+}
+
+/// Monthly budget of an environment: the project's budget for the environment or its
+/// tier, or else the registry's budget for the environment or its tier.
+fn environment_budget(
+    budgets: &BTreeMap<String, f64>,
+    registry: &SubscriptionRegistry,
+    env: &str,
+) -> Option<f64> {
+    let azure_subscription = registry.get(env).ok();
+    budgets
+        .get(env)
+        .or_else(|| {
+            azure_subscription
+                .as_ref()
+                .and_then(|subscription| budgets.get(&subscription.tier))
+        })
+        .copied()
+        .or_else(|| azure_subscription.and_then(|subscription| subscription.budget))
+}
+
+/// Compares every environment of an estimate that has a budget with that budget.
+///
+/// # Arguments
+/// - `budgets`: Project budgets by environment or tier.
+/// - `registry`: The subscription registry, for the tiers and registry budgets.
+/// - `estimate`: The estimated cost of the environments.
+///
+/// # Returns
+/// - An error if a project budget names neither a registry environment nor a tier.
+pub fn check_budgets(
+    budgets: &BTreeMap<String, f64>,
+    registry: &SubscriptionRegistry,
+    estimate: &CostEstimate,
+) -> Result<Vec<BudgetCheck>, Error> {
+    let environments = registry.environment_names();
+    if let Some(key) = budgets
+        .keys()
+        .find(|key| !environments.contains(key) && !TIERS.contains(&key.as_str()))
+    {
+        return Err(anyhow!(
+            "Budget '{}' is neither an environment nor a tier ({})",
+            key,
+            TIERS.join(", ")
+        ));
+    }
+
+    Ok(estimate
+        .environments
+        .iter()
+        .filter_map(|environment| {
+            environment_budget(budgets, registry, &environment.env)
+                .map(|budget| BudgetCheck::new(environment, budget))
+        })
+        .collect())
+}
+
+/// Estimates the selected environments from a price catalog and prints the
+/// ones that are over budget, with the resources that drive the overage, and the ones
+/// whose estimate is incomplete, with the meters without a price.
+///
+/// # Returns
+/// - An error if an environment is over budget or its estimate is incomplete and the
+///   policy is `BudgetPolicy::Enforce`.
+pub fn guard_budgets(
+    config: &ProjectConfig,
+    registry: &SubscriptionRegistry,
+    catalog: &PriceCatalog,
+) -> Result<(), Error> {
+    let usage = usage_from_config(config, registry)?;
+    let estimate = CostEstimate::new(&usage, catalog);
+    let flagged: Vec<BudgetCheck> = check_budgets(&config.budgets, registry, &estimate)?
+        .into_iter()
+        .filter(|check| check.is_over() || check.is_incomplete())
+        .collect();
+    if flagged.is_empty() {
+        return Ok(());
+    }
+
+    for check in &flagged {
+        eprint!("{}", check.render());
+        for meter in &check.unpriced {
+            let line = format!("No price found for {}", meter);
+            eprintln!("    {}", line.yellow());
+        }
+    }
+    let environments = |over: bool| -> Vec<&str> {
+        flagged
+            .iter()
+            .filter(|check| check.is_over() == over)
+            .map(|check| check.env.as_str())
+            .collect()
+    };
+    let (over, unknown) = (environments(true), environments(false));
+    match config.budget_policy {
+        BudgetPolicy::Warn => Ok(()),
+        BudgetPolicy::Enforce if !over.is_empty() => Err(anyhow!(
+            "Estimated monthly cost is over budget in {}. Choose smaller SKUs or fewer resources, or raise the budget",
+            over.join(", ")
+        )),
+        BudgetPolicy::Enforce => Err(anyhow!(
+            "Estimated monthly cost is unknown in {}: the price catalog has no price for some meters. Import their prices with `pulumimi cost import`, or use --budget-policy warn",
+            unknown.join(", ")
+        )),
+    }
+}
+
+/// Parses a `--budget` argument such as `prod=2000` or `bronze=300`.
+pub fn parse_budget(value: &str) -> Result<(String, f64), String> {
+    let invalid = || format!("'{}' is not of the form <env or tier>=<amount>", value);
+    let (key, amount) = value.split_once('=').ok_or_else(invalid)?;
+    match amount.trim().parse::<f64>() {
+        Ok(amount) if !key.trim().is_empty() && amount.is_finite() && amount >= 0.0 => {
+            Ok((key.trim().to_string(), amount))
+        }
+        _ => Err(invalid()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::subscription_registry::ConfigRoots;
+
+    fn resource(name: &str, monthly: f64) -> ResourceCost {
+        ResourceCost {
+            resource: name.to_string(),
+            region: "eastus2".to_string(),
+            sku: "Standard".to_string(),
+            monthly,
+            unpriced: Vec::new(),
+        }
+    }
+
+    const WESTEUROPE_DEV: &str = r#"
+subscriptions:
+  - env: dev
+    location: westeurope
+"#;
+
+    #[test]
+    fn test_check_budgets() {
+        let registry = SubscriptionRegistry::from_layers(&[(
+            "org".to_string(),
+            include_str!("../../config/subscriptions.yaml").to_string(),
+        )])
+        .unwrap();
+        let estimate = CostEstimate {
+            environments: ["dev", "test", "prod"]
+                .map(|env| EnvironmentCost {
+                    env: env.to_string(),
+                    resources: vec![
+                        resource("storage", 10.0),
+                        resource("database", 130.0),
+                        resource("cache", 100.0),
+                    ],
+                })
+                .to_vec(),
+        };
+        let budgets = BTreeMap::from([
+            ("dev".to_string(), 200.0),
+            ("bronze".to_string(), 50.0),
+            ("silver".to_string(), 1000.0),
+        ]);
+
+        let checks = check_budgets(&budgets, &registry, &estimate).unwrap();
+
+        // dev's own budget wins over its tier's; prod has no budget
+        assert_eq!(checks.len(), 2);
+        assert_eq!(checks[0].env, "dev");
+        assert_eq!(checks[0].overage(), 40.0);
+        assert_eq!(checks[0].drivers, [resource("database", 130.0)]);
+        assert!(!checks[1].is_over());
+        assert!(checks[1].drivers.is_empty());
+
+        let tight = BTreeMap::from([("dev".to_string(), 20.0)]);
+        let checks = check_budgets(&tight, &registry, &estimate).unwrap();
+        assert_eq!(
+            checks[0]
+                .drivers
+                .iter()
+                .map(|driver| driver.resource.as_str())
+                .collect::<Vec<_>>(),
+            ["database", "cache"]
+        );
+
+        let unknown = BTreeMap::from([("sandbox".to_string(), 5.0)]);
+        assert!(check_budgets(&unknown, &registry, &estimate).is_err());
+        assert_eq!(parse_budget("prod=2000"), Ok(("prod".to_string(), 2000.0)));
+        assert!(parse_budget("prod=-1").is_err());
+        assert!(parse_budget("prod").is_err());
+    }
+
+    #[test]
+    fn test_budgets_in_region_without_prices() {
+        let registry = SubscriptionRegistry::from_layers(&[
+            (
+                "org".to_string(),
+                include_str!("../../config/subscriptions.yaml").to_string(),
+            ),
+            ("project".to_string(), WESTEUROPE_DEV.to_string()),
+        ])
+        .unwrap();
+        let mut config = ProjectConfig {
+            resource_name: "b".to_string(),
+            environments: vec!["dev".to_string()],
+            additional_resources: vec!["cache".to_string()],
+            budgets: BTreeMap::from([("dev".to_string(), 10.0)]),
+            budget_policy: BudgetPolicy::Enforce,
+            ..Default::default()
+        };
+
+        // The bundled catalog has no westeurope prices, so the check is unknown, not within budget
+        let usage = usage_from_config(&config, &registry).unwrap();
+        let root = tempfile::tempdir().unwrap();
+        let catalog = PriceCatalog::load_from(&ConfigRoots {
+            user_dir: None,
+            project_root: root.path().to_path_buf(),
+        })
+        .unwrap();
+        let estimate = CostEstimate::new(&usage, &catalog);
+        let checks = check_budgets(&config.budgets, &registry, &estimate).unwrap();
+        assert!(!checks[0].is_over());
+        assert!(checks[0].is_incomplete());
+        assert!(checks[0]
+            .unpriced
+            .contains(&"cache: C1 Cache Instance".to_string()));
+        assert!(checks[0].render().contains("estimate incomplete"));
+
+        let err = guard_budgets(&config, &registry, &catalog).unwrap_err();
+        assert!(err.to_string().contains("unknown in dev"));
+        config.budget_policy = BudgetPolicy::Warn;
+        assert!(guard_budgets(&config, &registry, &catalog).is_ok());
+    }
+}
@@ -0,0 +1,373 @@
+use anyhow::{anyhow, Error};
+use colored::*;
+use serde_yaml::Value;
+
+use crate::helpers::config_helper::StackFile;
+use crate::helpers::snippet_catalog::SNIPPET_CATALOG;
+
+/// Output format of `pulumimi config diff`.
+#[derive(Debug, Clone, Copy, PartialEq, Default, clap::ValueEnum)]
+pub enum DiffFormat {
+    /// Table for the terminal, with differing and missing keys highlighted.
+    #[default]
+    Table,
+    /// Markdown table, e.g. for a pull request description.
+    Markdown,
+    Json,
+}
+
+/// How the values of one key compare across environments.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DiffStatus {
+    Same,
+    Differs,
+    /// Set in some environments only.
+    Missing,
+    /// A secret in at least one environment; values are never shown or compared.
+    Secret,
+}
+
+impl DiffStatus {
+    fn label(&self) -> &'static str {
+        match self {
+            DiffStatus::Same => "same",
+            DiffStatus::Differs => "differs",
+            DiffStatus::Missing => "missing",
+            DiffStatus::Secret => "secret",
+        }
+    }
+}
+
+/// Value of a key in one environment.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DiffCell {
+    Missing,
+    Secret,
+    Value(Value),
+}
+
+impl DiffCell {
+    fn text(&self) -> String {
+        match self {
+            DiffCell::Missing => "-".to_string(),
+            DiffCell::Secret => "[secret]".to_string(),
+            DiffCell::Value(Value::String(text)) => text.clone(),
+            DiffCell::Value(value) => serde_json::to_string(value).unwrap_or_default(),
+        }
+    }
+
+    fn json(&self) -> serde_json::Value {
+        match self {
+            DiffCell::Missing => serde_json::Value::Null,
+            DiffCell::Secret => serde_json::Value::from("[secret]"),
+            DiffCell::Value(value) => serde_json::to_value(value).unwrap_or_default(),
+        }
+    }
+}
+
+/// One key of the matrix.
+#[derive(Debug)]
+pub struct DiffRow {
+    pub key: String,
+    pub status: DiffStatus,
+    /// One cell per environment, in the order of `ConfigDiff::environments`.
+    pub cells: Vec<DiffCell>,
+}
+
+/// Per-key matrix of stack config values across environments.
+#[derive(Debug)]
+pub struct ConfigDiff {
+    pub environments: Vec<String>,
+    pub rows: Vec<DiffRow>,
+}
+
+impl ConfigDiff {
+    /// Compares stack files. Keys are listed in the order they first appear, and the
+    /// values of secure keys and snippet secrets are masked.
+    pub fn new(stack_files: &[&StackFile]) -> Result<Self, Error> {
+        if stack_files.len() < 2 {
+            return Err(anyhow!(
+                "Need at least two stack files to compare, found {}",
+                stack_files.len()
+            ));
+        }
+
+        let secret_keys: Vec<String> = SNIPPET_CATALOG
+            .iter()
+            .flat_map(|def| def.secrets.iter().map(|secret| def.config_key(secret.name)))
+            .collect();
+        let is_secret = |key: &str, value: &Value| {
+            matches!(value, Value::Mapping(mapping) if mapping.contains_key(&Value::from("secure")))
+                || key
+                    .rsplit_once(':')
+                    .is_some_and(|(_, name)| secret_keys.iter().any(|secret| secret == name))
+        };
+
+        let mut keys: Vec<String> = Vec::new();
+        for stack_file in stack_files {
+            for key in stack_file
+                .settings
+                .config
+                .iter()
+                .filter_map(|(key, _)| key.as_str())
+            {
+                if !keys.iter().any(|known| known == key) {
+                    keys.push(key.to_string());
+                }
+            }
+        }
+
+        let rows = keys
+            .into_iter()
+            .map(|key| {
+                let cells: Vec<DiffCell> = stack_files
+                    .iter()
+                    .map(|stack_file| {
+                        match stack_file.settings.config.get(&Value::from(key.as_str())) {
+                            None => DiffCell::Missing,
+                            Some(value) if is_secret(&key, value) => DiffCell::Secret,
+                            Some(value) => DiffCell::Value(value.clone()),
+                        }
+                    })
+                    .collect();
+                let status = if cells.contains(&DiffCell::Missing) {
+                    DiffStatus::Missing
+                } else if cells.contains(&DiffCell::Secret) {
+                    DiffStatus::Secret
+                } else if cells.windows(2).all(|pair| pair[0] == pair[1]) {
+                    DiffStatus::Same
+                } else {
+                    DiffStatus::Differs
+                };
+                DiffRow { key, status, cells }
+            })
+            .collect();
+
+        Ok(Self {
+            environments: stack_files.iter().map(|f| environment_label(f)).collect(),
+            rows,
+        })
+    }
+
+    pub fn render(&self, format: DiffFormat) -> String {
+        match format {
+            DiffFormat::Table => self.render_table(),
+            DiffFormat::Markdown => self.render_markdown(),
+            DiffFormat::Json => self.render_json(),
+        }
+    }
+
+    fn header(&self) -> Vec<String> {
+        let mut header = vec!["Key".to_string()];
+        header.extend(self.environments.iter().cloned());
+        header.push("Status".to_string());
+        header
+    }
+
+    fn row_texts(row: &DiffRow) -> Vec<String> {
+        let mut texts = vec![row.key.clone()];
+        texts.extend(row.cells.iter().map(DiffCell::text));
+        texts.push(row.status.label().to_string());
+        texts
+    }
+
+    fn render_table(&self) -> String {
+        let header = self.header();
+        let mut widths: Vec<usize> = header.iter().map(|text| text.chars().count()).collect();
+        for row in &self.rows {
+            for (width, text) in widths.iter_mut().zip(Self::row_texts(row)) {
+                *width = (*width).max(text.chars().count());
+            }
+        }
+
+        let border = format!(
+            "+{}+\n",
+            widths
+                .iter()
+                .map(|width| "-".repeat(width + 2))
+                .collect::<Vec<_>>()
+                .join("+")
+        );
+        let line = |texts: &[String]| {
+            let cells: Vec<String> = texts
+                .iter()
+                .zip(&widths)
+                .map(|(text, width)| format!(" {:<width$} ", text, width = width))
+                .collect();
+            format!("|{}|", cells.join("|"))
+        };
+
+        let mut table = border.clone();
+        table.push_str(&format!("{}\n", line(&header).bold()));
+        table.push_str(&border);
+        for row in &self.rows {
+            let text = line(&Self::row_texts(row));
+            let styled = match row.status {
+                DiffStatus::Same => text.normal(),
+                DiffStatus::Differs => text.yellow(),
+                DiffStatus::Missing => text.red(),
+                DiffStatus::Secret => text.cyan(),
+            };
+            table.push_str(&format!("{}\n", styled));
+        }
+        table.push_str(&border);
+        table
+    }
+
+    fn render_markdown(&self) -> String {
+        let header = self.header();
+        let mut markdown = format!(
+            "| {} |\n|{}\n",
+            header.join(" | "),
+            " --- |".repeat(header.len())
+        );
+        for row in &self.rows {
+            let cells: Vec<String> = row
+                .cells
+                .iter()
+                .map(|cell| match cell {
+                    DiffCell::Missing => "_missing_".to_string(),
+                    DiffCell::Secret => "_secret_".to_string(),
+                    DiffCell::Value(Value::String(text)) if text.is_empty() => {
+                        "_empty_".to_string()
+                    }
+                    DiffCell::Value(_) => format!("`{}`", cell.text().replace('|', "\\|")),
+                })
+                .collect();
+            let status = match row.status {
+                DiffStatus::Differs | DiffStatus::Missing => format!("**{}**", row.status.label()),
+                _ => row.status.label().to_string(),
+            };
+            markdown.push_str(&format!(
+                "| `{}` | {} | {} |\n",
+                row.key,
+                cells.join(" | "),
+                status
+            ));
+        }
+        markdown
+    }
+
+    fn render_json(&self) -> String {
+        let keys: Vec<serde_json::Value> = self
+            .rows
+            .iter()
+            .map(|row| {
+                serde_json::json!({
+                    "key": row.key,
+                    "status": row.status.label(),
+                    "values": row.cells.iter().map(DiffCell::json).collect::<Vec<_>>(),
+                })
+            })
+            .collect();
+        serde_json::to_string_pretty(&serde_json::json!({
+            "environments": self.environments,
+            "keys": keys,
+        }))
+        .unwrap_or_default()
+    }
+}
+
+/// Environment of a stack file: its `<project>:env` value, or else the stack name
+/// from the file name.
+pub fn environment_label(stack_file: &StackFile) -> String {
+    stack_file
+        .settings
+        .config
+        .iter()
+        .find(|(key, _)| key.as_str().is_some_and(|key| key.ends_with(":env")))
+        .and_then(|(_, value)| value.as_str())
+        .map(str::to_string)
+        .unwrap_or_else(|| {
+            stack_file
+                .file_name
+                .trim_start_matches("Pulumi.")
+                .trim_end_matches(".yaml")
+                .to_string()
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stack_file(file_name: &str, yaml: &str) -> StackFile {
+        StackFile {
+            file_name: file_name.to_string(),
+            settings: serde_yaml::from_str(yaml).unwrap(),
+        }
+    }
+
+    fn diff() -> ConfigDiff {
+        let dev = stack_file(
+            "Pulumi.dev.yaml",
+            "config:\n  myapp:env: dev\n  myapp:location: eastus2\n  myapp:cacheCapacity: 1\n  myapp:databaseAdministratorLoginPassword:\n    secure: v1:abc\n",
+        );
+        let prod = stack_file(
+            "Pulumi.prod.yaml",
+            "config:\n  myapp:env: prod\n  myapp:location: eastus2\n  myapp:cacheCapacity: 3\n  myapp:databaseAdministratorLoginPassword: hunter2\n  myapp:tags:\n    costCenter: '42'\n",
+        );
+        ConfigDiff::new(&[&dev, &prod]).unwrap()
+    }
+
+    #[test]
+    fn test_diff_statuses() {
+        let diff = diff();
+
+        assert_eq!(diff.environments, vec!["dev", "prod"]);
+        let statuses: Vec<(&str, DiffStatus)> = diff
+            .rows
+            .iter()
+            .map(|row| (row.key.as_str(), row.status))
+            .collect();
+        assert_eq!(
+            statuses,
+            vec![
+                ("myapp:env", DiffStatus::Differs),
+                ("myapp:location", DiffStatus::Same),
+                ("myapp:cacheCapacity", DiffStatus::Differs),
+                (
+                    "myapp:databaseAdministratorLoginPassword",
+                    DiffStatus::Secret
+                ),
+                ("myapp:tags", DiffStatus::Missing),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_render_never_reveals_secrets() {
+        let diff = diff();
+
+        for format in [DiffFormat::Table, DiffFormat::Markdown, DiffFormat::Json] {
+            let rendered = diff.render(format);
+            assert!(
+                !rendered.contains("hunter2"),
+                "{:?} reveals a secret",
+                format
+            );
+            assert!(
+                !rendered.contains("v1:abc"),
+                "{:?} reveals a secret",
+                format
+            );
+        }
+
+        let markdown = diff.render(DiffFormat::Markdown);
+        assert!(markdown.starts_with("| Key | dev | prod | Status |\n| --- | --- | --- | --- |\n"));
+        assert!(markdown.contains("| `myapp:cacheCapacity` | `1` | `3` | **differs** |"));
+        assert!(markdown
+            .contains("| `myapp:tags` | _missing_ | `{\"costCenter\":\"42\"}` | **missing** |"));
+
+        let json: serde_json::Value = serde_json::from_str(&diff.render(DiffFormat::Json)).unwrap();
+        assert_eq!(json["keys"][2]["values"], serde_json::json!([1, 3]));
+        assert_eq!(json["keys"][4]["values"][0], serde_json::Value::Null);
+    }
+
+    #[test]
+    fn test_diff_needs_two_stack_files() {
+        let dev = stack_file("Pulumi.dev.yaml", "config: {}\n");
+        assert!(ConfigDiff::new(&[&dev]).is_err());
+        assert_eq!(environment_label(&dev), "dev");
+    }
+}
@@ -1,75 +1,310 @@
-use anyhow::Error;
-use anyhow::Ok;
-use serde_yaml;
+use anyhow::{anyhow, Error, Ok};
+use serde_yaml::{self, Mapping, Value};
 use std::collections::BTreeMap;
 use std::fs;
 use std::path::Path;
 
-use crate::tui::app::ProjectConfig;
+use crate::helpers::network_plan::{plan_network, NetworkPlan, NetworkRegistry};
+use crate::helpers::snippet_catalog::resolve_in_dependency_order;
+use crate::helpers::subscription_registry::{AzureSubscription, SubscriptionRegistry};
+use crate::helpers::tag_policy::TagPolicy;
+use crate::tui::app::{ProjectConfig, SecretMode};
+
+/// Stack name pattern used when the project does not set one.
+pub const DEFAULT_STACK_PATTERN: &str = "{env}";
+
+/// Value of a secure stack config entry until it is set with `pulumi config set --secret`.
+pub const SECRET_PLACEHOLDER: &str = "REPLACE_ME";
+
+/// Azure region pairs, used as the default DR region of a primary location.
+const AZURE_REGION_PAIRS: &[(&str, &str)] = &[
+    ("eastus", "westus"),
+    ("westus", "eastus"),
+    ("eastus2", "centralus"),
+    ("centralus", "eastus2"),
+    ("westus2", "westcentralus"),
+    ("westcentralus", "westus2"),
+    ("westus3", "eastus"),
+    ("northcentralus", "southcentralus"),
+    ("southcentralus", "northcentralus"),
+    ("canadacentral", "canadaeast"),
+    ("canadaeast", "canadacentral"),
+    ("northeurope", "westeurope"),
+    ("westeurope", "northeurope"),
+    ("uksouth", "ukwest"),
+    ("ukwest", "uksouth"),
+    ("australiaeast", "australiasoutheast"),
+    ("australiasoutheast", "australiaeast"),
+    ("southeastasia", "eastasia"),
+    ("eastasia", "southeastasia"),
+];
+
+/// Regions one environment deploys to in multi-region mode.
+#[derive(Debug, PartialEq)]
+pub struct RegionPlan {
+    /// Active regions, starting with the environment's primary location.
+    pub regions: Vec<String>,
+    /// Region that hosts the standby copy of region-scoped resources.
+    pub dr_region: String,
+}
 
-/// Represents the structure of the configuration, including settings and environment metadata.
-#[derive(serde::Serialize)]
-struct AzureConfigYaml {
-    config: BTreeMap<String, String>, // Key-value settings for Azure configurations.
-    environment: Vec<String>,         // Metadata related to the environment (e.g., 'meta').
+/// A Pulumi stack settings file (`Pulumi.<stack>.yaml`): imported ESC environments
+/// and typed config values, in insertion order.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Default)]
+pub struct AzureConfigYaml {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub environment: Vec<String>, // ESC environments imported by the stack.
+    #[serde(default)]
+    pub config: Mapping, // Namespaced config keys, e.g. `myapp:location`.
 }
 
-pub struct AzureSubscription {
-    env: String,
-    id: String,
-    _name: String,
-    tier: String,
-    virtual_subnets: Vec<String>,
+/// A stack settings file read back from a project directory.
+#[derive(Debug)]
+pub struct StackFile {
+    /// File name, e.g. `Pulumi.dev.yaml`.
+    pub file_name: String,
+    pub settings: AzureConfigYaml,
 }
 
-/// Generates a configuration for a specific environment, preserving the order of keys.
+/// Generates the stack settings for a specific environment, preserving the order of keys.
 ///
 /// # Arguments
-/// - `env`: The name of the environment (e.g., "dev", "test").
-/// - `location`: The Azure region (e.g., "eastus2").
-/// - `subscription_id`: The Azure subscription ID.
-/// - `app_name`: The name of the app to dynamically configure the settings.
+/// - `infra_config`: The project configuration (app name, owner, resources, ESC imports).
+/// - `azure_subscription`: The environment's subscription, including its Azure region.
+/// - `network`: Subnets planned for the environment, used instead of the subscription's subnets.
+/// - `tags`: Tags resolved from the tag policy, added to the subscription's registry tags.
 ///
 /// # Returns
-/// - A `Config` struct representing the generated configuration with ordered keys.
-fn generate_config(infra_config: &ProjectConfig, azure_subscription: &AzureSubscription) -> AzureConfigYaml {
-    let mut config: BTreeMap<String, String> = BTreeMap::new();
-
-    // Static Azure location
-    let location: &str = "eastus2";
+/// - An `AzureConfigYaml` with typed config values and the rendered ESC imports.
+fn generate_config(
+    infra_config: &ProjectConfig,
+    azure_subscription: &AzureSubscription,
+    network: Option<&NetworkPlan>,
+    tags: &BTreeMap<String, String>,
+) -> Result<AzureConfigYaml, Error> {
+    let mut config = Mapping::new();
+
+    // Azure location of the environment's subscription
+    let location: &str = &azure_subscription.location;
 
     let app_name = &infra_config.resource_name;
     let owner_email = &infra_config.owner_email;
+    let mut insert = |key: &str, value: Value| {
+        config.insert(Value::from(format!("{}:{}", app_name, key)), value);
+    };
+
     // Populate config with environment-specific settings
-    config.insert(format!("{}:owneremail", app_name), owner_email.to_owned());
-    config.insert(
-        format!("{}:virtualSubnetworks", app_name),
-        azure_subscription.virtual_subnets.join(","),
+    insert("app", Value::from(app_name.as_str()));
+    insert("env", Value::from(azure_subscription.env.as_str()));
+    insert("owneremail", Value::from(owner_email.as_str()));
+    insert(
+        "subscriptionId",
+        Value::from(azure_subscription.id.as_str()),
     );
-    config.insert(
-        format!("{}:tier", app_name),
-        azure_subscription.tier.to_string(),
+    insert("tier", Value::from(azure_subscription.tier.as_str()));
+    insert("location", Value::from(location));
+    let subnets: Vec<&str> = match network {
+        Some(plan) => plan.cidrs(),
+        None => azure_subscription
+            .virtual_subnets
+            .iter()
+            .map(String::as_str)
+            .collect(),
+    };
+    insert(
+        "virtualSubnetworks",
+        Value::Sequence(subnets.into_iter().map(Value::from).collect()),
     );
-    config.insert(
-        format!("{}:subscriptionId", app_name),
-        azure_subscription.id.to_string(),
-    );
-    config.insert(format!("{}:location", app_name), location.to_string());
-    config.insert(
-        format!("{}:env", app_name),
-        azure_subscription.env.to_string(),
+    // Planned subnets by role, e.g. `data: 10.1.1.0/26`
+    if let Some(plan) = network {
+        insert(
+            "subnets",
+            Value::Mapping(
+                plan.subnets
+                    .iter()
+                    .map(|subnet| {
+                        (
+                            Value::from(subnet.role.name()),
+                            Value::from(subnet.cidr.as_str()),
+                        )
+                    })
+                    .collect(),
+            ),
+        );
+    }
+    // Applied to every taggable resource by the generated program
+    let mut all_tags = azure_subscription.tags.clone();
+    all_tags.extend(tags.clone());
+    insert(
+        "tags",
+        Value::Mapping(
+            all_tags
+                .iter()
+                .map(|(key, value)| (Value::from(key.as_str()), Value::from(value.as_str())))
+                .collect(),
+        ),
     );
-    config.insert(format!("{}:app", app_name), app_name.to_string());
-    config.insert(format!("azure-native:location"), location.to_string());
 
-    // Define metadata for the environment
-    let environment: Vec<String> = vec!["meta".to_string()];
+    if let Some(plan) = region_plan(infra_config, azure_subscription)? {
+        insert(
+            "regions",
+            Value::Sequence(
+                plan.regions
+                    .iter()
+                    .map(|r| Value::from(r.as_str()))
+                    .collect(),
+            ),
+        );
+        insert("drRegion", Value::from(plan.dr_region.as_str()));
+    }
 
-    // Return populated Config struct instance
-    AzureConfigYaml {
-        config,
+    // Defaults of the selected snippets, typed like the `pulumi.Config` getters that read them
+    for def in
+        resolve_in_dependency_order(&infra_config.additional_resources, infra_config.secret_mode)?
+    {
+        for parameter in def.parameters {
+            insert(
+                &def.config_key(parameter.name),
+                parameter.param_type.yaml_value(parameter.default),
+            );
+        }
+        // Secure placeholders for secrets read with `config.requireSecret`
+        if infra_config.secret_mode == SecretMode::Placeholder {
+            for secret in def.secrets {
+                let mut secure = Mapping::new();
+                secure.insert(Value::from("secure"), Value::from(SECRET_PLACEHOLDER));
+                insert(&def.config_key(secret.name), Value::Mapping(secure));
+            }
+        }
+    }
+
+    config.insert(Value::from("azure-native:location"), Value::from(location));
+
+    let environment = infra_config
+        .esc_environments
+        .iter()
+        .map(|pattern| render_name_pattern(pattern, infra_config, &azure_subscription.env))
+        .collect();
+
+    Ok(AzureConfigYaml {
         environment,
+        config,
+    })
+}
+
+/// Azure's paired region for a location, if it has one.
+pub fn paired_region(location: &str) -> Option<&'static str> {
+    AZURE_REGION_PAIRS
+        .iter()
+        .find(|(region, _)| *region == location)
+        .map(|(_, pair)| *pair)
+}
+
+/// Works out the active regions and DR region of an environment. Returns `None`
+/// unless the project is multi-region.
+pub fn region_plan(
+    config: &ProjectConfig,
+    azure_subscription: &AzureSubscription,
+) -> Result<Option<RegionPlan>, Error> {
+    if !config.multi_region {
+        return Ok(None);
+    }
+
+    let primary = &azure_subscription.location;
+    let mut regions = vec![primary.clone()];
+    for region in &config.regions {
+        if region.is_empty()
+            || region.contains(char::is_whitespace)
+            || *region != region.to_lowercase()
+        {
+            return Err(anyhow!(
+                "Region '{}' must be an Azure region name such as 'eastus2'",
+                region
+            ));
+        }
+        if !regions.contains(region) {
+            regions.push(region.clone());
+        }
+    }
+
+    let dr_region = match &config.dr_region {
+        Some(region) => region.clone(),
+        None => paired_region(primary)
+            .ok_or_else(|| {
+                anyhow!(
+                    "No paired region is known for '{}' (environment '{}'); set a DR region explicitly",
+                    primary,
+                    azure_subscription.env
+                )
+            })?
+            .to_string(),
+    };
+    if regions.contains(&dr_region) {
+        return Err(anyhow!(
+            "DR region '{}' is also an active region of environment '{}'",
+            dr_region,
+            azure_subscription.env
+        ));
+    }
+
+    Ok(Some(RegionPlan { regions, dr_region }))
+}
+
+/// Substitutes `{org}`, `{app}` and `{env}` in a stack or ESC environment name pattern.
+fn render_name_pattern(pattern: &str, config: &ProjectConfig, env: &str) -> String {
+    pattern
+        .replace("{org}", config.organization.as_deref().unwrap_or_default())
+        .replace("{app}", &config.resource_name)
+        .replace("{env}", env)
+}
+
+/// Fully qualified stack name for an environment, e.g. `acme/myapp/dev`.
+pub fn stack_name(config: &ProjectConfig, env: &str) -> String {
+    let pattern = config
+        .stack_pattern
+        .as_deref()
+        .unwrap_or(DEFAULT_STACK_PATTERN);
+    render_name_pattern(pattern, config, env)
+}
+
+/// Name of the stack settings file. Pulumi names it after the last segment of the stack name.
+pub fn stack_file_name(config: &ProjectConfig, env: &str) -> String {
+    let stack = stack_name(config, env);
+    format!("Pulumi.{}.yaml", stack.rsplit('/').next().unwrap_or(&stack))
+}
+
+/// Checks that the stack pattern yields a valid, distinct stack name for every environment.
+pub fn validate_stack_settings(config: &ProjectConfig) -> Result<(), Error> {
+    let pattern = config
+        .stack_pattern
+        .as_deref()
+        .unwrap_or(DEFAULT_STACK_PATTERN);
+
+    if !pattern
+        .rsplit('/')
+        .next()
+        .unwrap_or_default()
+        .contains("{env}")
+    {
+        return Err(anyhow!(
+            "Stack pattern '{}' must end with a segment containing {{env}}",
+            pattern
+        ));
+    }
+    if pattern.split('/').count() > 3 {
+        return Err(anyhow!(
+            "Stack pattern '{}' has more than three segments (org/project/stack)",
+            pattern
+        ));
+    }
+    if pattern.contains("{org}") && config.organization.is_none() {
+        return Err(anyhow!(
+            "Stack pattern '{}' uses {{org}} but no organization is set",
+            pattern
+        ));
     }
+
+    Ok(())
 }
 
 /// Writes a YAML configuration to a file.
@@ -100,58 +335,61 @@ fn write_config_to_file(config: &AzureConfigYaml, output_path: &str) -> Result<(
     Ok(())
 }
 
-pub fn get_subscription_info(environment: &str) -> AzureSubscription {
-    // TODO: These values should come from environment variables/.env file
-    // For now, hardcoded for demonstration purposes
-    match environment {
-        "dev" => AzureSubscription {
-            env: "dev".to_owned(),
-            id: "1234-5678-91011".to_owned(),
-            _name: "dev01".to_owned(),
-            tier: "bronze".to_owned(),
-            virtual_subnets: vec!["".to_string(), "".to_string()],
-        },
-        "test" => AzureSubscription {
-            env: "test".to_owned(),
-            id: "1234-5678-91011".to_owned(),
-            _name: "nonprod01".to_owned(),
-            tier: "silver".to_owned(),
-            virtual_subnets: vec!["".to_string(), "".to_string()],
-        },
-        "stage" => AzureSubscription {
-            env: "stage".to_owned(),
-            id: "1234-5678-91011".to_owned(),
-            _name: "preprod01".to_owned(),
-            tier: "gold".to_owned(),
-            virtual_subnets: vec!["".to_string(), "".to_string()],
-        },
-        "prod" => AzureSubscription {
-            env: "prod".to_owned(),
-            id: "1234-5678-91011".to_owned(),
-            _name: "prod01".to_owned(),
-            tier: "platinum".to_owned(),
-            virtual_subnets: vec!["".to_string(), "".to_string()],
-        },
-        "" => {
-            return AzureSubscription {
-                env: "default".to_owned(),
-                id: "default-id".to_owned(),
-                _name: "default-name".to_owned(),
-                tier: "default-tier".to_owned(),
-                virtual_subnets: vec!["default-subnet".to_owned()],
-            }
+/// Reads every `Pulumi.<stack>.yaml` stack settings file in a project directory, sorted by name.
+///
+/// # Returns
+/// - An error if the directory cannot be read, a file is not valid YAML or there are no stack files.
+pub fn load_stack_files(dir: &Path) -> Result<Vec<StackFile>, Error> {
+    let mut stack_files = Vec::new();
+    for entry in
+        fs::read_dir(dir).map_err(|err| anyhow!("Failed to read {}: {}", dir.display(), err))?
+    {
+        let path = entry?.path();
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        if !(file_name.starts_with("Pulumi.") && file_name.ends_with(".yaml"))
+            || file_name == "Pulumi.yaml"
+        {
+            continue;
         }
-        _ => panic!("Must pass an environment!"),
+        let content = fs::read_to_string(&path)?;
+        let settings = serde_yaml::from_str(&content)
+            .map_err(|err| anyhow!("Invalid stack file {}: {}", file_name, err))?;
+        stack_files.push(StackFile {
+            file_name,
+            settings,
+        });
+    }
+
+    if stack_files.is_empty() {
+        return Err(anyhow!(
+            "No Pulumi.<stack>.yaml files found in {}",
+            dir.display()
+        ));
     }
+    stack_files.sort_by(|a, b| a.file_name.cmp(&b.file_name));
+    Ok(stack_files)
 }
 
-/// Generates and writes configurations for multiple environments.
+/// Looks up the subscription for an environment in the layered subscription registry.
 ///
-/// This function creates default configurations for "dev", "test", "stage", and "prod",
-/// and writes them to individual YAML files in the `output_dir` directory.
+/// # Returns
+/// - An error if the registry is invalid or does not contain the environment.
+pub fn get_subscription_info(environment: &str) -> Result<AzureSubscription, Error> {
+    SubscriptionRegistry::load()?.get(environment)
+}
+
+/// Generates and writes stack settings files for the selected environments.
+///
+/// This function creates a configuration for each environment in `config.environments`,
+/// looked up in the subscription registry, and writes it to `Pulumi.<stack>.yaml` in
+/// the `output_dir` directory. Subnets planned from an environment's address space
+/// are recorded in the network registry.
 ///
 /// # Arguments
-/// - `app_name`: The name of the app to dynamically configure the settings.
+/// - `config`: The project configuration, including the selected environments.
 /// - `output_dir`: The directory where configuration files will be written.
 ///
 /// # Returns
@@ -160,30 +398,56 @@ pub async fn create_config_files(
     config: &ProjectConfig,
     output_dir: Option<&str>,
 ) -> Result<(), Error> {
-    // Define default environments
-    let environments = ["dev", "test", "stage", "prod"]; // get from .env file, with the rest of Azure Subscription values
-
-    // Generate and write configuration files for each environment
-    for env in environments.iter() {
-        let azure_subscription: AzureSubscription = get_subscription_info(env);
-        let config = generate_config(config, &azure_subscription);
+    validate_stack_settings(config)?;
+    let registry = SubscriptionRegistry::load()?;
+    let mut network_registry = NetworkRegistry::load()?;
+    let tag_policy = TagPolicy::load()?;
+    let mut planned = false;
+
+    // Generate and write configuration files for each selected environment
+    for env in config.environments.iter() {
+        let azure_subscription: AzureSubscription = registry.get(env)?;
+        let network = plan_network(config, &azure_subscription, &network_registry)?;
+        let tags = tag_policy.resolve(config, &azure_subscription)?;
+        let stack_config = generate_config(config, &azure_subscription, network.as_ref(), &tags)?;
         let output_path = format!(
-            "{}/{}.yaml",
+            "{}/{}",
             output_dir.unwrap_or("."), // Default to current directory if not provided
-            env
+            stack_file_name(config, env)
         );
-        write_config_to_file(&config, &output_path)?;
+        write_config_to_file(&stack_config, &output_path)?;
+        if let Some(plan) = &network {
+            network_registry.record(plan);
+            planned = true;
+        }
     }
 
+    if planned {
+        network_registry.save()?;
+    }
     Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::helpers::subscription_registry::isolate_user_config;
     use std::fs;
     use std::path::Path;
 
+    /// Reads a config value by key, panicking if it is missing.
+    fn config_value<'a>(config: &'a AzureConfigYaml, key: &str) -> &'a Value {
+        config.config.get(&Value::from(key)).unwrap()
+    }
+
+    /// Values for the tags of the organization tag policy that have no other source.
+    fn required_tags() -> BTreeMap<String, String> {
+        BTreeMap::from([
+            ("cost-center".to_string(), "1234".to_string()),
+            ("data-classification".to_string(), "internal".to_string()),
+        ])
+    }
+
     /// Helper function to clean up test files after testing.
     fn cleanup_test_files(output_dir: &str) {
         if Path::new(output_dir).exists() {
@@ -196,42 +460,151 @@ mod tests {
         let azure_subscription = AzureSubscription {
             env: "dev".to_string(),
             id: "1234-5678-91011".to_string(),
-            _name: "dev01".to_string(),
+            name: "dev01".to_string(),
             tier: "bronze".to_string(),
-            virtual_subnets: vec!["subnet1".to_string(), "subnet2".to_string()],
+            location: "eastus2".to_string(),
+            address_space: None,
+            virtual_subnets: vec!["10.0.0.0/24".to_string(), "10.0.1.0/24".to_string()],
+            tags: BTreeMap::from([("costCenter".to_string(), "1234".to_string())]),
+            service_connection: "azure-dev01".to_string(),
+            variable_groups: Vec::new(),
+            backend_url: "https://api.pulumi.com".to_string(),
+            budget: None,
         };
 
         let config = ProjectConfig {
             resource_name: "test_app".to_string(),
             environments: vec!["dev".to_string()],
-            additional_resources: vec![],
+            additional_resources: vec!["cache".to_string()],
             owner_email: "owner@example.com".to_string(),
+            esc_environments: vec!["{app}/{env}".to_string()],
+            ..Default::default()
         };
 
-        let config = generate_config(&config, &azure_subscription);
+        let tags = BTreeMap::from([("owner".to_string(), "owner@example.com".to_string())]);
+
+        let config = generate_config(&config, &azure_subscription, None, &tags).unwrap();
 
         assert_eq!(
-            config.config.get("test_app:virtualSubnetworks").unwrap(),
-            "subnet1,subnet2"
+            config_value(&config, "test_app:virtualSubnetworks"),
+            &serde_yaml::from_str::<Value>("[10.0.0.0/24, 10.0.1.0/24]").unwrap()
+        );
+        assert_eq!(
+            config_value(&config, "test_app:tags"),
+            &serde_yaml::from_str::<Value>("{costCenter: '1234', owner: owner@example.com}")
+                .unwrap()
         );
-        assert_eq!(config.config.get("test_app:tier").unwrap(), "bronze");
         assert_eq!(
-            config.config.get("test_app:subscriptionId").unwrap(),
+            config_value(&config, "test_app:cacheCapacity"),
+            &Value::from(1)
+        );
+        assert_eq!(config_value(&config, "test_app:cacheSkuName"), "Standard");
+        assert_eq!(config_value(&config, "test_app:tier"), "bronze");
+        assert_eq!(
+            config_value(&config, "test_app:subscriptionId"),
             "1234-5678-91011"
         );
-        assert_eq!(config.config.get("test_app:location").unwrap(), "eastus2");
-        assert_eq!(config.config.get("test_app:env").unwrap(), "dev");
-        assert_eq!(config.environment, vec!["meta".to_string()]);
+        assert_eq!(config_value(&config, "test_app:location"), "eastus2");
+        assert_eq!(config_value(&config, "test_app:env"), "dev");
+        assert_eq!(config.environment, vec!["test_app/dev".to_string()]);
+    }
+
+    #[test]
+    fn test_generate_config_planned_subnets() {
+        let mut azure_subscription = get_subscription_info("dev").unwrap();
+        azure_subscription.address_space = Some("10.4.0.0/16".to_string());
+        let config = ProjectConfig {
+            resource_name: "test_app".to_string(),
+            additional_resources: vec!["cache".to_string()],
+            ..Default::default()
+        };
+        let plan = plan_network(&config, &azure_subscription, &NetworkRegistry::default())
+            .unwrap()
+            .unwrap();
+
+        let stack =
+            generate_config(&config, &azure_subscription, Some(&plan), &BTreeMap::new()).unwrap();
+
+        assert_eq!(
+            config_value(&stack, "test_app:virtualSubnetworks"),
+            &serde_yaml::from_str::<Value>("[10.4.0.0/24, 10.4.1.0/27]").unwrap()
+        );
+        assert_eq!(
+            config_value(&stack, "test_app:subnets"),
+            &serde_yaml::from_str::<Value>("{app: 10.4.0.0/24, privateEndpoints: 10.4.1.0/27}")
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_generate_config_secrets() {
+        let azure_subscription = get_subscription_info("dev").unwrap();
+        let mut config = ProjectConfig {
+            resource_name: "test_app".to_string(),
+            additional_resources: vec!["database".to_string()],
+            ..Default::default()
+        };
+
+        let placeholder =
+            generate_config(&config, &azure_subscription, None, &BTreeMap::new()).unwrap();
+        assert_eq!(
+            config_value(&placeholder, "test_app:databaseAdministratorLoginPassword"),
+            &serde_yaml::from_str::<Value>("secure: REPLACE_ME").unwrap()
+        );
+
+        config.secret_mode = SecretMode::KeyVault;
+        let generated =
+            generate_config(&config, &azure_subscription, None, &BTreeMap::new()).unwrap();
+        assert!(generated
+            .config
+            .get(&Value::from("test_app:databaseAdministratorLoginPassword"))
+            .is_none());
+        assert_eq!(
+            config_value(&generated, "test_app:keyvaultSkuName"),
+            "standard"
+        );
+    }
+
+    #[test]
+    fn test_load_stack_files() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config = ProjectConfig {
+            resource_name: "test_app".to_string(),
+            additional_resources: vec!["cache".to_string()],
+            ..Default::default()
+        };
+        for env in ["prod", "dev"] {
+            let settings = generate_config(
+                &config,
+                &get_subscription_info(env).unwrap(),
+                None,
+                &BTreeMap::new(),
+            )
+            .unwrap();
+            let path = temp_dir.path().join(format!("Pulumi.{}.yaml", env));
+            write_config_to_file(&settings, path.to_str().unwrap()).unwrap();
+        }
+        fs::write(temp_dir.path().join("Pulumi.yaml"), "name: test_app\n").unwrap();
+
+        let stack_files = load_stack_files(temp_dir.path()).unwrap();
+
+        let names: Vec<&str> = stack_files.iter().map(|f| f.file_name.as_str()).collect();
+        assert_eq!(names, vec!["Pulumi.dev.yaml", "Pulumi.prod.yaml"]);
+        assert_eq!(
+            config_value(&stack_files[1].settings, "test_app:tier"),
+            "platinum"
+        );
+        assert!(load_stack_files(&temp_dir.path().join("missing")).is_err());
     }
 
     #[test]
     fn test_write_config_to_file() {
         let config = AzureConfigYaml {
-            config: BTreeMap::from([
-                ("key1".to_string(), "value1".to_string()),
-                ("key2".to_string(), "value2".to_string()),
+            config: Mapping::from_iter([
+                (Value::from("key1"), Value::from("value1")),
+                (Value::from("key2"), Value::from(2)),
             ]),
-            environment: vec!["meta".to_string()],
+            environment: vec!["shared/azure".to_string()],
         };
 
         let output_path = "test_output/test_config.yaml";
@@ -246,8 +619,9 @@ mod tests {
 
         // Validate file contents
         let written_content = fs::read_to_string(output_path).unwrap();
+        assert!(written_content.starts_with("environment:\n  - shared/azure\nconfig:\n"));
         assert!(written_content.contains("key1: value1"));
-        assert!(written_content.contains("key2: value2"));
+        assert!(written_content.contains("key2: 2"));
 
         // Clean up
         cleanup_test_files(output_dir);
@@ -255,12 +629,13 @@ mod tests {
 
     #[test]
     fn test_get_subscription_info() {
-        let dev_subscription = get_subscription_info("dev");
+        isolate_user_config();
+        let dev_subscription = get_subscription_info("dev").unwrap();
         assert_eq!(dev_subscription.env, "dev");
         assert_eq!(dev_subscription.id, "1234-5678-91011");
         assert_eq!(dev_subscription.tier, "bronze");
 
-        let prod_subscription = get_subscription_info("prod");
+        let prod_subscription = get_subscription_info("prod").unwrap();
         assert_eq!(prod_subscription.env, "prod");
         assert_eq!(prod_subscription.id, "1234-5678-91011");
         assert_eq!(prod_subscription.tier, "platinum");
@@ -268,6 +643,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_create_config_files() {
+        isolate_user_config();
         let config = ProjectConfig {
             resource_name: "test_app".to_string(),
             environments: vec![
@@ -278,6 +654,8 @@ mod tests {
             ],
             additional_resources: vec![],
             owner_email: "owner@example.com".to_string(),
+            tags: required_tags(),
+            ..Default::default()
         };
         let output_dir = "test_configs";
 
@@ -290,7 +668,12 @@ mod tests {
             .unwrap();
 
         // Validate generated files
-        let expected_files = ["dev.yaml", "test.yaml", "stage.yaml", "prod.yaml"];
+        let expected_files = [
+            "Pulumi.dev.yaml",
+            "Pulumi.test.yaml",
+            "Pulumi.stage.yaml",
+            "Pulumi.prod.yaml",
+        ];
         for file in expected_files.iter() {
             let path = format!("{}/{}", output_dir, file);
             assert!(Path::new(&path).exists());
@@ -305,9 +688,113 @@ mod tests {
         cleanup_test_files(output_dir);
     }
 
+    #[tokio::test]
+    async fn test_create_config_files_only_writes_selected_environments() {
+        isolate_user_config();
+        let config = ProjectConfig {
+            resource_name: "test_app".to_string(),
+            environments: vec!["dev".to_string(), "stage".to_string()],
+            owner_email: "owner@example.com".to_string(),
+            tags: required_tags(),
+            ..Default::default()
+        };
+        let output_dir = tempfile::tempdir().unwrap();
+        let output_dir = output_dir.path().to_str().unwrap();
+
+        create_config_files(&config, Some(output_dir))
+            .await
+            .unwrap();
+
+        assert!(Path::new(&format!("{}/Pulumi.dev.yaml", output_dir)).exists());
+        assert!(Path::new(&format!("{}/Pulumi.stage.yaml", output_dir)).exists());
+        assert!(!Path::new(&format!("{}/Pulumi.prod.yaml", output_dir)).exists());
+        let dev = load_stack_files(Path::new(output_dir)).unwrap();
+        assert_eq!(
+            config_value(&dev[0].settings, "test_app:tags")["data-classification"],
+            "internal"
+        );
+
+        // Generation fails if a required tag has no value
+        let untagged = ProjectConfig {
+            tags: BTreeMap::new(),
+            ..config.clone()
+        };
+        assert!(create_config_files(&untagged, Some(output_dir))
+            .await
+            .unwrap_err()
+            .to_string()
+            .contains("cost-center"));
+
+        let config = ProjectConfig {
+            environments: vec!["sandbox".to_string()],
+            ..config
+        };
+        assert!(create_config_files(&config, Some(output_dir))
+            .await
+            .is_err());
+    }
+
+    #[test]
+    fn test_stack_names_follow_the_pattern() {
+        let mut config = ProjectConfig {
+            resource_name: "myapp".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(stack_name(&config, "dev"), "dev");
+        assert_eq!(stack_file_name(&config, "dev"), "Pulumi.dev.yaml");
+
+        config.stack_pattern = Some("{org}/{app}/{env}".to_string());
+        assert!(validate_stack_settings(&config).is_err());
+
+        config.organization = Some("acme".to_string());
+        assert!(validate_stack_settings(&config).is_ok());
+        assert_eq!(stack_name(&config, "prod"), "acme/myapp/prod");
+        assert_eq!(stack_file_name(&config, "prod"), "Pulumi.prod.yaml");
+
+        config.stack_pattern = Some("{org}/{app}".to_string());
+        assert!(validate_stack_settings(&config).is_err());
+    }
+
+    #[test]
+    fn test_region_plan() {
+        let subscription = get_subscription_info("prod").unwrap();
+        let mut config = ProjectConfig {
+            resource_name: "myapp".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(region_plan(&config, &subscription).unwrap(), None);
+
+        config.multi_region = true;
+        config.regions = vec!["westus3".to_string(), "eastus2".to_string()];
+        assert_eq!(
+            region_plan(&config, &subscription).unwrap(),
+            Some(RegionPlan {
+                regions: vec!["eastus2".to_string(), "westus3".to_string()],
+                dr_region: "centralus".to_string(),
+            })
+        );
+
+        let stack = generate_config(&config, &subscription, None, &BTreeMap::new()).unwrap();
+        assert_eq!(
+            config_value(&stack, "myapp:regions"),
+            &serde_yaml::from_str::<Value>("[eastus2, westus3]").unwrap()
+        );
+        assert_eq!(config_value(&stack, "myapp:drRegion"), "centralus");
+
+        config.dr_region = Some("westus3".to_string());
+        assert!(region_plan(&config, &subscription).is_err());
+
+        config.dr_region = None;
+        config.regions = vec!["West US".to_string()];
+        assert!(region_plan(&config, &subscription).is_err());
+    }
+
     #[test]
-    #[should_panic(expected = "Must pass an environment!")]
     fn test_get_subscription_info_invalid_env() {
-        get_subscription_info("invalid_env");
+        isolate_user_config();
+        let err = get_subscription_info("invalid_env").unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("Unknown environment 'invalid_env'."));
     }
 }
@@ -0,0 +1,512 @@
+use anyhow::{anyhow, Error};
+use serde_yaml::Value;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use crate::helpers::config_helper::{AzureConfigYaml, StackFile, SECRET_PLACEHOLDER};
+use crate::helpers::snippet_catalog::{
+    resolve_in_dependency_order, ParameterType, SNIPPET_CATALOG,
+};
+use crate::helpers::subscription_registry::TIERS;
+use crate::tui::app::{ProjectConfig, SecretMode};
+
+/// Kind of value a stack config key holds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ValueKind {
+    String,
+    Number,
+    Boolean,
+    List,
+    Object,
+    /// A `secure:` value, set with `pulumi config set --secret`.
+    Secret,
+}
+
+impl ValueKind {
+    fn of_parameter(param_type: ParameterType) -> Self {
+        match param_type {
+            ParameterType::String => ValueKind::String,
+            ParameterType::Number => ValueKind::Number,
+            ParameterType::Boolean => ValueKind::Boolean,
+        }
+    }
+
+    /// Kind of a value found in a stack file.
+    fn of_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::String(_) => Some(ValueKind::String),
+            Value::Number(_) => Some(ValueKind::Number),
+            Value::Bool(_) => Some(ValueKind::Boolean),
+            Value::Sequence(_) => Some(ValueKind::List),
+            Value::Mapping(mapping) if is_secure(mapping) => Some(ValueKind::Secret),
+            Value::Mapping(_) => Some(ValueKind::Object),
+            Value::Null => None,
+        }
+    }
+}
+
+impl fmt::Display for ValueKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ValueKind::String => "string",
+            ValueKind::Number => "number",
+            ValueKind::Boolean => "boolean",
+            ValueKind::List => "list",
+            ValueKind::Object => "object",
+            ValueKind::Secret => "secret",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// One key of the project's stack config.
+#[derive(Debug, Clone)]
+pub struct SchemaKey {
+    /// Key without the project namespace, e.g. `cacheSkuName`.
+    pub key: String,
+    pub kind: ValueKind,
+    /// Read with `config.require*` (or always written by pulumimi), rather than defaulted in code.
+    pub required: bool,
+    /// Accepted values; empty when any value of the kind is accepted.
+    pub allowed: Vec<String>,
+    /// Snippet the key belongs to; `None` for the stack metadata written by pulumimi.
+    pub resource: Option<&'static str>,
+    /// Value written by generation, used when the key is added to a stack file.
+    pub default: Option<Value>,
+}
+
+/// Every stack config key a project reads: the stack metadata written by pulumimi
+/// plus the parameters and secrets of the selected snippets.
+#[derive(Debug)]
+pub struct ConfigSchema {
+    /// Project namespace of the keys, e.g. `myapp` for `myapp:location`.
+    pub namespace: String,
+    pub keys: Vec<SchemaKey>,
+}
+
+/// A problem found in a stack file.
+#[derive(Debug, PartialEq)]
+pub enum ConfigIssue {
+    Missing {
+        key: String,
+    },
+    TypeMismatch {
+        key: String,
+        expected: ValueKind,
+        found: String,
+    },
+    NotAllowed {
+        key: String,
+        value: String,
+        allowed: Vec<String>,
+    },
+    Unknown {
+        key: String,
+    },
+    Empty {
+        key: String,
+    },
+}
+
+impl fmt::Display for ConfigIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigIssue::Missing { key } => write!(f, "missing required key '{}'", key),
+            ConfigIssue::TypeMismatch {
+                key,
+                expected,
+                found,
+            } => write!(f, "'{}' should be a {}, found {}", key, expected, found),
+            ConfigIssue::NotAllowed {
+                key,
+                value,
+                allowed,
+            } => write!(
+                f,
+                "'{}' is '{}', expected one of {}",
+                key,
+                value,
+                allowed.join(", ")
+            ),
+            ConfigIssue::Unknown { key } => write!(f, "unknown key '{}'", key),
+            ConfigIssue::Empty { key } => write!(f, "'{}' is empty or a placeholder", key),
+        }
+    }
+}
+
+impl ConfigSchema {
+    /// Builds the schema of a project from its selected resources and settings.
+    pub fn for_project(config: &ProjectConfig) -> Result<Self, Error> {
+        let key = |key: &str, kind: ValueKind, required: bool| SchemaKey {
+            key: key.to_string(),
+            kind,
+            required,
+            allowed: Vec::new(),
+            resource: None,
+            default: None,
+        };
+
+        let mut keys = vec![
+            key("app", ValueKind::String, true),
+            key("env", ValueKind::String, true),
+            key("owneremail", ValueKind::String, true),
+            key("subscriptionId", ValueKind::String, true),
+            SchemaKey {
+                allowed: TIERS.iter().map(|tier| tier.to_string()).collect(),
+                ..key("tier", ValueKind::String, true)
+            },
+            key("location", ValueKind::String, true),
+            key("virtualSubnetworks", ValueKind::List, true),
+            key("subnets", ValueKind::Object, false),
+            key("tags", ValueKind::Object, true),
+            key("resourceGroupName", ValueKind::String, false),
+            key("regions", ValueKind::List, config.multi_region),
+            key("drRegion", ValueKind::String, config.multi_region),
+        ];
+
+        for def in resolve_in_dependency_order(&config.additional_resources, config.secret_mode)? {
+            for parameter in def.parameters {
+                keys.push(SchemaKey {
+                    allowed: parameter.allowed.iter().map(|v| v.to_string()).collect(),
+                    resource: Some(def.key),
+                    default: Some(parameter.param_type.yaml_value(parameter.default)),
+                    ..key(
+                        &def.config_key(parameter.name),
+                        ValueKind::of_parameter(parameter.param_type),
+                        false,
+                    )
+                });
+            }
+            if config.secret_mode == SecretMode::Placeholder {
+                for secret in def.secrets {
+                    let mut secure = serde_yaml::Mapping::new();
+                    secure.insert(Value::from("secure"), Value::from(SECRET_PLACEHOLDER));
+                    keys.push(SchemaKey {
+                        resource: Some(def.key),
+                        default: Some(Value::Mapping(secure)),
+                        ..key(&def.config_key(secret.name), ValueKind::Secret, true)
+                    });
+                }
+            }
+        }
+
+        Ok(Self {
+            namespace: config.resource_name.clone(),
+            keys,
+        })
+    }
+
+    /// Looks up a key without its namespace.
+    pub fn get(&self, key: &str) -> Option<&SchemaKey> {
+        self.keys.iter().find(|schema_key| schema_key.key == key)
+    }
+
+    /// Looks up a namespaced key such as `myapp:cacheSkuName`.
+    pub fn get_full(&self, full_key: &str) -> Option<&SchemaKey> {
+        full_key
+            .strip_prefix(&format!("{}:", self.namespace))
+            .and_then(|key| self.get(key))
+    }
+
+    /// Checks one value of a namespaced key, e.g. before it is written by the editor.
+    pub fn check(&self, full_key: &str, value: &Value) -> Option<ConfigIssue> {
+        match self.get_full(full_key) {
+            Some(schema_key) => check_value(schema_key, full_key, value),
+            None => Some(ConfigIssue::Unknown {
+                key: full_key.to_string(),
+            }),
+        }
+    }
+
+    /// Checks a stack file against the schema. Keys in other namespaces, such as
+    /// `azure-native:location`, belong to providers and are not checked.
+    pub fn validate(&self, settings: &AzureConfigYaml) -> Vec<ConfigIssue> {
+        let mut issues = Vec::new();
+        let prefix = format!("{}:", self.namespace);
+
+        for schema_key in &self.keys {
+            let full_key = format!("{}{}", prefix, schema_key.key);
+            match settings.config.get(&Value::from(full_key.as_str())) {
+                None if schema_key.required => issues.push(ConfigIssue::Missing { key: full_key }),
+                None => {}
+                Some(value) => {
+                    if let Some(issue) = check_value(schema_key, &full_key, value) {
+                        issues.push(issue);
+                    }
+                }
+            }
+        }
+
+        for key in settings.config.iter().filter_map(|(key, _)| key.as_str()) {
+            if let Some(name) = key.strip_prefix(&prefix) {
+                if self.get(name).is_none() {
+                    issues.push(ConfigIssue::Unknown {
+                        key: key.to_string(),
+                    });
+                }
+            }
+        }
+
+        issues
+    }
+}
+
+/// Checks one value against its schema key.
+fn check_value(schema_key: &SchemaKey, full_key: &str, value: &Value) -> Option<ConfigIssue> {
+    let empty = ConfigIssue::Empty {
+        key: full_key.to_string(),
+    };
+    let Some(kind) = ValueKind::of_value(value) else {
+        return Some(empty);
+    };
+    // Numbers and booleans written as strings are read fine by `getNumber`/`getBoolean`
+    let text = scalar_text(value);
+    let coerces = kind == ValueKind::String
+        && match schema_key.kind {
+            ValueKind::Number => text.as_deref().is_some_and(|t| t.parse::<f64>().is_ok()),
+            ValueKind::Boolean => matches!(text.as_deref(), Some("true" | "false")),
+            _ => false,
+        };
+    // Numbers such as `13` are fine where a string is expected
+    let widens = schema_key.kind == ValueKind::String && kind == ValueKind::Number;
+    if kind != schema_key.kind && !coerces && !widens {
+        return Some(ConfigIssue::TypeMismatch {
+            key: full_key.to_string(),
+            expected: schema_key.kind,
+            found: kind.to_string(),
+        });
+    }
+
+    let is_empty = match value {
+        Value::String(text) => text.trim().is_empty(),
+        Value::Sequence(items) => items.is_empty(),
+        Value::Mapping(mapping) if kind == ValueKind::Secret => mapping
+            .get(&Value::from("secure"))
+            .and_then(Value::as_str)
+            .is_none_or(|secure| secure.is_empty() || secure == SECRET_PLACEHOLDER),
+        Value::Mapping(mapping) => mapping.is_empty(),
+        _ => false,
+    };
+    if is_empty {
+        return Some(empty);
+    }
+
+    match text {
+        Some(text) if !schema_key.allowed.is_empty() && !schema_key.allowed.contains(&text) => {
+            Some(ConfigIssue::NotAllowed {
+                key: full_key.to_string(),
+                value: text,
+                allowed: schema_key.allowed.clone(),
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Text of a string, number or boolean value.
+fn scalar_text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(text) => Some(text.clone()),
+        Value::Number(number) => Some(number.to_string()),
+        Value::Bool(flag) => Some(flag.to_string()),
+        _ => None,
+    }
+}
+
+fn is_secure(mapping: &serde_yaml::Mapping) -> bool {
+    mapping.len() == 1 && mapping.contains_key(&Value::from("secure"))
+}
+
+/// Works out the project settings that shape the schema of an existing project: the
+/// namespace from the `<project>:app` key, the selected resources from the config keys
+/// found in the stack files or the TypeScript sources, and whether secrets are generated.
+pub fn infer_project(dir: &Path, stack_files: &[StackFile]) -> Result<ProjectConfig, Error> {
+    let keys: Vec<&str> = stack_files
+        .iter()
+        .flat_map(|file| {
+            file.settings
+                .config
+                .iter()
+                .filter_map(|(key, _)| key.as_str())
+        })
+        .collect();
+    let namespace = keys
+        .iter()
+        .find_map(|key| key.strip_suffix(":app"))
+        .ok_or_else(|| anyhow!("No stack file sets '<project>:app', cannot tell the project name"))?
+        .to_string();
+
+    let sources = project_sources(dir);
+    let references = |config_key: &str| {
+        keys.contains(&format!("{}:{}", namespace, config_key).as_str())
+            || sources.contains(&format!("\"{}\"", config_key))
+    };
+
+    let additional_resources = SNIPPET_CATALOG
+        .iter()
+        .filter(|def| {
+            def.parameters
+                .iter()
+                .map(|parameter| parameter.name)
+                .chain(def.secrets.iter().map(|secret| secret.name))
+                .any(|name| references(&def.config_key(name)))
+        })
+        .map(|def| def.key.to_string())
+        .collect();
+
+    let secret_mode = if sources.contains("new random.RandomPassword(") {
+        SecretMode::KeyVault
+    } else {
+        SecretMode::Placeholder
+    };
+
+    let environments = stack_files
+        .iter()
+        .filter_map(|file| {
+            file.settings
+                .config
+                .get(&Value::from(format!("{}:env", namespace)))
+                .and_then(Value::as_str)
+                .map(str::to_string)
+        })
+        .collect();
+
+    Ok(ProjectConfig {
+        multi_region: keys
+            .iter()
+            .any(|key| *key == format!("{}:regions", namespace)),
+        resource_name: namespace,
+        environments,
+        additional_resources,
+        secret_mode,
+        ..Default::default()
+    })
+}
+
+/// The generated TypeScript sources of a project (`*.ts` and `resources/*.ts`), concatenated.
+fn project_sources(dir: &Path) -> String {
+    let mut sources = String::new();
+    for source_dir in [dir.to_path_buf(), dir.join("resources")] {
+        let Ok(entries) = fs::read_dir(source_dir) else {
+            continue;
+        };
+        for path in entries.flatten().map(|entry| entry.path()) {
+            if path.extension().is_some_and(|ext| ext == "ts") {
+                sources.push_str(&fs::read_to_string(&path).unwrap_or_default());
+            }
+        }
+    }
+    sources
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(yaml: &str) -> AzureConfigYaml {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    fn schema() -> ConfigSchema {
+        ConfigSchema::for_project(&ProjectConfig {
+            resource_name: "myapp".to_string(),
+            additional_resources: vec!["cache".to_string(), "database".to_string()],
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    const VALID: &str = "config:
+  myapp:app: myapp
+  myapp:env: dev
+  myapp:owneremail: owner@example.com
+  myapp:subscriptionId: 1234-5678
+  myapp:tier: bronze
+  myapp:location: eastus2
+  myapp:virtualSubnetworks: [10.0.0.0/24]
+  myapp:tags:
+    owner: owner@example.com
+  myapp:cacheSkuName: Standard
+  myapp:cacheCapacity: 1
+  myapp:databaseVersion: \"13\"
+  myapp:databaseAdministratorLoginPassword:
+    secure: v1:abc
+  azure-native:location: eastus2
+";
+
+    #[test]
+    fn test_valid_stack_file_has_no_issues() {
+        assert_eq!(schema().validate(&settings(VALID)), vec![]);
+    }
+
+    #[test]
+    fn test_validate_reports_every_issue() {
+        let yaml = VALID
+            .replace("  myapp:owneremail: owner@example.com\n", "")
+            .replace("[10.0.0.0/24]", "[]")
+            .replace("cacheCapacity: 1", "cacheCapacity: [1]")
+            .replace("cacheSkuName: Standard", "cacheSkuName: Gold")
+            .replace("secure: v1:abc", "secure: REPLACE_ME")
+            .replace("  azure-native", "  myapp:cacheSize: 1\n  azure-native");
+
+        let issues = schema().validate(&settings(&yaml));
+
+        assert_eq!(
+            issues,
+            vec![
+                ConfigIssue::Missing {
+                    key: "myapp:owneremail".to_string()
+                },
+                ConfigIssue::Empty {
+                    key: "myapp:virtualSubnetworks".to_string()
+                },
+                ConfigIssue::NotAllowed {
+                    key: "myapp:cacheSkuName".to_string(),
+                    value: "Gold".to_string(),
+                    allowed: vec![
+                        "Basic".to_string(),
+                        "Standard".to_string(),
+                        "Premium".to_string()
+                    ],
+                },
+                ConfigIssue::TypeMismatch {
+                    key: "myapp:cacheCapacity".to_string(),
+                    expected: ValueKind::Number,
+                    found: "list".to_string(),
+                },
+                ConfigIssue::Empty {
+                    key: "myapp:databaseAdministratorLoginPassword".to_string()
+                },
+                ConfigIssue::Unknown {
+                    key: "myapp:cacheSize".to_string()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_infer_project() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        fs::write(
+            temp_dir.path().join("index.ts"),
+            "const sku = config.get(\"storageSkuName\") ?? \"Standard_LRS\";\nconst p = new random.RandomPassword(\"p\", {});\n",
+        )
+        .unwrap();
+        let stack_files = vec![StackFile {
+            file_name: "Pulumi.dev.yaml".to_string(),
+            settings: settings(VALID),
+        }];
+
+        let project = infer_project(temp_dir.path(), &stack_files).unwrap();
+
+        assert_eq!(project.resource_name, "myapp");
+        assert_eq!(project.environments, vec!["dev"]);
+        assert_eq!(
+            project.additional_resources,
+            vec!["database", "cache", "storage"]
+        );
+        assert_eq!(project.secret_mode, SecretMode::KeyVault);
+        assert!(!project.multi_region);
+    }
+}
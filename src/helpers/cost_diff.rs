@@ -0,0 +1,397 @@
+use crate::helpers::cost_helper::{
+    format_amount, render_table, CostEstimate, EnvironmentUsage, ResourceCost, RowStyle,
+};
+use crate::helpers::price_catalog::PriceCatalog;
+
+/// Output format of `pulumimi cost diff`.
+#[derive(Debug, Clone, Copy, PartialEq, Default, clap::ValueEnum)]
+pub enum CostDiffFormat {
+    /// Table for the terminal, with increases and savings highlighted.
+    #[default]
+    Table,
+    /// Markdown table, e.g. for a change request.
+    Markdown,
+    Csv,
+}
+
+/// One resource in one region of an environment, before and after the change.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CostDiffRow {
+    pub env: String,
+    pub resource: String,
+    pub region: String,
+    /// `None` when the resource is added by the change.
+    pub before: Option<ResourceCost>,
+    /// `None` when the resource is removed by the change.
+    pub after: Option<ResourceCost>,
+    /// Monthly cost after the change with each term of `CostDiff::terms` reserved;
+    /// `None` where the catalog has no reservation price for the resource.
+    pub reserved: Vec<Option<f64>>,
+}
+
+impl CostDiffRow {
+    pub fn before_monthly(&self) -> f64 {
+        self.before.as_ref().map_or(0.0, |cost| cost.monthly)
+    }
+
+    pub fn after_monthly(&self) -> f64 {
+        self.after.as_ref().map_or(0.0, |cost| cost.monthly)
+    }
+
+    pub fn delta(&self) -> f64 {
+        self.after_monthly() - self.before_monthly()
+    }
+}
+
+/// Monthly cost of two versions of a project per environment and resource, with the
+/// reserved-instance alternatives of the new version.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CostDiff {
+    /// Reservation terms the catalog offers for any resource after the change, e.g. `1 Year`.
+    pub terms: Vec<String>,
+    pub rows: Vec<CostDiffRow>,
+}
+
+impl CostDiff {
+    /// Prices both versions from a price catalog. Environments are listed in the order
+    /// they first appear, and resources are paired by name and region.
+    pub fn new(
+        before: &[EnvironmentUsage],
+        after: &[EnvironmentUsage],
+        catalog: &PriceCatalog,
+    ) -> Self {
+        let before_estimate = CostEstimate::new(before, catalog);
+        let after_estimate = CostEstimate::new(after, catalog);
+
+        let mut terms: Vec<String> = Vec::new();
+        for resource in after.iter().flat_map(|environment| &environment.resources) {
+            for term in resource.reservation_terms(catalog) {
+                if !terms.contains(&term) {
+                    terms.push(term);
+                }
+            }
+        }
+
+        let mut environments: Vec<&str> = Vec::new();
+        for environment in before.iter().chain(after) {
+            if !environments.contains(&environment.env.as_str()) {
+                environments.push(&environment.env);
+            }
+        }
+
+        let resources = |estimate: &CostEstimate, env: &str| -> Vec<ResourceCost> {
+            estimate
+                .environments
+                .iter()
+                .find(|environment| environment.env == env)
+                .map(|environment| environment.resources.clone())
+                .unwrap_or_default()
+        };
+        let mut rows = Vec::new();
+        for env in environments {
+            let before_resources = resources(&before_estimate, env);
+            let after_resources = resources(&after_estimate, env);
+            let after_usage = after.iter().find(|environment| environment.env == env);
+
+            let mut keys: Vec<(&str, &str)> = Vec::new();
+            for cost in before_resources.iter().chain(&after_resources) {
+                let key = (cost.resource.as_str(), cost.region.as_str());
+                if !keys.contains(&key) {
+                    keys.push(key);
+                }
+            }
+            for (resource, region) in keys {
+                let find = |costs: &[ResourceCost]| {
+                    costs
+                        .iter()
+                        .find(|cost| cost.resource == resource && cost.region == region)
+                        .cloned()
+                };
+                let usage = after_usage.and_then(|environment| {
+                    environment
+                        .resources
+                        .iter()
+                        .find(|usage| usage.resource == resource && usage.region == region)
+                });
+                rows.push(CostDiffRow {
+                    env: env.to_string(),
+                    resource: resource.to_string(),
+                    region: region.to_string(),
+                    before: find(&before_resources),
+                    after: find(&after_resources),
+                    reserved: terms
+                        .iter()
+                        .map(|term| usage.and_then(|usage| usage.reserved_monthly(catalog, term)))
+                        .collect(),
+                });
+            }
+        }
+        Self { terms, rows }
+    }
+
+    /// Meters without a price on either side, as `<env>/<resource>: <meter>`.
+    pub fn unpriced(&self) -> Vec<String> {
+        let mut unpriced: Vec<String> = Vec::new();
+        for row in &self.rows {
+            for cost in row.before.iter().chain(&row.after) {
+                for meter in &cost.unpriced {
+                    let text = format!("{}/{}: {}", row.env, row.resource, meter);
+                    if !unpriced.contains(&text) {
+                        unpriced.push(text);
+                    }
+                }
+            }
+        }
+        unpriced
+    }
+
+    pub fn render(&self, format: CostDiffFormat) -> String {
+        match format {
+            CostDiffFormat::Table => self.render_table(),
+            CostDiffFormat::Markdown => self.render_markdown(),
+            CostDiffFormat::Csv => self.render_csv(),
+        }
+    }
+
+    fn header(&self) -> Vec<String> {
+        let mut header = [
+            "Environment",
+            "Resource",
+            "Region",
+            "Before SKU",
+            "After SKU",
+            "Before",
+            "After",
+            "Delta",
+        ]
+        .map(str::to_string)
+        .to_vec();
+        header.extend(self.terms.iter().map(|term| format!("Reserved {}", term)));
+        header
+    }
+
+    /// Rows of each environment followed by its total, as (texts, style) with amounts
+    /// formatted by `amount` and deltas by `delta`.
+    fn lines(
+        &self,
+        amount: &dyn Fn(f64) -> String,
+        delta: &dyn Fn(f64) -> String,
+    ) -> Vec<(Vec<String>, RowStyle)> {
+        let sku = |cost: &Option<ResourceCost>| {
+            cost.as_ref()
+                .map_or_else(|| "-".to_string(), |cost| cost.sku.clone())
+        };
+        let style = |change: f64| match change {
+            change if change > 0.005 => RowStyle::Warning,
+            change if change < -0.005 => RowStyle::Saving,
+            _ => RowStyle::Normal,
+        };
+
+        let mut lines = Vec::new();
+        let mut rows = self.rows.iter().peekable();
+        while let Some(row) = rows.next() {
+            let mut texts = vec![
+                row.env.clone(),
+                row.resource.clone(),
+                row.region.clone(),
+                sku(&row.before),
+                sku(&row.after),
+                amount(row.before_monthly()),
+                amount(row.after_monthly()),
+                delta(row.delta()),
+            ];
+            texts.extend(
+                row.reserved
+                    .iter()
+                    .map(|reserved| reserved.map_or_else(|| "-".to_string(), amount)),
+            );
+            lines.push((texts, style(row.delta())));
+
+            if rows.peek().is_none_or(|next| next.env != row.env) {
+                let environment: Vec<&CostDiffRow> = self
+                    .rows
+                    .iter()
+                    .filter(|other| other.env == row.env)
+                    .collect();
+                let before: f64 = environment.iter().map(|row| row.before_monthly()).sum();
+                let after: f64 = environment.iter().map(|row| row.after_monthly()).sum();
+                let mut texts = vec![
+                    row.env.clone(),
+                    "total".to_string(),
+                    String::new(),
+                    String::new(),
+                    String::new(),
+                    amount(before),
+                    amount(after),
+                    delta(after - before),
+                ];
+                // Resources without a reservation stay pay-as-you-go in the reserved totals
+                texts.extend((0..self.terms.len()).map(|term| {
+                    amount(
+                        environment
+                            .iter()
+                            .map(|row| row.reserved[term].unwrap_or(row.after_monthly()))
+                            .sum(),
+                    )
+                }));
+                lines.push((texts, RowStyle::Total));
+            }
+        }
+        lines
+    }
+
+    fn render_table(&self) -> String {
+        render_table(
+            &self.header(),
+            &self.lines(&format_amount, &format_delta),
+            3 + self.terms.len(),
+        )
+    }
+
+    fn render_markdown(&self) -> String {
+        let header = self.header();
+        let numeric = 3 + self.terms.len();
+        let alignments: Vec<&str> = (0..header.len())
+            .map(|column| match column >= header.len() - numeric {
+                true => " ---: |",
+                false => " --- |",
+            })
+            .collect();
+        let mut markdown = format!("| {} |\n|{}\n", header.join(" | "), alignments.concat());
+        for (texts, style) in self.lines(&format_amount, &format_delta) {
+            let cells: Vec<String> = texts
+                .iter()
+                .map(|text| match style {
+                    RowStyle::Total if !text.is_empty() => format!("**{}**", text),
+                    _ => text.replace('|', "\\|"),
+                })
+                .collect();
+            markdown.push_str(&format!("| {} |\n", cells.join(" | ")));
+        }
+        markdown
+    }
+
+    fn render_csv(&self) -> String {
+        let record = |texts: &[String]| {
+            texts
+                .iter()
+                .map(|text| csv_field(text))
+                .collect::<Vec<_>>()
+                .join(",")
+                + "\n"
+        };
+        let plain = |amount: f64| format!("{:.2}", amount);
+        let mut csv = record(&self.header());
+        for (texts, _) in self.lines(&plain, &plain) {
+            csv.push_str(&record(&texts));
+        }
+        csv
+    }
+}
+
+/// Formats a change in monthly cost with its sign, e.g. `+12.50` or `-3.00`.
+pub fn format_delta(delta: f64) -> String {
+    match format_amount(delta.abs()).as_str() {
+        "0.00" => "0.00".to_string(),
+        amount if delta > 0.0 => format!("+{}", amount),
+        amount => format!("-{}", amount),
+    }
+}
+
+/// Quotes a CSV field if it contains a separator, quote or line break.
+fn csv_field(text: &str) -> String {
+    match text.contains([',', '"', '\n']) {
+        true => format!("\"{}\"", text.replace('"', "\"\"")),
+        false => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::cost_helper::{Item, MeterUsage, ResourceUsage, CURRENCY};
+
+    fn price(sku_name: &str, retail_price: f64, term: Option<&str>) -> Item {
+        Item {
+            currency_code: CURRENCY.to_string(),
+            retail_price,
+            arm_region_name: "eastus2".to_string(),
+            service_name: "Azure Database for PostgreSQL".to_string(),
+            product_name: String::new(),
+            sku_name: sku_name.to_string(),
+            arm_sku_name: String::new(),
+            meter_name: "vCore".to_string(),
+            unit_of_measure: "1 Hour".to_string(),
+            price_type: term.map_or("Consumption", |_| "Reservation").to_string(),
+            reservation_term: term.map(str::to_string),
+            tier_minimum_units: 0.0,
+        }
+    }
+
+    fn usage(env: &str, resources: &[(&str, &str, f64)]) -> EnvironmentUsage {
+        EnvironmentUsage {
+            env: env.to_string(),
+            resources: resources
+                .iter()
+                .map(|(resource, sku, vcores)| ResourceUsage {
+                    resource: resource.to_string(),
+                    region: "eastus2".to_string(),
+                    sku: format!("GeneralPurpose Standard_{}", sku.replace(' ', "_")),
+                    meters: vec![MeterUsage {
+                        service_name: "Azure Database for PostgreSQL",
+                        product_name: None,
+                        sku_name: sku.to_string(),
+                        meter_name: "vCore".to_string(),
+                        region: "eastus2".to_string(),
+                        quantity: vcores * 730.0,
+                    }],
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_cost_diff() {
+        let catalog = PriceCatalog::from_items(
+            "test",
+            "2026-10-01",
+            vec![
+                price("D2s v3", 0.1, None),
+                price("D4s v3", 0.1, None),
+                price("D4s v3", 600.0, Some("1 Year")),
+                price("D4s v3", 1440.0, Some("3 Years")),
+            ],
+        );
+        let before = [usage("dev", &[("database", "D2s v3", 2.0)])];
+        let after = [
+            usage("dev", &[("database", "D4s v3", 4.0)]),
+            usage("prod", &[("database", "D2s v3", 2.0)]),
+        ];
+
+        let diff = CostDiff::new(&before, &after, &catalog);
+        assert_eq!(diff.terms, ["1 Year", "3 Years"]);
+        assert_eq!(diff.rows.len(), 2);
+        assert_eq!(format_delta(diff.rows[0].delta()), "+146.00");
+        assert_eq!(diff.rows[0].reserved, [Some(200.0), Some(160.0)]);
+        // prod is new, and D2s v3 has no reservation price
+        assert!(diff.rows[1].before.is_none());
+        assert_eq!(diff.rows[1].reserved, [None, None]);
+
+        let markdown = diff.render(CostDiffFormat::Markdown);
+        assert!(markdown.starts_with(
+            "| Environment | Resource | Region | Before SKU | After SKU | Before | After | Delta | Reserved 1 Year | Reserved 3 Years |\n"
+        ));
+        assert!(markdown.contains(
+            "| **prod** | **total** |  |  |  | **0.00** | **146.00** | **+146.00** | **146.00** | **146.00** |\n"
+        ));
+
+        let csv = diff.render(CostDiffFormat::Csv);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(
+            lines[1],
+            "dev,database,eastus2,GeneralPurpose Standard_D2s_v3,GeneralPurpose Standard_D4s_v3,146.00,292.00,146.00,200.00,160.00"
+        );
+        assert_eq!(lines.len(), 5);
+        assert_eq!(csv_field("a,\"b\""), "\"a,\"\"b\"\"\"");
+    }
+}
@@ -1,85 +1,844 @@
+use anyhow::{anyhow, Error};
+use colored::*;
 use reqwest::Client;
-use reqwest::Error;
-use serde::Deserialize;
-use std::error::Error as StdError;
+use serde::{Deserialize, Serialize};
+use serde_yaml::Value;
+use std::collections::BTreeSet;
+use std::path::Path;
 
-#[derive(Deserialize, Debug)]
-struct Item {
-    arm_sku_name: String,
-    retail_price: f64,
-    unit_of_measure: String,
-    arm_region_name: String,
-    meter_name: String,
-    product_name: String,
+use crate::helpers::config_diff::environment_label;
+use crate::helpers::config_helper::{load_stack_files, region_plan, StackFile};
+use crate::helpers::config_schema::infer_project;
+use crate::helpers::price_catalog::PriceCatalog;
+use crate::helpers::snippet_catalog::{resolve_in_dependency_order, SnippetDefinition};
+use crate::helpers::subscription_registry::SubscriptionRegistry;
+use crate::tui::app::ProjectConfig;
+
+/// Azure Retail Prices API
+const RETAIL_PRICES_API: &str = "https://prices.azure.com/api/retail/prices";
+const RETAIL_PRICES_API_VERSION: &str = "2023-01-01-preview";
+
+/// Currency of the Retail Prices API when none is requested
+pub const CURRENCY: &str = "USD";
+
+/// Hours Azure bills an always-on resource for in a month.
+pub const HOURS_PER_MONTH: f64 = 730.0;
+const DAYS_PER_MONTH: f64 = 30.0;
+
+/// Assumed monthly usage of consumption-billed meters, in the meter's unit
+const STORAGE_GB_PER_MONTH: f64 = 100.0;
+const KEYVAULT_10K_OPERATIONS_PER_MONTH: f64 = 10.0;
+const SERVICEBUS_1M_OPERATIONS_PER_MONTH: f64 = 1.0;
+
+/// One price of the Azure Retail Prices API.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Item {
+    #[serde(default)]
+    pub currency_code: String,
+    pub retail_price: f64,
+    pub arm_region_name: String,
+    pub service_name: String,
+    pub product_name: String,
+    pub sku_name: String,
+    #[serde(default)]
+    pub arm_sku_name: String,
+    pub meter_name: String,
+    pub unit_of_measure: String,
+    /// `Consumption`, `Reservation` or `DevTestConsumption`.
+    #[serde(rename = "type", default)]
+    pub price_type: String,
+    /// Term of a reservation price, e.g. `1 Year`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reservation_term: Option<String>,
+    /// Usage from which a tiered price applies.
+    #[serde(default)]
+    pub tier_minimum_units: f64,
 }
 
+/// One page of the Azure Retail Prices API.
 #[derive(Deserialize, Debug)]
-struct PricingResponse {
-    items: Vec<Item>,
-    next_page_link: Option<String>,
+pub struct PricingResponse {
+    #[serde(rename = "Items")]
+    pub items: Vec<Item>,
+    #[serde(rename = "NextPageLink")]
+    pub next_page_link: Option<String>,
+}
+
+/// A meter a resource is billed on, with its expected monthly usage.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MeterUsage {
+    pub service_name: &'static str,
+    /// Product the meter belongs to; any product of the service when `None`.
+    pub product_name: Option<String>,
+    pub sku_name: String,
+    pub meter_name: String,
+    pub region: String,
+    /// Monthly usage in the meter's unit of measure, e.g. 730 for an hourly meter.
+    pub quantity: f64,
+}
+
+impl MeterUsage {
+    /// OData filter of the Retail Prices API that returns the prices of this meter.
+    fn filter(&self) -> String {
+        let mut filter = format!(
+            "serviceName eq '{}' and armRegionName eq '{}' and skuName eq '{}' and meterName eq '{}'",
+            self.service_name, self.region, self.sku_name, self.meter_name
+        );
+        if let Some(product_name) = &self.product_name {
+            filter.push_str(&format!(" and productName eq '{}'", product_name));
+        }
+        filter
+    }
+
+    fn matches(&self, item: &Item) -> bool {
+        item.service_name == self.service_name
+            && item.arm_region_name == self.region
+            && item.sku_name == self.sku_name
+            && item.meter_name == self.meter_name
+            && self
+                .product_name
+                .as_ref()
+                .is_none_or(|product_name| &item.product_name == product_name)
+    }
+
+    /// Pay-as-you-go unit price of the meter: the lowest usage tier of its consumption prices.
+    pub fn unit_price(&self, prices: &[Item]) -> Option<f64> {
+        prices
+            .iter()
+            .filter(|item| item.price_type == "Consumption" && self.matches(item))
+            .min_by(|a, b| a.tier_minimum_units.total_cmp(&b.tier_minimum_units))
+            .map(|item| item.retail_price)
+    }
+
+    /// Monthly cost of the meter with a reservation of the given term, e.g. `1 Year`.
+    /// Only hourly meters are reserved; a reservation price covers one unit for the
+    /// whole term.
+    pub fn reserved_monthly(&self, prices: &[Item], term: &str) -> Option<f64> {
+        let months = term_months(term)?;
+        prices
+            .iter()
+            .find(|item| {
+                item.price_type == "Reservation"
+                    && item.reservation_term.as_deref() == Some(term)
+                    && item.unit_of_measure == "1 Hour"
+                    && self.matches(item)
+            })
+            .map(|item| item.retail_price / months * self.quantity / HOURS_PER_MONTH)
+    }
 }
 
-fn build_pricing_table(json_data: &PricingResponse, table_data: &mut Vec<Vec<String>>) {
-    for item in &json_data.items {
-        table_data.push(vec![
-            item.arm_sku_name.clone(),
-            item.retail_price.to_string(),
-            item.unit_of_measure.clone(),
-            item.arm_region_name.clone(),
-            item.meter_name.clone(),
-            item.product_name.clone(),
-        ]);
+/// Months of a reservation term such as `1 Year` or `3 Years`.
+pub fn term_months(term: &str) -> Option<f64> {
+    let (count, unit) = term.split_once(' ')?;
+    let count: f64 = count.parse().ok()?;
+    match unit {
+        "Year" | "Years" => Some(count * 12.0),
+        "Month" | "Months" => Some(count),
+        _ => None,
     }
 }
 
-async fn get_pricing_data(
-    client: &Client,
-    api_url: &str,
-    query: &str,
-) -> Result<PricingResponse, Box<dyn StdError>> {
-    let response = client
-        .get(api_url)
-        .query(&[("api-version", "2021-10-01-preview"), ("$filter", query)])
-        .send()
-        .await?;
+/// One resource of an environment in one region, with the meters it is billed on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResourceUsage {
+    /// Snippet key, e.g. `cache`.
+    pub resource: String,
+    pub region: String,
+    /// SKU as configured, e.g. `Standard C1`.
+    pub sku: String,
+    pub meters: Vec<MeterUsage>,
+}
 
-    if !response.status().is_success() {
-        return Err("Failed to fetch pricing data".into());
+impl ResourceUsage {
+    /// Reservation terms offered for any meter of the resource, e.g. `1 Year`.
+    pub fn reservation_terms(&self, catalog: &PriceCatalog) -> Vec<String> {
+        let mut terms: Vec<String> = self
+            .meters
+            .iter()
+            .flat_map(|meter| catalog.lookup(meter))
+            .filter(|item| item.price_type == "Reservation")
+            .filter_map(|item| item.reservation_term.clone())
+            .collect();
+        terms.sort_by(|a, b| {
+            term_months(a)
+                .unwrap_or_default()
+                .total_cmp(&term_months(b).unwrap_or_default())
+        });
+        terms.dedup();
+        terms
+    }
+
+    /// Monthly cost with every meter that offers the term reserved and the others
+    /// at pay-as-you-go prices, or `None` if no meter offers the term.
+    pub fn reserved_monthly(&self, catalog: &PriceCatalog, term: &str) -> Option<f64> {
+        let mut reserved = false;
+        let mut monthly = 0.0;
+        for meter in &self.meters {
+            let prices = catalog.lookup(meter);
+            match meter.reserved_monthly(prices, term) {
+                Some(cost) => {
+                    reserved = true;
+                    monthly += cost;
+                }
+                None => monthly += meter.unit_price(prices).unwrap_or_default() * meter.quantity,
+            }
+        }
+        reserved.then_some(monthly)
+    }
+}
+
+/// The priced resources of one environment.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnvironmentUsage {
+    pub env: String,
+    pub resources: Vec<ResourceUsage>,
+}
+
+/// Monthly cost of one resource. Meters without a price are left out of the cost
+/// and listed in `unpriced`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResourceCost {
+    pub resource: String,
+    pub region: String,
+    pub sku: String,
+    pub monthly: f64,
+    pub unpriced: Vec<String>,
+}
+
+/// Monthly cost of the resources of one environment.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnvironmentCost {
+    pub env: String,
+    pub resources: Vec<ResourceCost>,
+}
+
+impl EnvironmentCost {
+    pub fn total(&self) -> f64 {
+        self.resources.iter().map(|resource| resource.monthly).sum()
+    }
+
+    /// Meters without a price, as `<resource>: <meter>`.
+    pub fn unpriced(&self) -> Vec<String> {
+        self.resources
+            .iter()
+            .flat_map(|resource| {
+                resource
+                    .unpriced
+                    .iter()
+                    .map(move |meter| format!("{}: {}", resource.resource, meter))
+            })
+            .collect()
+    }
+}
+
+/// Estimated monthly cost of a project, per environment and resource.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CostEstimate {
+    pub environments: Vec<EnvironmentCost>,
+}
+
+impl CostEstimate {
+    /// Prices the usage of every environment from a price catalog.
+    pub fn new(usage: &[EnvironmentUsage], catalog: &PriceCatalog) -> Self {
+        let environments = usage
+            .iter()
+            .map(|environment| EnvironmentCost {
+                env: environment.env.clone(),
+                resources: environment
+                    .resources
+                    .iter()
+                    .map(|resource| {
+                        let mut monthly = 0.0;
+                        let mut unpriced = Vec::new();
+                        for meter in &resource.meters {
+                            match meter.unit_price(catalog.lookup(meter)) {
+                                Some(price) => monthly += price * meter.quantity,
+                                None => unpriced.push(meter.meter_name.clone()),
+                            }
+                        }
+                        ResourceCost {
+                            resource: resource.resource.clone(),
+                            region: resource.region.clone(),
+                            sku: resource.sku.clone(),
+                            monthly,
+                            unpriced,
+                        }
+                    })
+                    .collect(),
+            })
+            .collect();
+        Self { environments }
+    }
+
+    /// Meters without a price, as `<env>/<resource>: <meter>`.
+    pub fn unpriced(&self) -> Vec<String> {
+        self.environments
+            .iter()
+            .flat_map(|environment| {
+                environment
+                    .unpriced()
+                    .into_iter()
+                    .map(move |meter| format!("{}/{}", environment.env, meter))
+            })
+            .collect()
+    }
+
+    /// Terminal table with one row per resource and a total per environment.
+    pub fn render_table(&self) -> String {
+        let header = ["Environment", "Resource", "Region", "SKU", "Monthly"]
+            .map(str::to_string)
+            .to_vec();
+        let mut rows = Vec::new();
+        for environment in &self.environments {
+            for resource in &environment.resources {
+                let style = if resource.unpriced.is_empty() {
+                    RowStyle::Normal
+                } else {
+                    RowStyle::Warning
+                };
+                rows.push((
+                    vec![
+                        environment.env.clone(),
+                        resource.resource.clone(),
+                        resource.region.clone(),
+                        resource.sku.clone(),
+                        format_amount(resource.monthly),
+                    ],
+                    style,
+                ));
+            }
+            rows.push((
+                vec![
+                    environment.env.clone(),
+                    "total".to_string(),
+                    String::new(),
+                    String::new(),
+                    format_amount(environment.total()),
+                ],
+                RowStyle::Total,
+            ));
+        }
+        render_table(&header, &rows, 1)
+    }
+}
+
+/// How a row of a cost table is highlighted.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RowStyle {
+    Normal,
+    Total,
+    Warning,
+    Saving,
+}
+
+/// Renders a bordered terminal table; the last `numeric_columns` columns are right-aligned.
+pub fn render_table(
+    header: &[String],
+    rows: &[(Vec<String>, RowStyle)],
+    numeric_columns: usize,
+) -> String {
+    let mut widths: Vec<usize> = header.iter().map(|text| text.chars().count()).collect();
+    for (texts, _) in rows {
+        for (width, text) in widths.iter_mut().zip(texts) {
+            *width = (*width).max(text.chars().count());
+        }
+    }
+
+    let border = format!(
+        "+{}+\n",
+        widths
+            .iter()
+            .map(|width| "-".repeat(width + 2))
+            .collect::<Vec<_>>()
+            .join("+")
+    );
+    let first_numeric = widths.len().saturating_sub(numeric_columns);
+    let line = |texts: &[String]| {
+        let cells: Vec<String> = texts
+            .iter()
+            .zip(&widths)
+            .enumerate()
+            .map(|(column, (text, width))| match column >= first_numeric {
+                true => format!(" {:>width$} ", text, width = width),
+                false => format!(" {:<width$} ", text, width = width),
+            })
+            .collect();
+        format!("|{}|", cells.join("|"))
+    };
+
+    let mut table = border.clone();
+    table.push_str(&format!("{}\n", line(header).bold()));
+    table.push_str(&border);
+    for (texts, style) in rows {
+        let text = line(texts);
+        let styled = match style {
+            RowStyle::Normal => text.normal(),
+            RowStyle::Total => text.bold(),
+            RowStyle::Warning => text.yellow(),
+            RowStyle::Saving => text.green(),
+        };
+        table.push_str(&format!("{}\n", styled));
+    }
+    table.push_str(&border);
+    table
+}
+
+/// Formats a monthly amount, e.g. `1234.50`.
+pub fn format_amount(amount: f64) -> String {
+    format!("{:.2}", amount)
+}
+
+/// Works out the meters a snippet's resources are billed on in a region.
+///
+/// # Arguments
+/// - `def`: The snippet.
+/// - `parameter`: Value of a snippet parameter, e.g. `skuName`.
+/// - `region`: Azure region the resources are deployed to.
+///
+/// # Returns
+/// - `None` for snippets that are not priced.
+fn resource_usage(
+    def: &SnippetDefinition,
+    parameter: &dyn Fn(&str) -> String,
+    region: &str,
+) -> Option<ResourceUsage> {
+    let meter =
+        |service_name, product_name: Option<String>, sku_name: String, meter_name, quantity| {
+            MeterUsage {
+                service_name,
+                product_name,
+                sku_name,
+                meter_name,
+                region: region.to_string(),
+                quantity,
+            }
+        };
+
+    let (sku, meters) = match def.key {
+        "keyvault" => {
+            let sku = capitalize(&parameter("skuName"));
+            let operations = meter(
+                "Key Vault",
+                None,
+                sku.clone(),
+                "Operations".to_string(),
+                KEYVAULT_10K_OPERATIONS_PER_MONTH,
+            );
+            (sku, vec![operations])
+        }
+        "database" => {
+            // `Standard_D2s_v3` is billed per vCore as `D2s v3`
+            let sku_name = parameter("skuName");
+            let size = sku_name.trim_start_matches("Standard_").replace('_', " ");
+            let vcores: f64 = size
+                .chars()
+                .skip_while(|c| !c.is_ascii_digit())
+                .take_while(char::is_ascii_digit)
+                .collect::<String>()
+                .parse()
+                .unwrap_or(1.0);
+            let storage_gb: f64 = parameter("storageSizeGb").parse().unwrap_or(0.0);
+            let meters = vec![
+                meter(
+                    "Azure Database for PostgreSQL",
+                    None,
+                    size.clone(),
+                    "vCore".to_string(),
+                    vcores * HOURS_PER_MONTH,
+                ),
+                meter(
+                    "Azure Database for PostgreSQL",
+                    Some("Azure Database for PostgreSQL Flexible Server Storage".to_string()),
+                    "Storage".to_string(),
+                    "Storage Data Stored".to_string(),
+                    storage_gb,
+                ),
+            ];
+            (format!("{} {}", parameter("skuTier"), size), meters)
+        }
+        "cache" => {
+            let tier = parameter("skuName");
+            let size = format!("{}{}", parameter("skuFamily"), parameter("capacity"));
+            let instance = meter(
+                "Redis Cache",
+                Some(format!("Azure Redis Cache {}", tier)),
+                size.clone(),
+                format!("{} Cache Instance", size),
+                HOURS_PER_MONTH,
+            );
+            (format!("{} {}", tier, size), vec![instance])
+        }
+        "servicebus" => {
+            let sku = parameter("skuName");
+            let (meter_name, quantity) = match sku.as_str() {
+                "Basic" => (
+                    "Basic Messaging Operations",
+                    SERVICEBUS_1M_OPERATIONS_PER_MONTH,
+                ),
+                "Premium" => ("Premium Messaging Unit", HOURS_PER_MONTH),
+                _ => ("Standard Base Unit", HOURS_PER_MONTH),
+            };
+            let base = meter(
+                "Service Bus",
+                None,
+                sku.clone(),
+                meter_name.to_string(),
+                quantity,
+            );
+            (sku, vec![base])
+        }
+        "storage" => {
+            // `Standard_GRS` stores hot blobs as `Hot GRS`, `Premium_LRS` as `Premium LRS`
+            let sku = parameter("skuName");
+            let (performance, redundancy) = sku.split_once('_').unwrap_or(("Standard", "LRS"));
+            let (product_name, sku_name) = match performance {
+                "Premium" => ("Premium Block Blob", format!("Premium {}", redundancy)),
+                _ => ("General Block Blob v2", format!("Hot {}", redundancy)),
+            };
+            let stored = meter(
+                "Storage",
+                Some(product_name.to_string()),
+                sku_name.clone(),
+                format!("{} Data Stored", sku_name),
+                STORAGE_GB_PER_MONTH,
+            );
+            (sku, vec![stored])
+        }
+        "containerregistry" => {
+            let sku = parameter("skuName");
+            let registry = meter(
+                "Container Registry",
+                None,
+                sku.clone(),
+                format!("{} Registry Unit", sku),
+                DAYS_PER_MONTH,
+            );
+            (sku, vec![registry])
+        }
+        _ => return None,
+    };
+
+    Some(ResourceUsage {
+        resource: def.key.to_string(),
+        region: region.to_string(),
+        sku,
+        meters,
+    })
+}
+
+fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Usage of the environments a project configuration would generate, with the
+/// snippet defaults as SKUs and the regions of the subscription registry.
+pub fn usage_from_config(
+    config: &ProjectConfig,
+    registry: &SubscriptionRegistry,
+) -> Result<Vec<EnvironmentUsage>, Error> {
+    let mut usage = Vec::new();
+    for env in &config.environments {
+        let azure_subscription = registry.get(env)?;
+        // Region-scoped resources are deployed to the DR region too
+        let regions = match region_plan(config, &azure_subscription)? {
+            Some(plan) => [plan.regions, vec![plan.dr_region]].concat(),
+            None => vec![azure_subscription.location.clone()],
+        };
+
+        let mut resources = Vec::new();
+        for def in resolve_in_dependency_order(&config.additional_resources, config.secret_mode)? {
+            let default = |name: &str| {
+                def.parameters
+                    .iter()
+                    .find(|parameter| parameter.name == name)
+                    .map(|parameter| parameter.default.to_string())
+                    .unwrap_or_default()
+            };
+            let copies = match config.multi_region && def.region_scoped {
+                true => &regions[..],
+                false => &regions[..1],
+            };
+            resources.extend(
+                copies
+                    .iter()
+                    .filter_map(|region| resource_usage(def, &default, region)),
+            );
+        }
+        usage.push(EnvironmentUsage {
+            env: env.clone(),
+            resources,
+        });
+    }
+    Ok(usage)
+}
+
+/// Usage of the environments of an existing project, with the SKUs and regions set
+/// in its stack files and the snippet defaults for settings they leave out.
+pub fn usage_from_project(dir: &Path) -> Result<Vec<EnvironmentUsage>, Error> {
+    let stack_files = load_stack_files(dir)?;
+    let project = infer_project(dir, &stack_files)?;
+    stack_files
+        .iter()
+        .map(|stack_file| stack_usage(&project, stack_file))
+        .collect()
+}
+
+fn stack_usage(project: &ProjectConfig, stack_file: &StackFile) -> Result<EnvironmentUsage, Error> {
+    let namespace = &project.resource_name;
+    let config = &stack_file.settings.config;
+    let get = |key: &str| config.get(&Value::from(format!("{}:{}", namespace, key)));
+    let text = |value: &Value| match value {
+        Value::String(text) => Some(text.clone()),
+        Value::Number(number) => Some(number.to_string()),
+        Value::Bool(flag) => Some(flag.to_string()),
+        _ => None,
+    };
+
+    let location = get("location")
+        .or_else(|| config.get(&Value::from("azure-native:location")))
+        .and_then(text)
+        .ok_or_else(|| anyhow!("{} does not set a location", stack_file.file_name))?;
+    let mut regions: Vec<String> = get("regions")
+        .and_then(Value::as_sequence)
+        .map(|regions| regions.iter().filter_map(text).collect())
+        .filter(|regions: &Vec<String>| !regions.is_empty())
+        .unwrap_or_else(|| vec![location]);
+    // Region-scoped resources are deployed to the DR region too
+    if let Some(dr_region) = get("drRegion").and_then(text) {
+        if !regions.contains(&dr_region) {
+            regions.push(dr_region);
+        }
     }
 
-    let json_data: PricingResponse = response.json().await?;
-    Ok(json_data)
+    let mut resources = Vec::new();
+    for def in resolve_in_dependency_order(&project.additional_resources, project.secret_mode)? {
+        let parameter = |name: &str| {
+            get(&def.config_key(name))
+                .and_then(text)
+                .or_else(|| {
+                    def.parameters
+                        .iter()
+                        .find(|parameter| parameter.name == name)
+                        .map(|parameter| parameter.default.to_string())
+                })
+                .unwrap_or_default()
+        };
+        let copies = match def.region_scoped {
+            true => &regions[..],
+            false => &regions[..1],
+        };
+        resources.extend(
+            copies
+                .iter()
+                .filter_map(|region| resource_usage(def, &parameter, region)),
+        );
+    }
+    Ok(EnvironmentUsage {
+        env: environment_label(stack_file),
+        resources,
+    })
 }
 
-pub async fn calculate() -> Result<(), Box<dyn StdError>> {
+/// Fetches the prices of every meter in the usage from the Azure Retail Prices API,
+/// following `NextPageLink` until the last page.
+pub async fn fetch_prices(usage: &[EnvironmentUsage]) -> Result<Vec<Item>, Error> {
+    let filters: BTreeSet<String> = usage
+        .iter()
+        .flat_map(|environment| &environment.resources)
+        .flat_map(|resource| resource.meters.iter().map(MeterUsage::filter))
+        .collect();
+
     let client = Client::new();
-    let api_url = "https://prices.azure.com/api/retail/prices";
-    let query = "armRegionName eq 'southcentralus' and armSkuName eq 'Standard_NP20s' and priceType eq 'Consumption' and contains(meterName, 'Spot')";
+    let mut prices = Vec::new();
+    for filter in filters {
+        let mut page = get_pricing_data(&client, &filter).await?;
+        prices.append(&mut page.items);
+        while let Some(next_page) = page.next_page_link.take() {
+            page = client
+                .get(&next_page)
+                .send()
+                .await?
+                .error_for_status()?
+                .json()
+                .await?;
+            prices.append(&mut page.items);
+        }
+    }
+    Ok(prices)
+}
+
+async fn get_pricing_data(client: &Client, filter: &str) -> Result<PricingResponse, Error> {
+    let response = client
+        .get(RETAIL_PRICES_API)
+        .query(&[
+            ("api-version", RETAIL_PRICES_API_VERSION),
+            ("$filter", filter),
+        ])
+        .send()
+        .await
+        .map_err(|err| anyhow!("Failed to reach the Azure Retail Prices API: {}", err))?;
+    if !response.status().is_success() {
+        return Err(anyhow!(
+            "The Azure Retail Prices API returned {} for \"{}\"",
+            response.status(),
+            filter
+        ));
+    }
+    Ok(response.json().await?)
+}
 
-    let mut table_data = vec![vec![
-        "SKU".to_string(),
-        "Retail Price".to_string(),
-        "Unit of Measure".to_string(),
-        "Region".to_string(),
-        "Meter".to_string(),
-        "Product Name".to_string(),
-    ]];
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    // Fetch the initial pricing data
-    let mut json_data = get_pricing_data(&client, api_url, query).await?;
-    build_pricing_table(&json_data, &mut table_data);
+    fn price(service_name: &str, sku_name: &str, meter_name: &str, retail_price: f64) -> Item {
+        Item {
+            currency_code: CURRENCY.to_string(),
+            retail_price,
+            arm_region_name: "eastus2".to_string(),
+            service_name: service_name.to_string(),
+            product_name: String::new(),
+            sku_name: sku_name.to_string(),
+            arm_sku_name: String::new(),
+            meter_name: meter_name.to_string(),
+            unit_of_measure: "1 Hour".to_string(),
+            price_type: "Consumption".to_string(),
+            reservation_term: None,
+            tier_minimum_units: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_parse_pricing_response() {
+        let page = r#"{
+            "BillingCurrency": "USD",
+            "Items": [{
+                "currencyCode": "USD",
+                "tierMinimumUnits": 0.0,
+                "retailPrice": 0.1,
+                "unitPrice": 0.1,
+                "armRegionName": "eastus2",
+                "location": "US East 2",
+                "effectiveStartDate": "2024-01-01T00:00:00Z",
+                "meterId": "0000",
+                "meterName": "Standard Base Unit",
+                "productId": "DZH318Z0BPVW",
+                "skuId": "DZH318Z0BPVW/0001",
+                "productName": "Service Bus",
+                "skuName": "Standard",
+                "serviceName": "Service Bus",
+                "serviceId": "DZH3180NZMPS",
+                "serviceFamily": "Integration",
+                "unitOfMeasure": "1 Hour",
+                "type": "Consumption",
+                "isPrimaryMeterRegion": true,
+                "armSkuName": ""
+            }],
+            "NextPageLink": "https://prices.azure.com/api/retail/prices?$skip=100",
+            "Count": 1
+        }"#;
+        let response: PricingResponse = serde_json::from_str(page).unwrap();
 
-    // Paginate through the next pages, if any
-    while let Some(next_page) = json_data.next_page_link {
-        let response: PricingResponse = client.get(&next_page).send().await?.json().await?;
-        json_data = response;
-        build_pricing_table(&json_data, &mut table_data);
+        assert_eq!(
+            response.items,
+            [price("Service Bus", "Standard", "Standard Base Unit", 0.1)].map(|item| Item {
+                product_name: "Service Bus".to_string(),
+                ..item
+            })
+        );
+        assert!(response.next_page_link.is_some());
     }
 
-    // Print the result in a table format
-    for row in table_data {
-        println!("{}", row.join(" | "));
+    #[test]
+    fn test_estimate_project_config() {
+        let config = ProjectConfig {
+            resource_name: "myapp".to_string(),
+            environments: vec!["dev".to_string(), "prod".to_string()],
+            additional_resources: vec!["servicebus".to_string(), "cache".to_string()],
+            ..Default::default()
+        };
+        let registry = SubscriptionRegistry::from_layers(&[(
+            "org".to_string(),
+            include_str!("../../config/subscriptions.yaml").to_string(),
+        )])
+        .unwrap();
+        let usage = usage_from_config(&config, &registry).unwrap();
+        assert_eq!(usage[0].resources[1].sku, "Standard C1");
+
+        let mut cache = price("Redis Cache", "C1", "C1 Cache Instance", 0.1);
+        cache.product_name = "Azure Redis Cache Standard".to_string();
+        let mut cache_tier = cache.clone();
+        cache_tier.tier_minimum_units = 100.0;
+        cache_tier.retail_price = 0.05;
+        let service_bus = price("Service Bus", "Standard", "Standard Base Unit", 0.01);
+        let catalog = PriceCatalog::from_items(
+            "test",
+            "2026-10-01",
+            vec![service_bus.clone(), cache, cache_tier],
+        );
+
+        let estimate = CostEstimate::new(&usage, &catalog);
+        let dev = &estimate.environments[0];
+        assert_eq!(dev.env, "dev");
+        assert_eq!(format_amount(dev.resources[0].monthly), "7.30");
+        assert_eq!(format_amount(dev.resources[1].monthly), "73.00");
+        assert_eq!(format_amount(dev.total()), "80.30");
+        assert!(estimate.unpriced().is_empty());
+
+        // Meters missing from the price list are reported, not guessed
+        let catalog = PriceCatalog::from_items("test", "2026-10-01", vec![service_bus]);
+        let estimate = CostEstimate::new(&usage, &catalog);
+        assert_eq!(
+            estimate.unpriced(),
+            [
+                "dev/cache: C1 Cache Instance",
+                "prod/cache: C1 Cache Instance"
+            ]
+        );
     }
 
-    Ok(())
+    #[test]
+    fn test_multi_region_usage_includes_dr_region() {
+        let config = ProjectConfig {
+            resource_name: "myapp".to_string(),
+            environments: vec!["dev".to_string()],
+            additional_resources: vec!["keyvault".to_string(), "cache".to_string()],
+            multi_region: true,
+            dr_region: Some("westus3".to_string()),
+            ..Default::default()
+        };
+        let registry = SubscriptionRegistry::from_layers(&[(
+            "org".to_string(),
+            include_str!("../../config/subscriptions.yaml").to_string(),
+        )])
+        .unwrap();
+        let regions = |usage: &EnvironmentUsage, resource: &str| -> Vec<String> {
+            usage
+                .resources
+                .iter()
+                .filter(|usage| usage.resource == resource)
+                .map(|usage| usage.region.clone())
+                .collect()
+        };
+
+        let usage = usage_from_config(&config, &registry).unwrap();
+        assert_eq!(regions(&usage[0], "cache"), ["eastus2", "westus3"]);
+        assert_eq!(regions(&usage[0], "keyvault"), ["eastus2"]);
+
+        let stack_file = StackFile {
+            file_name: "Pulumi.dev.yaml".to_string(),
+            settings: serde_yaml::from_str(
+                "config:\n  myapp:env: dev\n  myapp:location: eastus2\n  myapp:regions: [eastus2]\n  myapp:drRegion: westus3\n",
+            )
+            .unwrap(),
+        };
+        let usage = stack_usage(&config, &stack_file).unwrap();
+        assert_eq!(regions(&usage, "cache"), ["eastus2", "westus3"]);
+        assert_eq!(regions(&usage, "keyvault"), ["eastus2"]);
+    }
 }
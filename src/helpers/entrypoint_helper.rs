@@ -2,16 +2,24 @@ use anyhow::Ok;
 use std::path::Path;
 use std::{env, fs};
 
-use crate::tui::app::ProjectConfig;
+use crate::helpers::snippet_catalog::{
+    exported_names, find_snippet, lint_credentials, resolve_in_dependency_order, ParameterSource,
+    SnippetDefinition,
+};
+use crate::tui::app::{OutputLayout, ProjectConfig, SecretMode};
 
 /// Default embedded Pulumi TypeScript template
 const TYPESCRIPT_TEMPLATE: &str = include_str!("../../snippets/pulumi.webstackvzn.ts");
-const DATABASE_CODE_TEMPLATE: &str = include_str!("../../snippets/database.ts");
-const STORAGE_CODE_TEMPLATE: &str = include_str!("../../snippets/storage.ts");
-const SERVICEBUS_CODE_TEMPLATE: &str = include_str!("../../snippets/service_bus.ts");
-const CACHE_CODE_TEMPLATE: &str = include_str!("../../snippets/cache.ts");
-const KEYVAULT_CODE_TEMPLATE: &str = include_str!("../../snippets/keyvault.ts");
-const REGISTRY_CODE_TEMPLATE: &str = include_str!("../../snippets/container-registry.ts");
+/// Entry point template used by the modular layout
+const MODULAR_INDEX_TEMPLATE: &str = include_str!("../../snippets/pulumi.modular.ts");
+/// Shared context type imported by every resource module
+const CONTEXT_TEMPLATE: &str = include_str!("../../snippets/context.ts");
+/// Wrapper that turns a snippet into a factory function for the modular layout
+const RESOURCE_MODULE_TEMPLATE: &str = include_str!("../../snippets/resource-module.ts");
+/// Entry point template used by the component layout
+const COMPONENT_INDEX_TEMPLATE: &str = include_str!("../../snippets/pulumi.component.ts");
+/// `ComponentResource` class wrapping every selected resource
+const COMPONENT_TEMPLATE: &str = include_str!("../../snippets/component.ts");
 
 pub async fn create_entry_point(
     project_config: &ProjectConfig,
@@ -34,12 +42,18 @@ pub async fn create_entry_point(
         fs::create_dir_all(&output_dir)?;
     }
 
-    // Replace placeholders in the template
-    let mut modified_index =
-        TYPESCRIPT_TEMPLATE.replace("{{project_name}}", &project_config.resource_name);
+    let final_index_file = match project_config.layout {
+        OutputLayout::SingleFile => {
+            // Replace placeholders in the template
+            let mut modified_index =
+                TYPESCRIPT_TEMPLATE.replace("{{project_name}}", &project_config.resource_name);
+            handle_additional_resources(&mut modified_index, project_config)?
+        }
+        OutputLayout::Modular => write_resource_modules(project_config, &output_dir)?,
+        OutputLayout::Component => write_component(project_config, &output_dir)?,
+    };
 
-    let final_index_file =
-        handle_additional_resources(&mut modified_index, &project_config.additional_resources)?;
+    lint_credentials(&final_index_file, "index.ts")?;
 
     // Define the output file path
     let index_out_path = format!("{}/index.ts", &output_dir);
@@ -50,40 +64,341 @@ pub async fn create_entry_point(
 
     println!(
         "index.ts created successfully with {} characters!",
-        &modified_index.len()
+        &final_index_file.len()
     );
     Ok(())
 }
 
 fn handle_additional_resources(
     modified_index: &mut String,
-    additional_resources: &[String],
+    project_config: &ProjectConfig,
 ) -> Result<String, anyhow::Error> {
-    additional_resources
-        .iter()
-        .for_each(|res: &String| match res.to_lowercase().as_str() {
-            "container registry" => modified_index.push_str(&format!("{}", REGISTRY_CODE_TEMPLATE)),
-            "database" => modified_index.push_str(&format!("{}", DATABASE_CODE_TEMPLATE)),
-            "cache" => modified_index.push_str(&format!("{}", CACHE_CODE_TEMPLATE)),
-            "storage" => modified_index.push_str(&format!("{}", STORAGE_CODE_TEMPLATE)),
-            "service bus" => modified_index.push_str(&format!("{}", SERVICEBUS_CODE_TEMPLATE)),
-            "keyvault" => modified_index.push_str(&format!("{}", KEYVAULT_CODE_TEMPLATE)),
-            _ => (),
-        });
+    resolve_in_dependency_order(
+        &project_config.additional_resources,
+        project_config.secret_mode,
+    )?
+    .iter()
+    .for_each(|def| {
+        modified_index.push_str(&def.render_for(ParameterSource::Config, project_config))
+    });
+    if generates_secrets(project_config)? {
+        *modified_index = with_random_import(modified_index);
+    }
     Ok(modified_index.to_string())
 }
 
+/// Whether the generated code creates random passwords, and so needs `@pulumi/random`.
+fn generates_secrets(project_config: &ProjectConfig) -> Result<bool, anyhow::Error> {
+    Ok(project_config.secret_mode == SecretMode::KeyVault
+        && resolve_in_dependency_order(
+            &project_config.additional_resources,
+            project_config.secret_mode,
+        )?
+        .iter()
+        .any(|def| !def.secrets.is_empty()))
+}
+
+/// Adds the `@pulumi/random` import below the `@pulumi/azure-native` import.
+fn with_random_import(code: &str) -> String {
+    let azure_import = "import * as azure from \"@pulumi/azure-native\";\n";
+    code.replacen(
+        azure_import,
+        &format!(
+            "{}// @ts-ignore\nimport * as random from \"@pulumi/random\";\n",
+            azure_import
+        ),
+        1,
+    )
+}
+
+/// Writes one `resources/<module>.ts` file per selected resource plus the shared
+/// `resources/context.ts`, and returns the generated `index.ts` that wires them together.
+fn write_resource_modules(
+    project_config: &ProjectConfig,
+    output_dir: &str,
+) -> Result<String, anyhow::Error> {
+    let resources_dir = format!("{}/resources", output_dir);
+    fs::create_dir_all(&resources_dir)?;
+    fs::write(format!("{}/context.ts", resources_dir), CONTEXT_TEMPLATE)?;
+
+    let mut module_imports = String::new();
+    let mut module_calls = String::new();
+    let mut module_exports = String::new();
+
+    for def in resolve_in_dependency_order(
+        &project_config.additional_resources,
+        project_config.secret_mode,
+    )? {
+        let module_path = format!("{}/{}.ts", resources_dir, def.module_name);
+        let module = render_resource_module(def, project_config)?;
+        lint_credentials(&module, &format!("resources/{}.ts", def.module_name))?;
+        println!("Writing to: {}", &module_path);
+        fs::write(&module_path, module)?;
+
+        let instance = def.instance_name();
+        module_imports.push_str(&format!(
+            "import {{ {} }} from \"./resources/{}\";\n",
+            def.factory_name, def.module_name
+        ));
+        let dependency_args: String = dependency_snippets(def, project_config)?
+            .iter()
+            .map(|dep| format!(", {}", dep.instance_name()))
+            .collect();
+        module_calls.push_str(&format!(
+            "const {} = {}(ctx{});\n",
+            instance, def.factory_name, dependency_args
+        ));
+        for output in exported_names(def.template) {
+            module_exports.push_str(&format!(
+                "export const {} = {}.{};\n",
+                output, instance, output
+            ));
+        }
+    }
+
+    Ok(MODULAR_INDEX_TEMPLATE
+        .replace("{{project_name}}", &project_config.resource_name)
+        .replace("{{module_imports}}", &module_imports)
+        .replace("{{module_calls}}", &module_calls)
+        .replace("{{module_exports}}", &module_exports))
+}
+
+/// Wraps a snippet in an exported factory function that receives the shared
+/// `ResourceContext`, plus the outputs of each snippet it depends on, and returns
+/// the snippet's outputs.
+fn render_resource_module(
+    def: &SnippetDefinition,
+    project_config: &ProjectConfig,
+) -> Result<String, anyhow::Error> {
+    let mut dependency_imports = String::new();
+    let mut dependency_params = String::new();
+    let mut dependency_locals = String::new();
+    for dep in dependency_snippets(def, project_config)? {
+        let instance = dep.instance_name();
+        dependency_imports.push_str(&format!(
+            "import {{ {} }} from \"./{}\";\n",
+            dep.factory_name, dep.module_name
+        ));
+        dependency_params.push_str(&format!(
+            ", {}: ReturnType<typeof {}>",
+            instance, dep.factory_name
+        ));
+        dependency_locals.push_str(&format!(
+            "    const {{ {} }} = {};\n",
+            exported_names(dep.template).join(", "),
+            instance
+        ));
+    }
+
+    let module = RESOURCE_MODULE_TEMPLATE
+        .replace("{{factory_name}}", def.factory_name)
+        .replace("{{dependency_imports}}", &dependency_imports)
+        .replace("{{dependency_params}}", &dependency_params)
+        .replace("{{dependency_locals}}", &dependency_locals)
+        .replace(
+            "{{body}}",
+            &indent_snippet_body(&def.render_for(ParameterSource::Config, project_config), 4),
+        )
+        .replace("{{outputs}}", &exported_names(def.template).join(", "));
+
+    Ok(
+        if def.secrets.is_empty() || project_config.secret_mode != SecretMode::KeyVault {
+            module
+        } else {
+            with_random_import(&module)
+        },
+    )
+}
+
+/// Catalog entries of the snippets `def` depends on in this project.
+fn dependency_snippets(
+    def: &SnippetDefinition,
+    project_config: &ProjectConfig,
+) -> Result<Vec<&'static SnippetDefinition>, anyhow::Error> {
+    def.dependencies(project_config.secret_mode)
+        .iter()
+        .map(|dep| {
+            find_snippet(dep).ok_or_else(|| anyhow::anyhow!("Unknown snippet dependency '{}'", dep))
+        })
+        .collect()
+}
+
+/// Writes `component.ts`, a `ComponentResource` class named after the project that
+/// creates every selected resource as its child, and returns the `index.ts` that
+/// instantiates it from stack config.
+fn write_component(
+    project_config: &ProjectConfig,
+    output_dir: &str,
+) -> Result<String, anyhow::Error> {
+    let class_name = component_class_name(&project_config.resource_name);
+
+    let mut args_interfaces = String::new();
+    let mut args_fields = String::new();
+    let mut output_fields = String::new();
+    let mut body = String::new();
+    let mut output_assignments = String::new();
+    let mut registered_outputs = String::new();
+    let mut component_args = String::new();
+    let mut component_exports = String::new();
+
+    for def in resolve_in_dependency_order(
+        &project_config.additional_resources,
+        project_config.secret_mode,
+    )? {
+        let instance = def.instance_name();
+        let args_type = format!("{}Args", def.factory_name.trim_start_matches("create"));
+
+        args_interfaces.push_str(&format!("\nexport interface {} {{\n", args_type));
+        component_args.push_str(&format!("    {}: {{\n", instance));
+        for parameter in def.parameters {
+            args_interfaces.push_str(&format!(
+                "    /** {} */\n    {}?: pulumi.Input<{}>;\n",
+                parameter.description,
+                parameter.name,
+                parameter.param_type.ts_type()
+            ));
+            component_args.push_str(&format!(
+                "        {}: config.{}(\"{}\"),\n",
+                parameter.name,
+                parameter.param_type.config_getter(),
+                def.config_key(parameter.name)
+            ));
+        }
+        if project_config.secret_mode == SecretMode::Placeholder {
+            for secret in def.secrets {
+                args_interfaces.push_str(&format!(
+                    "    /** {} */\n    {}?: pulumi.Input<string>;\n",
+                    secret.description, secret.name
+                ));
+                component_args.push_str(&format!(
+                    "        {}: config.requireSecret(\"{}\"),\n",
+                    secret.name,
+                    def.config_key(secret.name)
+                ));
+            }
+        }
+        args_interfaces.push_str("}\n");
+        component_args.push_str("    },\n");
+        args_fields.push_str(&format!("    {}?: {};\n", instance, args_type));
+
+        body.push_str(&indent_snippet_body(
+            &component_logical_names(
+                &def.render_for(ParameterSource::ComponentArgs, project_config),
+            ),
+            8,
+        ));
+        body.push('\n');
+
+        for output in exported_names(def.template) {
+            output_fields.push_str(&format!(
+                "    public readonly {}: pulumi.Output<any>;\n",
+                output
+            ));
+            output_assignments.push_str(&format!("        this.{} = {};\n", output, output));
+            registered_outputs.push_str(&format!("            {}: this.{},\n", output, output));
+            component_exports.push_str(&format!("export const {} = app.{};\n", output, output));
+        }
+    }
+
+    let component = COMPONENT_TEMPLATE
+        .replace("{{project_name}}", &project_config.resource_name)
+        .replace("{{class_name}}", &class_name)
+        .replace("{{args_interfaces}}", &args_interfaces)
+        .replace("{{args_fields}}", &args_fields)
+        .replace("{{output_fields}}", &output_fields)
+        .replace("{{body}}", &body)
+        .replace("{{output_assignments}}", &output_assignments)
+        .replace("{{registered_outputs}}", &registered_outputs);
+    let component = if generates_secrets(project_config)? {
+        with_random_import(&component)
+    } else {
+        component
+    };
+    lint_credentials(&component, "component.ts")?;
+
+    let component_path = format!("{}/component.ts", output_dir);
+    println!("Writing to: {}", &component_path);
+    fs::write(&component_path, component)?;
+
+    Ok(COMPONENT_INDEX_TEMPLATE
+        .replace("{{project_name}}", &project_config.resource_name)
+        .replace("{{class_name}}", &class_name)
+        .replace("{{component_args}}", &component_args)
+        .replace("{{component_exports}}", &component_exports))
+}
+
+/// Turns a resource name such as "my-web_app" into a class name such as "MyWebApp".
+fn component_class_name(resource_name: &str) -> String {
+    let class_name: String = resource_name
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect::<String>(),
+                None => String::new(),
+            }
+        })
+        .collect();
+
+    match class_name.chars().next() {
+        Some(first) if first.is_alphabetic() => class_name,
+        _ => format!("App{}", class_name),
+    }
+}
+
+/// Prefixes the logical name of every resource a snippet creates with the component's
+/// `name`, so several instances of the component can live in one stack without
+/// duplicate URNs.
+fn component_logical_names(snippet: &str) -> String {
+    let mut rendered = String::new();
+    for line in snippet.lines() {
+        let line = match line.split_once("new ") {
+            Some((head, rest)) => match rest.split_once('(') {
+                Some((class, args)) => {
+                    let args = if let Some(args) = args.strip_prefix('"') {
+                        let (logical_name, tail) = args.split_once('"').unwrap_or((args, ""));
+                        format!("naming(\"{}\"){}", logical_name, tail)
+                    } else if let Some(args) = args.strip_prefix('`') {
+                        format!("`${{name}}-{}", args)
+                    } else {
+                        args.to_string()
+                    };
+                    format!("{}new {}({}", head, class, args)
+                }
+                None => line.to_string(),
+            },
+            None => line.to_string(),
+        };
+        rendered.push_str(&line);
+        rendered.push('\n');
+    }
+    rendered
+}
+
+/// Indents a rendered snippet so it can be placed inside a function body, turning
+/// its top-level `export const` declarations into plain locals.
+fn indent_snippet_body(snippet: &str, width: usize) -> String {
+    let mut body = String::new();
+    for line in snippet.trim().lines() {
+        let line = line
+            .strip_prefix("export const ")
+            .map_or_else(|| line.to_string(), |rest| format!("const {}", rest));
+        if line.trim().is_empty() {
+            body.push('\n');
+        } else {
+            body.push_str(&format!("{:width$}{}\n", "", line, width = width));
+        }
+    }
+    body
+}
+
 pub fn generate_resource_snippet(
     resource: &str,
     name: Option<&str>,
 ) -> Result<String, anyhow::Error> {
-    let raw_snippet = match resource.to_lowercase().as_str() {
-        "containerregistry" => Ok(REGISTRY_CODE_TEMPLATE.to_string()),
-        "database" => Ok(DATABASE_CODE_TEMPLATE.to_string()),
-        "cache" => Ok(CACHE_CODE_TEMPLATE.to_string()),
-        "storage" => Ok(STORAGE_CODE_TEMPLATE.to_string()),
-        "servicebus" => Ok(SERVICEBUS_CODE_TEMPLATE.to_string()),
-        "keyvault" => Ok(KEYVAULT_CODE_TEMPLATE.to_string()),
+    let raw_snippet = match find_snippet(resource) {
+        Some(def) => Ok(def.render(ParameterSource::Config)),
         _ => Ok(TYPESCRIPT_TEMPLATE.to_string()),
     };
     match name {
@@ -95,6 +410,14 @@ pub fn generate_resource_snippet(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::helpers::snippet_catalog::SNIPPET_CATALOG;
+
+    /// Renders a catalog snippet the way the single-file layout does.
+    fn rendered(resource: &str) -> String {
+        find_snippet(resource)
+            .unwrap()
+            .render(ParameterSource::Config)
+    }
     use tempfile::tempdir;
 
     #[tokio::test]
@@ -105,12 +428,17 @@ mod tests {
             environments: vec!["dev".to_string(), "prod".to_string()],
             additional_resources: vec!["database".to_string(), "keyvault".to_string()],
             owner_email: "".to_string(),
+            ..Default::default()
         };
         let temp_dir = tempdir()?; // Create a temporary directory
         let output_dir = temp_dir.path().to_str().unwrap(); // Convert path to a string
         let expected_file_path = format!("{}/index.ts", output_dir);
-        let expected_content =
-            TYPESCRIPT_TEMPLATE.replace("{{project_name}}", &config.resource_name);
+        let expected_content = format!(
+            "{}{}{}",
+            TYPESCRIPT_TEMPLATE.replace("{{project_name}}", &config.resource_name),
+            rendered("database"),
+            rendered("keyvault")
+        );
 
         // Act
         create_entry_point(&config, Some(output_dir)).await?;
@@ -147,12 +475,17 @@ mod tests {
             environments: vec!["dev".to_string(), "prod".to_string()],
             additional_resources: vec!["database".to_string(), "keyvault".to_string()],
             owner_email: "".to_string(),
+            ..Default::default()
         };
         let temp_dir = tempdir()?; // Create a temporary directory
         let output_dir = temp_dir.path().to_str().unwrap(); // Convert path to a string
         let expected_file_path = format!("{}/index.ts", output_dir);
-        let expected_content =
-            TYPESCRIPT_TEMPLATE.replace("{{project_name}}", &config.resource_name);
+        let expected_content = format!(
+            "{}{}{}",
+            TYPESCRIPT_TEMPLATE.replace("{{project_name}}", &config.resource_name),
+            rendered("database"),
+            rendered("keyvault")
+        );
 
         // Act
         create_entry_point(&config, Some(output_dir)).await?;
@@ -185,26 +518,218 @@ mod tests {
     async fn test_handle_additional_resources() -> Result<(), anyhow::Error> {
         // Arrange
         let mut modified_index = TYPESCRIPT_TEMPLATE.to_string();
-        let additional_resources = vec![
-            "container registry".to_string(),
-            "database".to_string(),
-            "cache".to_string(),
-            "storage".to_string(),
-            "service bus".to_string(),
-            "keyvault".to_string(),
-        ];
+        let config = ProjectConfig {
+            additional_resources: vec![
+                "container registry".to_string(),
+                "database".to_string(),
+                "cache".to_string(),
+                "storage".to_string(),
+                "service bus".to_string(),
+                "keyvault".to_string(),
+            ],
+            ..Default::default()
+        };
+
+        // Act
+        let final_index = handle_additional_resources(&mut modified_index, &config)?;
+
+        // Assert
+        assert!(final_index.contains(&rendered("container registry")));
+        assert!(final_index.contains(&rendered("database")));
+        assert!(final_index.contains(&rendered("cache")));
+        assert!(final_index.contains(&rendered("storage")));
+        assert!(final_index.contains(&rendered("service bus")));
+        assert!(final_index.contains(&rendered("keyvault")));
+        assert!(!final_index.contains("@pulumi/random"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_create_entry_point_with_key_vault_secrets() -> Result<(), anyhow::Error> {
+        // Arrange
+        let config = ProjectConfig {
+            resource_name: "test_app".to_string(),
+            additional_resources: vec!["database".to_string()],
+            layout: OutputLayout::Modular,
+            secret_mode: SecretMode::KeyVault,
+            ..Default::default()
+        };
+        let temp_dir = tempdir()?;
+        let output_dir = temp_dir.path().to_str().unwrap();
+
+        // Act
+        create_entry_point(&config, Some(output_dir)).await?;
+
+        // Assert
+        let index = fs::read_to_string(format!("{}/index.ts", output_dir))?;
+        assert!(index.contains("const keyvault = createKeyvault(ctx);"));
+        assert!(index.contains("const database = createDatabase(ctx, keyvault);"));
+
+        let database_module = fs::read_to_string(format!("{}/resources/database.ts", output_dir))?;
+        assert!(database_module.contains("import * as random from \"@pulumi/random\";"));
+        assert!(database_module.contains("import { createKeyvault } from \"./keyvault\";"));
+        assert!(database_module.contains(
+            "export function createDatabase(ctx: ResourceContext, keyvault: ReturnType<typeof createKeyvault>)"
+        ));
+        assert!(database_module.contains("    const { keyVaultName, keyVaultUri } = keyvault;"));
+        assert!(database_module
+            .contains("administratorLoginPassword: databaseAdministratorLoginPassword.result,"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_create_entry_point_with_modular_layout() -> Result<(), anyhow::Error> {
+        // Arrange
+        let config = ProjectConfig {
+            resource_name: "test_app".to_string(),
+            environments: vec!["dev".to_string()],
+            additional_resources: vec!["storage".to_string(), "keyvault".to_string()],
+            owner_email: "".to_string(),
+            layout: OutputLayout::Modular,
+            ..Default::default()
+        };
+        let temp_dir = tempdir()?;
+        let output_dir = temp_dir.path().to_str().unwrap();
+
+        // Act
+        create_entry_point(&config, Some(output_dir)).await?;
+
+        // Assert
+        let index = fs::read_to_string(format!("{}/index.ts", output_dir))?;
+        assert!(index.contains("import { createStorage } from \"./resources/storage\";"));
+        assert!(index.contains("const storage = createStorage(ctx);"));
+        assert!(index.contains("export const keyVaultUri = keyvault.keyVaultUri;"));
+        assert!(
+            !index.contains("{{"),
+            "index.ts has unresolved placeholders"
+        );
+
+        let storage_module = fs::read_to_string(format!("{}/resources/storage.ts", output_dir))?;
+        assert!(storage_module.contains("export function createStorage(ctx: ResourceContext)"));
+        assert!(storage_module.contains("return { storageAccountName, containerName };"));
+        assert!(!storage_module.contains("export const"));
+        assert!(Path::new(&format!("{}/resources/context.ts", output_dir)).exists());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_create_entry_point_with_multi_region_modules() -> Result<(), anyhow::Error> {
+        // Arrange
+        let config = ProjectConfig {
+            resource_name: "test_app".to_string(),
+            additional_resources: vec!["storage".to_string(), "keyvault".to_string()],
+            layout: OutputLayout::Modular,
+            multi_region: true,
+            ..Default::default()
+        };
+        let temp_dir = tempdir()?;
+        let output_dir = temp_dir.path().to_str().unwrap();
+
+        // Act
+        create_entry_point(&config, Some(output_dir)).await?;
+
+        // Assert
+        let index = fs::read_to_string(format!("{}/index.ts", output_dir))?;
+        assert!(index.contains("const location = config.require(\"location\");"));
+        assert!(index.contains("    regions,\n"));
+
+        let storage_module = fs::read_to_string(format!("{}/resources/storage.ts", output_dir))?;
+        assert!(storage_module.contains("const storageByRegion = regions.map((region) => {"));
+        assert!(storage_module.contains(
+            "const storageAccountName = pulumi.all(storageByRegion.map((r) => r.storageAccountName));"
+        ));
+        assert!(storage_module.contains("return { storageAccountName, containerName };"));
+
+        let keyvault_module = fs::read_to_string(format!("{}/resources/keyvault.ts", output_dir))?;
+        assert!(!keyvault_module.contains("ByRegion"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate_resource_snippet_normalizes_resource_name() -> Result<(), anyhow::Error> {
+        let snippet = generate_resource_snippet("Service Bus", None)?;
+        assert_eq!(snippet, rendered("servicebus"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_create_entry_point_with_component_layout() -> Result<(), anyhow::Error> {
+        // Arrange
+        let config = ProjectConfig {
+            resource_name: "my-web_app".to_string(),
+            environments: vec!["dev".to_string()],
+            additional_resources: vec!["cache".to_string(), "storage".to_string()],
+            owner_email: "".to_string(),
+            layout: OutputLayout::Component,
+            ..Default::default()
+        };
+        let temp_dir = tempdir()?;
+        let output_dir = temp_dir.path().to_str().unwrap();
 
         // Act
-        let final_index = handle_additional_resources(&mut modified_index, &additional_resources)?;
+        create_entry_point(&config, Some(output_dir)).await?;
 
         // Assert
-        assert!(final_index.contains(REGISTRY_CODE_TEMPLATE));
-        assert!(final_index.contains(DATABASE_CODE_TEMPLATE));
-        assert!(final_index.contains(CACHE_CODE_TEMPLATE));
-        assert!(final_index.contains(STORAGE_CODE_TEMPLATE));
-        assert!(final_index.contains(SERVICEBUS_CODE_TEMPLATE));
-        assert!(final_index.contains(KEYVAULT_CODE_TEMPLATE));
+        let component = fs::read_to_string(format!("{}/component.ts", output_dir))?;
+        assert!(component.contains("export class MyWebApp extends pulumi.ComponentResource"));
+        assert!(component.contains("export interface CacheArgs {"));
+        assert!(component.contains("    capacity?: pulumi.Input<number>;"));
+        assert!(component.contains("    cache?: CacheArgs;"));
+        assert!(component.contains("name: args.cache?.skuName ?? \"Standard\","));
+        assert!(component.contains("            redisHostName: this.redisHostName,"));
+        assert!(!component.contains("export const"));
+        assert!(
+            !component.contains("{{"),
+            "component.ts has unresolved placeholders"
+        );
+        // Every child is named after the component instance
+        assert!(component.contains("new azure.cache.Redis(naming(\"myRedisCache\"), {"));
+        assert!(!component.contains("Redis(\"myRedisCache\""));
+        assert!(!component.contains("StorageAccount(\"myStorageAccount\""));
+        assert!(!component.contains("BlobContainer(\"myContainer\""));
+
+        let index = fs::read_to_string(format!("{}/index.ts", output_dir))?;
+        assert!(index.contains("const app = new MyWebApp(\"my-web_app\", {"));
+        assert!(index.contains("        capacity: config.getNumber(\"cacheCapacity\"),"));
+        assert!(!index.contains("requireSecret"));
+        assert!(index.contains("export const containerName = app.containerName;"));
+        assert!(
+            !index.contains("{{"),
+            "index.ts has unresolved placeholders"
+        );
 
         Ok(())
     }
+
+    #[test]
+    fn test_component_logical_names() {
+        let config = ProjectConfig {
+            multi_region: true,
+            secret_mode: SecretMode::KeyVault,
+            ..Default::default()
+        };
+        for def in SNIPPET_CATALOG {
+            let rendered =
+                component_logical_names(&def.render_for(ParameterSource::ComponentArgs, &config));
+            for line in rendered.lines().filter(|line| line.contains("new ")) {
+                assert!(
+                    line.contains("(naming(\"") || line.contains("(`${name}-"),
+                    "{}: logical name is not prefixed in `{}`",
+                    def.key,
+                    line
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_component_class_name() {
+        assert_eq!(component_class_name("my-web_app"), "MyWebApp");
+        assert_eq!(component_class_name("billing"), "Billing");
+        assert_eq!(component_class_name("1st-app"), "App1stApp");
+    }
 }
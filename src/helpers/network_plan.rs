@@ -0,0 +1,502 @@
+use anyhow::{anyhow, Error};
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fmt;
+use std::fs;
+use std::net::Ipv4Addr;
+use std::path::{Path, PathBuf};
+
+use crate::helpers::snippet_catalog::find_snippet;
+use crate::helpers::subscription_registry::{project_config_dir, AzureSubscription};
+use crate::tui::app::ProjectConfig;
+
+/// File name of the allocation registry inside the project config directory
+const ALLOCATIONS_FILE_NAME: &str = "network.yaml";
+
+/// An IPv4 network such as `10.1.0.0/24`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ipv4Cidr {
+    network: u32,
+    prefix: u8,
+}
+
+impl Ipv4Cidr {
+    /// Parses a CIDR, rejecting host bits outside the prefix (e.g. `10.1.0.1/24`).
+    pub fn parse(value: &str) -> Result<Self, Error> {
+        let invalid = || anyhow!("'{}' is not an IPv4 CIDR", value);
+        let (addr, prefix) = value.split_once('/').ok_or_else(invalid)?;
+        let addr: Ipv4Addr = addr.parse().map_err(|_| invalid())?;
+        let prefix: u8 = prefix.parse().map_err(|_| invalid())?;
+        if prefix > 32 {
+            return Err(invalid());
+        }
+
+        let cidr = Self {
+            network: u32::from(addr) & Self::mask(prefix),
+            prefix,
+        };
+        if cidr.network != u32::from(addr) {
+            return Err(anyhow!(
+                "'{}' has host bits set, did you mean '{}'?",
+                value,
+                cidr
+            ));
+        }
+        Ok(cidr)
+    }
+
+    fn mask(prefix: u8) -> u32 {
+        u32::MAX.checked_shl(32 - prefix as u32).unwrap_or(0)
+    }
+
+    /// Number of addresses in the network.
+    fn size(&self) -> u64 {
+        1u64 << (32 - self.prefix)
+    }
+
+    fn last(&self) -> u32 {
+        (self.network as u64 + self.size() - 1) as u32
+    }
+
+    pub fn contains(&self, other: &Ipv4Cidr) -> bool {
+        self.prefix <= other.prefix && self.network <= other.network && other.last() <= self.last()
+    }
+
+    pub fn overlaps(&self, other: &Ipv4Cidr) -> bool {
+        self.network <= other.last() && other.network <= self.last()
+    }
+
+    /// Blocks of the given prefix length inside this network, in address order.
+    fn subnets(&self, prefix: u8) -> impl Iterator<Item = Ipv4Cidr> {
+        let step = 1u64 << (32 - prefix);
+        let count = if prefix < self.prefix {
+            0
+        } else {
+            self.size() / step
+        };
+        let network = self.network as u64;
+        (0..count).map(move |index| Ipv4Cidr {
+            network: (network + index * step) as u32,
+            prefix,
+        })
+    }
+}
+
+impl fmt::Display for Ipv4Cidr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", Ipv4Addr::from(self.network), self.prefix)
+    }
+}
+
+/// What a subnet is used for. Each app gets one subnet per role it needs.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum SubnetRole {
+    /// The application's own compute, e.g. App Service VNet integration.
+    App,
+    /// Resources injected into a delegated subnet, such as a PostgreSQL flexible server.
+    Data,
+    /// Private endpoints of PaaS resources such as Key Vault or Storage.
+    PrivateEndpoints,
+}
+
+impl SubnetRole {
+    /// Every role, in allocation order (largest subnets first).
+    pub const ALL: &'static [SubnetRole] = &[
+        SubnetRole::App,
+        SubnetRole::Data,
+        SubnetRole::PrivateEndpoints,
+    ];
+
+    /// Name of the role in stack config and the allocation registry.
+    pub fn name(&self) -> &'static str {
+        match self {
+            SubnetRole::App => "app",
+            SubnetRole::Data => "data",
+            SubnetRole::PrivateEndpoints => "privateEndpoints",
+        }
+    }
+
+    /// Prefix length of the subnet handed out for the role.
+    pub fn prefix_len(&self) -> u8 {
+        match self {
+            SubnetRole::App => 24,
+            SubnetRole::Data => 26,
+            SubnetRole::PrivateEndpoints => 27,
+        }
+    }
+}
+
+impl fmt::Display for SubnetRole {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// A subnet handed out to one role of one app in one environment.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SubnetAllocation {
+    /// Id of the subscription whose virtual network the subnet is in.
+    pub subscription: String,
+    pub env: String,
+    pub app: String,
+    pub role: SubnetRole,
+    pub cidr: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+struct AllocationFile {
+    #[serde(default)]
+    allocations: Vec<SubnetAllocation>,
+}
+
+/// Subnets already handed out, read from `.pulumimi/network.yaml` so that apps
+/// generated from the same workspace never get overlapping address ranges.
+#[derive(Debug, Default)]
+pub struct NetworkRegistry {
+    path: PathBuf,
+    allocations: Vec<SubnetAllocation>,
+}
+
+impl NetworkRegistry {
+    /// Loads the allocation registry of the current directory, if it exists.
+    pub fn load() -> Result<Self, Error> {
+        Self::load_from(&project_config_dir(&env::current_dir()?).join(ALLOCATIONS_FILE_NAME))
+    }
+
+    /// Loads an allocation registry file; a missing file is an empty registry.
+    pub fn load_from(path: &Path) -> Result<Self, Error> {
+        let file: AllocationFile = match path.exists() {
+            true => serde_yaml::from_str(&fs::read_to_string(path)?)
+                .map_err(|err| anyhow!("Invalid network registry {}: {}", path.display(), err))?,
+            false => AllocationFile::default(),
+        };
+
+        let mut registry = Self {
+            path: path.to_path_buf(),
+            allocations: Vec::new(),
+        };
+        for allocation in file.allocations {
+            registry
+                .check_free(
+                    &allocation.subscription,
+                    &allocation.env,
+                    &allocation.app,
+                    Ipv4Cidr::parse(&allocation.cidr)?,
+                )
+                .map_err(|err| anyhow!("Invalid network registry {}: {}", path.display(), err))?;
+            registry.allocations.push(allocation);
+        }
+        Ok(registry)
+    }
+
+    /// Allocations in the same subscription as an app's environment, other than its own.
+    /// Environments that share a subscription share its virtual network.
+    fn others<'a>(
+        &'a self,
+        subscription: &'a str,
+        env: &'a str,
+        app: &'a str,
+    ) -> impl Iterator<Item = &'a SubnetAllocation> {
+        self.allocations.iter().filter(move |allocation| {
+            allocation.subscription == subscription
+                && !(allocation.env == env && allocation.app == app)
+        })
+    }
+
+    /// Fails if a subnet overlaps one recorded for another app or environment in the
+    /// same subscription.
+    fn check_free(
+        &self,
+        subscription: &str,
+        env: &str,
+        app: &str,
+        cidr: Ipv4Cidr,
+    ) -> Result<(), Error> {
+        for allocation in self.others(subscription, env, app) {
+            if Ipv4Cidr::parse(&allocation.cidr)?.overlaps(&cidr) {
+                return Err(anyhow!(
+                    "Subnet {} of environment '{}' overlaps {} allocated to the {} subnet of '{}' in environment '{}'",
+                    cidr,
+                    env,
+                    allocation.cidr,
+                    allocation.role,
+                    allocation.app,
+                    allocation.env
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Records the subnets of a plan, replacing every earlier allocation of the app in
+    /// the environment, so roles the app no longer needs are released.
+    pub fn record(&mut self, plan: &NetworkPlan) {
+        self.allocations.retain(|existing| {
+            !plan
+                .subnets
+                .iter()
+                .any(|allocation| existing.env == allocation.env && existing.app == allocation.app)
+        });
+        self.allocations.extend(plan.subnets.iter().cloned());
+    }
+
+    pub fn save(&self) -> Result<(), Error> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let yaml = serde_yaml::to_string(&AllocationFile {
+            allocations: self.allocations.clone(),
+        })?;
+        fs::write(&self.path, yaml.trim_start_matches("---").trim_start())?;
+        Ok(())
+    }
+}
+
+/// Subnets of one app in one environment.
+#[derive(Debug, PartialEq)]
+pub struct NetworkPlan {
+    pub subnets: Vec<SubnetAllocation>,
+}
+
+impl NetworkPlan {
+    pub fn cidrs(&self) -> Vec<&str> {
+        self.subnets
+            .iter()
+            .map(|allocation| allocation.cidr.as_str())
+            .collect()
+    }
+}
+
+/// Roles an app needs: its own subnet, plus the subnets its resources are placed in.
+pub fn subnet_roles(config: &ProjectConfig) -> Vec<SubnetRole> {
+    let needed: Vec<SubnetRole> = std::iter::once(SubnetRole::App)
+        .chain(
+            config
+                .additional_resources
+                .iter()
+                .filter_map(|key| find_snippet(key))
+                .map(|def| def.subnet_role),
+        )
+        .collect();
+    SubnetRole::ALL
+        .iter()
+        .copied()
+        .filter(|role| needed.contains(role))
+        .collect()
+}
+
+/// Hands out subnets for an environment from its subscription's address space.
+/// Subnets already recorded for the app are kept; new ones take the first free
+/// block that overlaps no allocation of another app or environment in the subscription.
+///
+/// Returns `None` when the subscription lists its subnets explicitly or has no
+/// address space; explicit subnets are still checked against the registry.
+pub fn plan_network(
+    config: &ProjectConfig,
+    azure_subscription: &AzureSubscription,
+    registry: &NetworkRegistry,
+) -> Result<Option<NetworkPlan>, Error> {
+    let subscription = &azure_subscription.id;
+    let env = &azure_subscription.env;
+    let app = &config.resource_name;
+
+    if !azure_subscription.virtual_subnets.is_empty() {
+        for subnet in &azure_subscription.virtual_subnets {
+            registry.check_free(subscription, env, app, Ipv4Cidr::parse(subnet)?)?;
+        }
+        return Ok(None);
+    }
+    let Some(address_space) = &azure_subscription.address_space else {
+        return Ok(None);
+    };
+    let space = Ipv4Cidr::parse(address_space)?;
+
+    let mut taken: Vec<Ipv4Cidr> = registry
+        .others(subscription, env, app)
+        .map(|allocation| Ipv4Cidr::parse(&allocation.cidr))
+        .collect::<Result<_, _>>()?;
+
+    let mut subnets = Vec::new();
+    for role in subnet_roles(config) {
+        let recorded = registry.allocations.iter().find(|allocation| {
+            allocation.env == *env && allocation.app == *app && allocation.role == role
+        });
+        let cidr = match recorded {
+            Some(allocation) => {
+                let cidr = Ipv4Cidr::parse(&allocation.cidr)?;
+                if !space.contains(&cidr) || taken.iter().any(|other| other.overlaps(&cidr)) {
+                    return Err(anyhow!(
+                        "Recorded {} subnet {} of '{}' does not fit address space {} of environment '{}'",
+                        role,
+                        cidr,
+                        app,
+                        space,
+                        env
+                    ));
+                }
+                cidr
+            }
+            None => space
+                .subnets(role.prefix_len())
+                .find(|candidate| !taken.iter().any(|other| other.overlaps(candidate)))
+                .ok_or_else(|| {
+                    anyhow!(
+                        "Address space {} of environment '{}' has no free /{} left for the {} subnet of '{}'",
+                        space,
+                        env,
+                        role.prefix_len(),
+                        role,
+                        app
+                    )
+                })?,
+        };
+        taken.push(cidr);
+        subnets.push(SubnetAllocation {
+            subscription: subscription.clone(),
+            env: env.clone(),
+            app: app.clone(),
+            role,
+            cidr: cidr.to_string(),
+        });
+    }
+
+    Ok(Some(NetworkPlan { subnets }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    fn subscription(address_space: Option<&str>, subnets: &[&str]) -> AzureSubscription {
+        AzureSubscription {
+            env: "dev".to_string(),
+            id: "1234".to_string(),
+            name: "dev01".to_string(),
+            tier: "bronze".to_string(),
+            location: "eastus2".to_string(),
+            address_space: address_space.map(str::to_string),
+            virtual_subnets: subnets.iter().map(|s| s.to_string()).collect(),
+            tags: BTreeMap::new(),
+            service_connection: "azure-dev01".to_string(),
+            variable_groups: Vec::new(),
+            backend_url: "https://api.pulumi.com".to_string(),
+            budget: None,
+        }
+    }
+
+    fn registry(yaml: &str) -> NetworkRegistry {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("network.yaml");
+        fs::write(&path, yaml).unwrap();
+        NetworkRegistry::load_from(&path).unwrap()
+    }
+
+    #[test]
+    fn test_parse_cidr() {
+        let cidr = Ipv4Cidr::parse("10.1.0.0/16").unwrap();
+        assert!(cidr.contains(&Ipv4Cidr::parse("10.1.255.0/24").unwrap()));
+        assert!(!cidr.contains(&Ipv4Cidr::parse("10.2.0.0/24").unwrap()));
+        assert!(cidr.overlaps(&Ipv4Cidr::parse("10.0.0.0/8").unwrap()));
+        assert_eq!(
+            Ipv4Cidr::parse("10.1.0.1/24").unwrap_err().to_string(),
+            "'10.1.0.1/24' has host bits set, did you mean '10.1.0.0/24'?"
+        );
+        assert!(Ipv4Cidr::parse("10.1.0.0/33").is_err());
+        assert!(Ipv4Cidr::parse("").is_err());
+    }
+
+    #[test]
+    fn test_plan_skips_allocations_of_other_apps() {
+        let mut config = ProjectConfig::default("myapp");
+        config.additional_resources = vec!["database".to_string(), "keyvault".to_string()];
+        let registry = registry(
+            "allocations:\n  - subscription: '1234'\n    env: dev\n    app: billing\n    role: app\n    cidr: 10.1.0.0/24\n  - subscription: '5678'\n    env: test\n    app: billing\n    role: app\n    cidr: 10.1.1.0/24\n",
+        );
+
+        let plan = plan_network(&config, &subscription(Some("10.1.0.0/22"), &[]), &registry)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(
+            plan.cidrs(),
+            vec!["10.1.1.0/24", "10.1.2.0/26", "10.1.2.64/27"]
+        );
+        assert_eq!(plan.subnets[1].role, SubnetRole::Data);
+
+        // Planning again after recording keeps the same subnets
+        let mut registry = registry;
+        registry.record(&plan);
+        let replanned = plan_network(&config, &subscription(Some("10.1.0.0/22"), &[]), &registry)
+            .unwrap()
+            .unwrap();
+        assert_eq!(replanned, plan);
+        assert_eq!(registry.allocations.len(), 5);
+
+        // Roles the app no longer needs are released
+        config.additional_resources.clear();
+        let plan = plan_network(&config, &subscription(Some("10.1.0.0/22"), &[]), &registry)
+            .unwrap()
+            .unwrap();
+        registry.record(&plan);
+        assert_eq!(registry.allocations.len(), 3);
+        assert_eq!(plan.cidrs(), vec!["10.1.1.0/24"]);
+    }
+
+    #[test]
+    fn test_environments_sharing_a_subscription_do_not_overlap() {
+        let mut config = ProjectConfig::default("myapp");
+        config.additional_resources.clear();
+        let registry = registry(
+            "allocations:\n  - subscription: '1234'\n    env: test\n    app: myapp\n    role: app\n    cidr: 10.1.0.0/24\n",
+        );
+
+        // dev is on the same subscription, so it gets the next block even for the same app
+        let plan = plan_network(&config, &subscription(Some("10.1.0.0/22"), &[]), &registry)
+            .unwrap()
+            .unwrap();
+        assert_eq!(plan.cidrs(), vec!["10.1.1.0/24"]);
+
+        let err =
+            plan_network(&config, &subscription(None, &["10.1.0.0/25"]), &registry).unwrap_err();
+        assert!(err.to_string().contains("in environment 'test'"));
+    }
+
+    #[test]
+    fn test_overlaps_and_exhaustion_are_rejected() {
+        let config = ProjectConfig::default("myapp");
+        let registry = registry(
+            "allocations:\n  - subscription: '1234'\n    env: dev\n    app: billing\n    role: app\n    cidr: 10.1.0.0/24\n",
+        );
+
+        let err =
+            plan_network(&config, &subscription(None, &["10.1.0.128/25"]), &registry).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Subnet 10.1.0.128/25 of environment 'dev' overlaps 10.1.0.0/24 allocated to the app subnet of 'billing' in environment 'dev'"
+        );
+
+        let err =
+            plan_network(&config, &subscription(Some("10.1.0.0/24"), &[]), &registry).unwrap_err();
+        assert!(err.to_string().contains("has no free /24 left"));
+
+        assert_eq!(
+            plan_network(&config, &subscription(None, &[]), &registry).unwrap(),
+            None
+        );
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("network.yaml");
+        fs::write(
+            &path,
+            "allocations:\n  - subscription: '1234'\n    env: dev\n    app: a\n    role: app\n    cidr: 10.1.0.0/24\n  - subscription: '1234'\n    env: test\n    app: b\n    role: data\n    cidr: 10.1.0.64/26\n",
+        )
+        .unwrap();
+        assert!(NetworkRegistry::load_from(&path)
+            .unwrap_err()
+            .to_string()
+            .contains("overlaps"));
+    }
+}
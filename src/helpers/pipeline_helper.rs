@@ -1,15 +1,297 @@
-use anyhow::Ok;
-use std::{env, fs};
+use anyhow::{anyhow, Ok};
+use std::path::Path;
+use std::{env, fs, iter};
 
-use crate::tui::app::ProjectConfig;
+use crate::helpers::config_helper::stack_name;
+use crate::helpers::pipeline_schema::{PipelineReferences, PipelineSchema};
+use crate::helpers::subscription_registry::{SubscriptionRegistry, TIERS};
+use crate::helpers::variable_group::{secret_inputs, variable_group_name, SecretInput};
+use crate::tui::app::{CiTarget, DriftNotify, ProjectConfig};
 
 /// Default embedded `azure-pipelines.yaml` template
 const PIPELINE_TEMPLATE: &str = include_str!("../../snippets/azure-pipelines.yaml");
 
-/// Creates a pipeline YAML file for the given app name, saving it in the specified or default directory.
+/// Default embedded `.github/workflows/infra.yml` template
+const GITHUB_WORKFLOW_TEMPLATE: &str = include_str!("../../snippets/github-workflow.yml");
+
+/// GitHub Actions job previewing every stack on pull requests, one matrix entry per stack.
+const GITHUB_PREVIEW_JOB: &str = "  preview:
+    name: Preview ${{ matrix.env }}
+    if: github.event_name == 'pull_request'
+    runs-on: ubuntu-latest
+    strategy:
+      fail-fast: false
+      matrix:
+        include:
+{{matrix}}    env:
+      ARM_USE_OIDC: 'true'
+      ARM_CLIENT_ID: ${{ secrets.AZURE_CLIENT_ID }}
+      ARM_TENANT_ID: ${{ secrets.AZURE_TENANT_ID }}
+      ARM_SUBSCRIPTION_ID: ${{ matrix.subscription-id }}
+      PULUMI_ACCESS_TOKEN: ${{ secrets.PULUMI_ACCESS_TOKEN }}
+    steps:
+{{steps}}";
+
+/// GitHub Actions job running `pulumi up` on one environment.
+const GITHUB_DEPLOY_JOB: &str = "  {{job}}:
+    name: Deploy {{env}}
+    if: github.event_name == 'push' && github.ref == 'refs/heads/main'
+{{needs}}    runs-on: ubuntu-latest
+    environment: {{env}}
+    concurrency: {{project_name}}-{{env}}
+    env:
+      ARM_USE_OIDC: 'true'
+      ARM_CLIENT_ID: ${{ secrets.AZURE_CLIENT_ID }}
+      ARM_TENANT_ID: ${{ secrets.AZURE_TENANT_ID }}
+      ARM_SUBSCRIPTION_ID: '{{subscription_id}}'
+      PULUMI_ACCESS_TOKEN: ${{ secrets.PULUMI_ACCESS_TOKEN }}
+    steps:
+{{steps}}";
+
+/// Default embedded `.gitlab-ci.yml` template
+const GITLAB_CI_TEMPLATE: &str = include_str!("../../snippets/gitlab-ci.yml");
+
+/// GitLab CI job previewing one stack on merge requests.
+const GITLAB_PREVIEW_JOB: &str = "preview:{{env}}:
+  extends: .pulumi
+  stage: preview
+  rules:
+    - if: $CI_PIPELINE_SOURCE == 'merge_request_event'
+  variables:
+    ARM_SUBSCRIPTION_ID: '{{subscription_id}}'
+  script:
+{{secrets}}    - pulumi preview --stack '{{stack}}' --non-interactive
+";
+
+/// GitLab CI job running `pulumi up` on one environment.
+const GITLAB_DEPLOY_JOB: &str = "deploy:{{env}}:
+  extends: .pulumi
+  stage: deploy-{{env}}
+  rules:
+    - if: $CI_PIPELINE_SOURCE == 'push' && $CI_COMMIT_BRANCH == $CI_DEFAULT_BRANCH
+{{when}}  resource_group: {{project_name}}-{{env}}
+  environment:
+    name: {{env}}
+    deployment_tier: {{deployment_tier}}
+  variables:
+    ARM_SUBSCRIPTION_ID: '{{subscription_id}}'
+  script:
+{{secrets}}    - pulumi up --stack '{{stack}}' --yes --non-interactive
+";
+
+/// GitLab deployment tier of each subscription tier, in the order of `TIERS`.
+const GITLAB_DEPLOYMENT_TIERS: &[&str] = &["development", "testing", "staging", "production"];
+
+/// Steps of a GitHub Actions job: OIDC login to the subscription, the secret inputs, then
+/// a Pulumi command.
+const GITHUB_PULUMI_STEPS: &str = "{{setup}}{{secrets}}      - uses: pulumi/actions@v6
+        with:
+          command: {{command}}
+          stack-name: {{stack}}
+";
+
+/// Steps of a GitHub Actions job that check out the project and log in to the subscription.
+const GITHUB_SETUP_STEPS: &str = "      - uses: actions/checkout@v4
+      - uses: actions/setup-node@v4
+        with:
+          node-version: ${{ env.NODE_VERSION }}
+      - run: npm install
+      - uses: azure/login@v2
+        with:
+          client-id: ${{ secrets.AZURE_CLIENT_ID }}
+          tenant-id: ${{ secrets.AZURE_TENANT_ID }}
+          subscription-id: {{subscription_id}}
+";
+
+/// GitHub Actions job checking every stack for drift on schedule, one matrix entry per stack.
+const GITHUB_DRIFT_JOB: &str = "  drift:
+    name: Drift ${{ matrix.env }}
+    if: github.event_name == 'schedule'
+    runs-on: ubuntu-latest
+    strategy:
+      fail-fast: false
+      matrix:
+        include:
+{{matrix}}    env:
+      ARM_USE_OIDC: 'true'
+      ARM_CLIENT_ID: ${{ secrets.AZURE_CLIENT_ID }}
+      ARM_TENANT_ID: ${{ secrets.AZURE_TENANT_ID }}
+      ARM_SUBSCRIPTION_ID: ${{ matrix.subscription-id }}
+      PULUMI_ACCESS_TOKEN: ${{ secrets.PULUMI_ACCESS_TOKEN }}
+    steps:
+{{setup}}      - uses: pulumi/actions@v6
+{{secrets}}      - run: pulumi refresh {{drift_args}} --stack '${{ matrix.stack }}' --non-interactive
+{{notify}}";
+
+/// GitHub Actions step emailing the owner when the drift check fails.
+const GITHUB_DRIFT_EMAIL_STEP: &str = "      - name: Email the owner
+        if: failure()
+        env:
+          CONNECTION_STRING: ${{ secrets.DRIFT_EMAIL_CONNECTION_STRING }}
+          SENDER: ${{ secrets.DRIFT_EMAIL_SENDER }}
+        run: |
+          az extension add --name communication --upgrade
+          az communication email send --connection-string \"$CONNECTION_STRING\" --sender \"$SENDER\" --to '{{owner}}' --subject \"Drift detected in {{project_name}} (${{ matrix.env }})\" --text \"pulumi refresh found changes made outside Pulumi: ${{ github.server_url }}/${{ github.repository }}/actions/runs/${{ github.run_id }}\"
+";
+
+/// GitLab CI job checking one stack for drift on schedule.
+const GITLAB_DRIFT_JOB: &str = "drift:{{env}}:
+  extends: .pulumi
+  stage: drift
+  rules:
+    - if: $CI_PIPELINE_SOURCE == 'schedule'
+  variables:
+    ARM_SUBSCRIPTION_ID: '{{subscription_id}}'
+  script:
+{{secrets}}    - pulumi refresh {{drift_args}} --stack '{{stack}}' --non-interactive
+";
+
+/// Arguments of the drift check's `pulumi refresh`: fail when the cloud differs from
+/// the stack's state, without changing it.
+const DRIFT_ARGS: &str = "--preview-only --expect-no-changes";
+
+/// Highest tier that deploys without manual approval when the project does not set one.
+pub const DEFAULT_APPROVAL_TIER: &str = "silver";
+
+/// Minutes a deployment waits for approval before it is rejected.
+const APPROVAL_TIMEOUT_MINUTES: u32 = 1440;
+
+/// One environment's `pulumi up` in the generated pipeline.
+#[derive(Debug, PartialEq)]
+pub struct DeployStage {
+    pub env: String,
+    /// Fully qualified stack name, e.g. `acme/myapp/prod`.
+    pub stack: String,
+    pub service_connection: String,
+    pub subscription_id: String,
+    pub tier: String,
+    /// Variable groups linked to the stage: the environment's generated group, then
+    /// the groups listed in the registry.
+    pub variable_groups: Vec<String>,
+    /// Whether the deployment waits for manual approval.
+    pub approval: bool,
+}
+
+impl DeployStage {
+    /// Identifier of the stage, e.g. `Deploy_pre_prod`.
+    fn id(&self) -> String {
+        format!("Deploy_{}", self.env.replace('-', "_"))
+    }
+
+    /// Identifier of the GitHub Actions job, e.g. `deploy-pre-prod`.
+    fn job_id(&self) -> String {
+        format!("deploy-{}", self.env.replace('_', "-"))
+    }
+}
+
+/// Works out the deployment stages of the selected environments, in promotion
+/// order (the order of the subscription registry).
+///
+/// # Returns
+/// - An error if an environment is not in the registry or the approval tier is unknown.
+pub fn deploy_stages(
+    config: &ProjectConfig,
+    registry: &SubscriptionRegistry,
+) -> Result<Vec<DeployStage>, anyhow::Error> {
+    let approval_tier = config
+        .approval_tier
+        .as_deref()
+        .unwrap_or(DEFAULT_APPROVAL_TIER);
+    let threshold = TIERS
+        .iter()
+        .position(|tier| *tier == approval_tier)
+        .ok_or_else(|| {
+            anyhow!(
+                "Unknown approval tier '{}', expected one of {}",
+                approval_tier,
+                TIERS.join(", ")
+            )
+        })?;
+
+    // Fail on environments missing from the registry before ordering them
+    for env in &config.environments {
+        registry.get(env)?;
+    }
+
+    let mut stages = Vec::new();
+    for subscription in registry.subscriptions() {
+        if !config.environments.contains(&subscription.env) {
+            continue;
+        }
+        let rank = TIERS
+            .iter()
+            .position(|tier| *tier == subscription.tier)
+            .unwrap_or_default();
+        stages.push(DeployStage {
+            env: subscription.env.clone(),
+            stack: stack_name(config, &subscription.env),
+            service_connection: subscription.service_connection.clone(),
+            subscription_id: subscription.id.clone(),
+            tier: subscription.tier.clone(),
+            variable_groups: iter::once(variable_group_name(config, &subscription.env))
+                .chain(subscription.variable_groups.iter().cloned())
+                .collect(),
+            approval: rank > threshold,
+        });
+    }
+    Ok(stages)
+}
+
+/// Works out which deployment stages the scheduled drift check covers; none when
+/// drift detection is off.
+///
+/// # Returns
+/// - An error if the schedule is not a cron expression, a drift environment is not
+///   selected or the owner must be emailed but has no email.
+pub fn drift_stages<'a>(
+    config: &ProjectConfig,
+    stages: &'a [DeployStage],
+) -> Result<Vec<&'a DeployStage>, anyhow::Error> {
+    let Some(drift) = &config.drift else {
+        return Ok(Vec::new());
+    };
+    if drift.schedule.split_whitespace().count() != 5 {
+        return Err(anyhow!(
+            "Drift schedule '{}' is not a cron expression of five fields",
+            drift.schedule
+        ));
+    }
+    if drift.notify == DriftNotify::Email && config.owner_email.trim().is_empty() {
+        return Err(anyhow!(
+            "Emailing drift needs an owner email; set it with --owner"
+        ));
+    }
+    if let Some(env) = drift
+        .environments
+        .iter()
+        .find(|env| !stages.iter().any(|stage| &stage.env == *env))
+    {
+        return Err(anyhow!(
+            "Drift environment '{}' is not one of the selected environments",
+            env
+        ));
+    }
+
+    Ok(stages
+        .iter()
+        .filter(|stage| drift.environments.is_empty() || drift.environments.contains(&stage.env))
+        .collect())
+}
+
+/// Path of the pipeline definition of a CI system, relative to the project directory.
+pub fn pipeline_file(ci: CiTarget) -> &'static str {
+    match ci {
+        CiTarget::AzurePipelines => "azure-pipelines.yaml",
+        CiTarget::Github => ".github/workflows/infra.yml",
+        CiTarget::Gitlab => ".gitlab-ci.yml",
+    }
+}
+
+/// Creates the deployment pipeline of the project's CI system: a preview on pull
+/// requests, then a `pulumi up` per selected environment in promotion order.
 ///
 /// # Arguments
-/// - `app_name`: The name of the application for which the pipeline YAML is being generated.
+/// - `config`: The project configuration, including the selected environments and CI system.
 /// - `output_dir`: An optional directory where the YAML file will be saved. If not provided, it defaults to a subdirectory named after the app.
 ///
 /// # Returns
@@ -31,23 +313,479 @@ pub async fn create_pipeline_yaml(
         }
     };
 
-    // Ensure the output directory exists, creating it if necessary
-    fs::create_dir_all(&output_dir)?;
-
-    // Replace placeholders for `app_name` in the pipeline template (PIPELINE_TEMPLATE must be predefined)
-    let modified_pipeline: String =
-        PIPELINE_TEMPLATE.replace("{{project_name}}", &config.resource_name);
+    let registry = SubscriptionRegistry::load()?;
+    let stages = deploy_stages(config, &registry)?;
+    let drift = drift_stages(config, &stages)?;
+    let pipeline = match config.ci {
+        CiTarget::AzurePipelines => {
+            let pipeline = render_azure_pipeline(config, &stages, &drift, &secret_inputs(config)?);
+            check_azure_pipeline(config, &pipeline, &registry)?;
+            pipeline
+        }
+        CiTarget::Github => {
+            render_github_workflow(config, &stages, &drift, &secret_inputs(config)?)
+        }
+        CiTarget::Gitlab => render_gitlab_ci(config, &stages, &drift, &secret_inputs(config)?),
+    };
 
     // Construct the full file path for the pipeline YAML file
-    let pipeline_path = format!("{}/azure-pipelines.yaml", output_dir);
+    let pipeline_path = Path::new(&output_dir).join(pipeline_file(config.ci));
+
+    // Ensure the pipeline's directory exists, creating it if necessary
+    if let Some(parent) = pipeline_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
 
-    // Write the modified pipeline YAML content to the file
-    fs::write(&pipeline_path, &modified_pipeline)
-        .map_err(|err| anyhow::anyhow!("Failed to write to {}: {}", pipeline_path, err))?;
+    // Write the pipeline YAML content to the file
+    fs::write(&pipeline_path, &pipeline).map_err(|err| {
+        anyhow::anyhow!("Failed to write to {}: {}", pipeline_path.display(), err)
+    })?;
 
     Ok(())
 }
 
+/// Checks a generated Azure Pipelines definition against the bundled schema, so a
+/// broken template fails generation rather than the first pipeline run.
+fn check_azure_pipeline(
+    config: &ProjectConfig,
+    pipeline: &str,
+    registry: &SubscriptionRegistry,
+) -> Result<(), anyhow::Error> {
+    let mut references = PipelineReferences::from_registry(registry);
+    references.variable_groups.extend(
+        config
+            .environments
+            .iter()
+            .map(|env| variable_group_name(config, env)),
+    );
+    let issues = PipelineSchema::load()?.validate(pipeline, &references);
+    if !issues.is_empty() {
+        return Err(anyhow!(
+            "Generated pipeline is invalid:\n  {}",
+            issues
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join("\n  ")
+        ));
+    }
+    Ok(())
+}
+
+/// Renders the Azure Pipelines definition from the deployment stages.
+fn render_azure_pipeline(
+    config: &ProjectConfig,
+    stages: &[DeployStage],
+    drift: &[&DeployStage],
+    secrets: &[SecretInput],
+) -> String {
+    let mut rendered = String::new();
+
+    // Preview every stack on pull requests
+    rendered.push_str(
+        "  - stage: Preview\n    displayName: 'Preview'\n    condition: eq(variables['Build.Reason'], 'PullRequest')\n    jobs:\n",
+    );
+    for stage in stages {
+        rendered.push_str(&format!(
+            "      - job: preview_{}\n        displayName: 'Preview {}'\n{}        steps:\n{}",
+            stage.env.replace('-', "_"),
+            stage.env,
+            job_variables(stage),
+            indent(&pulumi_steps(stage, "preview", None, secrets), 10)
+        ));
+    }
+
+    // Check for drift on schedule
+    let mut schedules = String::new();
+    if let (Some(settings), false) = (&config.drift, drift.is_empty()) {
+        schedules = format!(
+            "schedules:\n  - cron: '{}'\n    displayName: 'Drift check'\n    branches:\n      include:\n        - main\n    always: true\n\n",
+            settings.schedule
+        );
+        rendered.push_str(
+            "\n  - stage: Drift\n    displayName: 'Drift check'\n    dependsOn: []\n    condition: eq(variables['Build.Reason'], 'Schedule')\n    jobs:\n",
+        );
+        for stage in drift {
+            let mut steps = pulumi_steps(stage, "refresh", Some(DRIFT_ARGS), secrets);
+            if settings.notify == DriftNotify::Email {
+                steps.push_str(&format!(
+                    "- task: AzureCLI@2\n  displayName: 'Email the owner'\n  condition: failed()\n  inputs:\n    azureSubscription: '{connection}'\n    scriptType: 'bash'\n    scriptLocation: 'inlineScript'\n    inlineScript: |\n      az extension add --name communication --upgrade\n      az communication email send --connection-string \"$(DRIFT_EMAIL_CONNECTION_STRING)\" --sender \"$(DRIFT_EMAIL_SENDER)\" --to '{owner}' --subject 'Drift detected in {app} ({env})' --text 'pulumi refresh found changes made outside Pulumi in stack {stack}: $(System.CollectionUri)$(System.TeamProject)/_build/results?buildId=$(Build.BuildId)'\n",
+                    connection = stage.service_connection,
+                    owner = config.owner_email,
+                    app = config.resource_name,
+                    env = stage.env,
+                    stack = stage.stack
+                ));
+            }
+            rendered.push_str(&format!(
+                "      - job: drift_{}\n        displayName: 'Drift {}'\n{}        steps:\n{}",
+                stage.env.replace('-', "_"),
+                stage.env,
+                job_variables(stage),
+                indent(&steps, 10)
+            ));
+        }
+    }
+
+    // Deploy one environment at a time, each after the previous one succeeded
+    let mut previous: Option<String> = None;
+    for stage in stages {
+        rendered.push_str(&format!(
+            "\n  - stage: {}\n    displayName: 'Deploy {}'\n    dependsOn: {}\n    condition: and(succeeded(), in(variables['Build.Reason'], 'IndividualCI', 'BatchedCI', 'Manual'), eq(variables['Build.SourceBranch'], 'refs/heads/main'))\n",
+            stage.id(),
+            stage.env,
+            previous.as_deref().unwrap_or("[]")
+        ));
+        rendered.push_str("    variables:\n");
+        for group in &stage.variable_groups {
+            rendered.push_str(&format!("      - group: '{}'\n", group));
+        }
+        rendered.push_str("    jobs:\n");
+        if stage.approval {
+            let notify = match config.owner_email.is_empty() {
+                true => "$(Build.RequestedForEmail)",
+                false => config.owner_email.as_str(),
+            };
+            rendered.push_str(&format!(
+                "      - job: approve\n        displayName: 'Approve {env}'\n        pool: server\n        steps:\n          - task: ManualValidation@0\n            timeoutInMinutes: {timeout}\n            inputs:\n              notifyUsers: '{notify}'\n              instructions: 'Approve pulumi up on stack {stack} ({tier} tier).'\n              onTimeout: 'reject'\n",
+                env = stage.env,
+                timeout = APPROVAL_TIMEOUT_MINUTES,
+                notify = notify,
+                stack = stage.stack,
+                tier = stage.tier
+            ));
+        }
+        rendered.push_str(&format!(
+            "      - deployment: deploy\n        displayName: 'pulumi up {}'\n{}        environment: '{}-{}'\n        strategy:\n          runOnce:\n            deploy:\n              steps:\n                - checkout: self\n{}",
+            stage.env,
+            if stage.approval { "        dependsOn: approve\n" } else { "" },
+            config.resource_name,
+            stage.env,
+            indent(&pulumi_steps(stage, "up", Some("--yes"), secrets), 16)
+        ));
+        previous = Some(stage.id());
+    }
+
+    fill_optional(PIPELINE_TEMPLATE, "{{schedules}}", &schedules)
+        .replace("{{project_name}}", &config.resource_name)
+        .replace("{{stages}}", rendered.trim_end())
+}
+
+/// Renders the GitHub Actions workflow from the deployment stages.
+fn render_github_workflow(
+    config: &ProjectConfig,
+    stages: &[DeployStage],
+    drift: &[&DeployStage],
+    secrets: &[SecretInput],
+) -> String {
+    let matrix = |stages: &mut dyn Iterator<Item = &DeployStage>| -> String {
+        stages
+            .map(|stage| {
+                format!(
+                    "          - env: {}\n            stack: '{}'\n            subscription-id: '{}'\n",
+                    stage.env, stage.stack, stage.subscription_id
+                )
+            })
+            .collect()
+    };
+    let matrix_setup =
+        GITHUB_SETUP_STEPS.replace("{{subscription_id}}", "${{ matrix.subscription-id }}");
+    // Secret inputs come from repository or environment secrets; setting them needs
+    // the Pulumi CLI before the Pulumi action runs the command
+    let secret_steps = |stack: &str| match github_secret_step(secrets, stack) {
+        step if step.is_empty() => step,
+        step => format!("      - uses: pulumi/actions@v6\n{}", step),
+    };
+
+    // Preview every stack on pull requests, one matrix entry per stack
+    let mut jobs = GITHUB_PREVIEW_JOB
+        .replace("{{matrix}}", &matrix(&mut stages.iter()))
+        .replace(
+            "{{steps}}",
+            &GITHUB_PULUMI_STEPS
+                .replace("{{setup}}", &matrix_setup)
+                .replace("{{secrets}}", &secret_steps("'${{ matrix.stack }}'"))
+                .replace("{{command}}", "preview")
+                .replace("{{stack}}", "${{ matrix.stack }}"),
+        );
+
+    // Check for drift on schedule
+    let mut schedule = String::new();
+    if let (Some(settings), false) = (&config.drift, drift.is_empty()) {
+        schedule = format!("  schedule:\n    - cron: '{}'\n", settings.schedule);
+        let notify = match settings.notify {
+            DriftNotify::Email => GITHUB_DRIFT_EMAIL_STEP.replace("{{owner}}", &config.owner_email),
+            DriftNotify::Fail => String::new(),
+        };
+        jobs.push('\n');
+        jobs.push_str(
+            &GITHUB_DRIFT_JOB
+                .replace("{{matrix}}", &matrix(&mut drift.iter().copied()))
+                .replace("{{setup}}", &matrix_setup)
+                .replace(
+                    "{{secrets}}",
+                    &github_secret_step(secrets, "'${{ matrix.stack }}'"),
+                )
+                .replace("{{drift_args}}", DRIFT_ARGS)
+                .replace("{{notify}}", &notify),
+        );
+    }
+
+    // Deploy one environment at a time, each after the previous one succeeded
+    let mut previous: Option<String> = None;
+    for stage in stages {
+        let needs = previous
+            .as_ref()
+            .map_or(String::new(), |job| format!("    needs: {}\n", job));
+        jobs.push('\n');
+        jobs.push_str(
+            &GITHUB_DEPLOY_JOB
+                .replace("{{job}}", &stage.job_id())
+                .replace("{{needs}}", &needs)
+                .replace(
+                    "{{steps}}",
+                    &GITHUB_PULUMI_STEPS
+                        .replace(
+                            "{{setup}}",
+                            &GITHUB_SETUP_STEPS
+                                .replace("{{subscription_id}}", "${{ env.ARM_SUBSCRIPTION_ID }}"),
+                        )
+                        .replace("{{secrets}}", &secret_steps(&format!("'{}'", stage.stack)))
+                        .replace("{{command}}", "up")
+                        .replace("{{stack}}", &format!("'{}'", stage.stack)),
+                )
+                .replace("{{env}}", &stage.env)
+                .replace("{{subscription_id}}", &stage.subscription_id),
+        );
+        previous = Some(stage.job_id());
+    }
+
+    let environments: Vec<&str> = stages.iter().map(|stage| stage.env.as_str()).collect();
+    let protected: Vec<&str> = stages
+        .iter()
+        .filter(|stage| stage.approval)
+        .map(|stage| stage.env.as_str())
+        .collect();
+    let protected = match protected.is_empty() {
+        true => "none".to_string(),
+        false => protected.join(", "),
+    };
+    let secret_note = match secrets.is_empty() {
+        true => String::new(),
+        false => format!(
+            "# Add the secret inputs of the resources as secrets too: {}.\n",
+            secret_variables(secrets)
+        ),
+    };
+    fill_optional(GITHUB_WORKFLOW_TEMPLATE, "{{schedule}}", &schedule)
+        .replace("{{secret_inputs}}\n", &secret_note)
+        .replace("{{environments}}", &environments.join(", "))
+        .replace("{{protected_environments}}", &protected)
+        .replace("{{jobs}}", jobs.trim_end())
+        .replace("{{project_name}}", &config.resource_name)
+}
+
+/// Renders the GitLab CI/CD pipeline from the deployment stages.
+fn render_gitlab_ci(
+    config: &ProjectConfig,
+    stages: &[DeployStage],
+    drift: &[&DeployStage],
+    secrets: &[SecretInput],
+) -> String {
+    let mut stage_names = vec!["  - preview".to_string()];
+    let mut jobs = String::new();
+
+    // Secret inputs come from masked CI/CD variables
+    let secret_lines = |stage: &DeployStage| -> String {
+        secrets
+            .iter()
+            .map(|secret| {
+                format!(
+                    "    - pulumi config set --secret --stack '{}' {} \"${}\"\n",
+                    stage.stack, secret.config_key, secret.variable
+                )
+            })
+            .collect()
+    };
+
+    // Preview every stack on merge requests
+    for stage in stages {
+        jobs.push_str(
+            &GITLAB_PREVIEW_JOB
+                .replace("{{secrets}}", &secret_lines(stage))
+                .replace("{{env}}", &stage.env)
+                .replace("{{stack}}", &stage.stack)
+                .replace("{{subscription_id}}", &stage.subscription_id),
+        );
+        jobs.push('\n');
+    }
+
+    // Check for drift on a pipeline schedule, which GitLab keeps in the project settings
+    let mut drift_note = String::new();
+    if let (Some(settings), false) = (&config.drift, drift.is_empty()) {
+        stage_names.push("  - drift".to_string());
+        drift_note = format!(
+            "# Create a pipeline schedule '{}' (UTC) on the default branch for the drift check.\n",
+            settings.schedule
+        );
+        if settings.notify == DriftNotify::Email {
+            drift_note.push_str(&format!(
+                "# GitLab emails failed scheduled pipelines to the schedule owner; make {} its owner.\n",
+                config.owner_email
+            ));
+        }
+        for stage in drift {
+            jobs.push_str(
+                &GITLAB_DRIFT_JOB
+                    .replace("{{secrets}}", &secret_lines(stage))
+                    .replace("{{env}}", &stage.env)
+                    .replace("{{stack}}", &stage.stack)
+                    .replace("{{subscription_id}}", &stage.subscription_id)
+                    .replace("{{drift_args}}", DRIFT_ARGS),
+            );
+            jobs.push('\n');
+        }
+    }
+
+    // Deploy one environment per pipeline stage, so each waits for the previous one.
+    // Manual jobs in rules block the later stages until they run.
+    for stage in stages {
+        stage_names.push(format!("  - deploy-{}", stage.env));
+        let deployment_tier = TIERS
+            .iter()
+            .position(|tier| *tier == stage.tier)
+            .and_then(|index| GITLAB_DEPLOYMENT_TIERS.get(index))
+            .unwrap_or(&"other");
+        let when = match stage.approval {
+            true => "      when: manual\n",
+            false => "",
+        };
+        jobs.push_str(
+            &GITLAB_DEPLOY_JOB
+                .replace("{{when}}", when)
+                .replace("{{secrets}}", &secret_lines(stage))
+                .replace("{{env}}", &stage.env)
+                .replace("{{stack}}", &stage.stack)
+                .replace("{{subscription_id}}", &stage.subscription_id)
+                .replace("{{deployment_tier}}", deployment_tier),
+        );
+        jobs.push('\n');
+    }
+
+    let protected: Vec<&str> = stages
+        .iter()
+        .filter(|stage| stage.approval)
+        .map(|stage| stage.env.as_str())
+        .collect();
+    let protected = match protected.is_empty() {
+        true => "none".to_string(),
+        false => protected.join(", "),
+    };
+    let secret_note = match secrets.is_empty() {
+        true => String::new(),
+        false => format!(
+            "# Add masked variables for the secret inputs of the resources: {}.\n",
+            secret_variables(secrets)
+        ),
+    };
+    fill_optional(GITLAB_CI_TEMPLATE, "{{drift}}", &drift_note)
+        .replace("{{secret_inputs}}\n", &secret_note)
+        .replace("{{protected_environments}}", &protected)
+        .replace("{{stages}}", &stage_names.join("\n"))
+        .replace("{{jobs}}", jobs.trim_end())
+        .replace("{{project_name}}", &config.resource_name)
+}
+
+/// GitHub Actions step setting the secret inputs on a stack from secrets of the same name.
+fn github_secret_step(secrets: &[SecretInput], stack: &str) -> String {
+    if secrets.is_empty() {
+        return String::new();
+    }
+    let mut step = "      - name: Set secret inputs\n        run: |\n".to_string();
+    for secret in secrets {
+        step.push_str(&format!(
+            "          pulumi config set --secret --stack {} {} \"${{{{ secrets.{} }}}}\"\n",
+            stack, secret.config_key, secret.variable
+        ));
+    }
+    step
+}
+
+/// Names of the variables the secret inputs are read from, e.g. `DATABASE_ADMINISTRATOR_LOGIN_PASSWORD`.
+fn secret_variables(secrets: &[SecretInput]) -> String {
+    secrets
+        .iter()
+        .map(|secret| secret.variable.as_str())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Steps that install the project's dependencies, set the secret inputs and run a
+/// Pulumi command on a stage's stack.
+fn pulumi_steps(
+    stage: &DeployStage,
+    command: &str,
+    args: Option<&str>,
+    secrets: &[SecretInput],
+) -> String {
+    let mut steps = "- task: NodeTool@0\n  inputs:\n    versionSpec: $(nodeVersion)\n- script: npm install\n  displayName: 'Install dependencies'\n".to_string();
+    // Secret inputs come from the environment's variable group
+    for secret in secrets {
+        steps.push_str(&pulumi_task(
+            stage,
+            &format!("Set {}", secret.config_key),
+            "config",
+            Some(&format!(
+                "set --secret {} \"$({})\"",
+                secret.config_key, secret.variable
+            )),
+        ));
+    }
+    steps.push_str(&pulumi_task(
+        stage,
+        &format!("pulumi {}", command),
+        command,
+        args,
+    ));
+    steps
+}
+
+/// A `Pulumi@1` step running a command on a stage's stack.
+fn pulumi_task(stage: &DeployStage, name: &str, command: &str, args: Option<&str>) -> String {
+    let args = args.map_or(String::new(), |args| format!("    args: '{}'\n", args));
+    format!(
+        "- task: Pulumi@1\n  displayName: '{name} ({stack})'\n  inputs:\n    azureSubscription: '{connection}'\n    command: '{command}'\n{args}    stack: '{stack}'\n  env:\n    PULUMI_ACCESS_TOKEN: $(PULUMI_ACCESS_TOKEN)\n",
+        name = name,
+        command = command,
+        stack = stage.stack,
+        connection = stage.service_connection,
+        args = args
+    )
+}
+
+/// `variables` of a job linking the variable groups of its stage's environment.
+fn job_variables(stage: &DeployStage) -> String {
+    let mut variables = "        variables:\n".to_string();
+    for group in &stage.variable_groups {
+        variables.push_str(&format!("          - group: '{}'\n", group));
+    }
+    variables
+}
+
+/// Replaces a placeholder that sits on a line of its own, dropping the line when the
+/// value is empty. The value ends with a newline.
+fn fill_optional(template: &str, placeholder: &str, value: &str) -> String {
+    template.replace(&format!("{}\n", placeholder), value)
+}
+
+/// Indents every line of a YAML fragment by `width` spaces.
+fn indent(fragment: &str, width: usize) -> String {
+    fragment
+        .lines()
+        .map(|line| format!("{:width$}{}\n", "", line, width = width))
+        .collect()
+}
+
 #[tokio::test]
 async fn test_create_pipeline_yaml() -> Result<(), anyhow::Error> {
     use tempfile::tempdir;
@@ -55,32 +793,254 @@ async fn test_create_pipeline_yaml() -> Result<(), anyhow::Error> {
     // Arrange
     let config = ProjectConfig {
         resource_name: "myapp".to_string(),
-        environments: vec!["dev".to_string(), "prod".to_string()],
+        environments: vec!["prod".to_string(), "dev".to_string(), "stage".to_string()],
         additional_resources: vec!["database".to_string(), "keyvault".to_string()],
-        owner_email: "".to_string(),
+        owner_email: "owner@example.com".to_string(),
+        ..Default::default()
     };
     let temp_dir = tempdir()?; // Create a temporary directory
     let output_dir = temp_dir.path().to_str().unwrap(); // Convert path to a string
     let expected_file_path = format!("{}/azure-pipelines.yaml", output_dir);
-    let expected_content = PIPELINE_TEMPLATE.replace("{{project_name}}", &config.resource_name);
 
     // Act
     create_pipeline_yaml(&config, Some(output_dir)).await?;
 
     // Assert
-    // Check if the file exists
-    assert!(
-        fs::metadata(&expected_file_path).is_ok(),
-        "Pipeline file was not created."
+    let actual_content = fs::read_to_string(&expected_file_path)?;
+    let pipeline: serde_yaml::Value = serde_yaml::from_str(&actual_content)?;
+    let stages = pipeline["stages"].as_sequence().unwrap();
+    let ids: Vec<&str> = stages
+        .iter()
+        .map(|stage| stage["stage"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        ids,
+        ["Preview", "Deploy_dev", "Deploy_stage", "Deploy_prod"]
     );
+    assert_eq!(stages[2]["dependsOn"].as_str(), Some("Deploy_dev"));
+    assert_eq!(stages[0]["jobs"].as_sequence().unwrap().len(), 3);
 
-    // Verify file contents
-    let actual_content = fs::read_to_string(&expected_file_path)?;
+    // Only tiers above silver wait for approval
+    let jobs = |index: usize| stages[index]["jobs"].as_sequence().unwrap().len();
+    assert_eq!((jobs(1), jobs(2), jobs(3)), (1, 2, 2));
     assert_eq!(
-        actual_content, expected_content,
-        "Pipeline file content mismatch."
+        stages[3]["jobs"][0]["steps"][0]["inputs"]["notifyUsers"].as_str(),
+        Some("owner@example.com")
     );
+    assert!(actual_content.contains("azureSubscription: 'azure-prod01'"));
+    assert!(actual_content.contains("stack: 'prod'"));
+
+    // A lower threshold gates every environment above it
+    let registry = SubscriptionRegistry::load()?;
+    let gated = ProjectConfig {
+        approval_tier: Some("bronze".to_string()),
+        ..config.clone()
+    };
+    let approvals: Vec<bool> = deploy_stages(&gated, &registry)?
+        .iter()
+        .map(|stage| stage.approval)
+        .collect();
+    assert_eq!(approvals, [false, true, true]);
+    let unknown = ProjectConfig {
+        approval_tier: Some("diamond".to_string()),
+        ..config
+    };
+    assert!(deploy_stages(&unknown, &registry).is_err());
 
     // Cleanup: Temp directory is automatically cleaned up
     Ok(())
 }
+
+#[tokio::test]
+async fn test_create_github_workflow() -> Result<(), anyhow::Error> {
+    use tempfile::tempdir;
+
+    // Arrange
+    let config = ProjectConfig {
+        resource_name: "myapp".to_string(),
+        environments: vec!["prod".to_string(), "dev".to_string()],
+        additional_resources: vec!["database".to_string()],
+        ci: CiTarget::Github,
+        ..Default::default()
+    };
+    let temp_dir = tempdir()?;
+    let output_dir = temp_dir.path().to_str().unwrap();
+
+    // Act
+    create_pipeline_yaml(&config, Some(output_dir)).await?;
+
+    // Assert
+    let actual_content = fs::read_to_string(temp_dir.path().join(".github/workflows/infra.yml"))?;
+    assert!(
+        !actual_content.replace("${{", "").contains("{{"),
+        "Unresolved placeholder"
+    );
+    let workflow: serde_yaml::Value = serde_yaml::from_str(&actual_content)?;
+    let jobs = workflow["jobs"].as_mapping().unwrap();
+    let ids: Vec<&str> = jobs.iter().map(|(id, _)| id.as_str().unwrap()).collect();
+    assert_eq!(ids, ["preview", "deploy-dev", "deploy-prod"]);
+    assert_eq!(
+        workflow["jobs"]["preview"]["strategy"]["matrix"]["include"]
+            .as_sequence()
+            .unwrap()
+            .len(),
+        2
+    );
+    let prod = &workflow["jobs"]["deploy-prod"];
+    assert_eq!(prod["needs"].as_str(), Some("deploy-dev"));
+    assert_eq!(prod["environment"].as_str(), Some("prod"));
+    assert_eq!(workflow["permissions"]["id-token"].as_str(), Some("write"));
+    assert!(actual_content.contains("protected environments: prod."));
+
+    // Secret inputs are set from secrets before the Pulumi command
+    assert!(actual_content.contains("as secrets too: DATABASE_ADMINISTRATOR_LOGIN_PASSWORD.\n"));
+    let steps = prod["steps"].as_sequence().unwrap();
+    let set_secrets = steps
+        .iter()
+        .position(|step| step["name"].as_str() == Some("Set secret inputs"))
+        .unwrap();
+    assert_eq!(
+        steps[set_secrets]["run"].as_str(),
+        Some("pulumi config set --secret --stack 'prod' databaseAdministratorLoginPassword \"${{ secrets.DATABASE_ADMINISTRATOR_LOGIN_PASSWORD }}\"\n")
+    );
+    assert_eq!(
+        steps[set_secrets - 1]["uses"].as_str(),
+        Some("pulumi/actions@v6")
+    );
+    assert_eq!(
+        steps[set_secrets + 1]["with"]["command"].as_str(),
+        Some("up")
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_create_gitlab_ci() -> Result<(), anyhow::Error> {
+    use tempfile::tempdir;
+
+    // Arrange
+    let config = ProjectConfig {
+        resource_name: "myapp".to_string(),
+        environments: vec!["prod".to_string(), "test".to_string(), "dev".to_string()],
+        additional_resources: vec!["database".to_string()],
+        ci: CiTarget::Gitlab,
+        ..Default::default()
+    };
+    let temp_dir = tempdir()?;
+    let output_dir = temp_dir.path().to_str().unwrap();
+
+    // Act
+    create_pipeline_yaml(&config, Some(output_dir)).await?;
+
+    // Assert
+    let actual_content = fs::read_to_string(temp_dir.path().join(".gitlab-ci.yml"))?;
+    assert!(!actual_content.contains("{{"), "Unresolved placeholder");
+    let pipeline: serde_yaml::Value = serde_yaml::from_str(&actual_content)?;
+    let stages: Vec<&str> = pipeline["stages"]
+        .as_sequence()
+        .unwrap()
+        .iter()
+        .map(|stage| stage.as_str().unwrap())
+        .collect();
+    assert_eq!(
+        stages,
+        ["preview", "deploy-dev", "deploy-test", "deploy-prod"]
+    );
+
+    // One stack per job, and only the production tier waits for a manual run
+    let prod = &pipeline["deploy:prod"];
+    assert_eq!(
+        prod["environment"]["deployment_tier"].as_str(),
+        Some("production")
+    );
+    assert_eq!(prod["rules"][0]["when"].as_str(), Some("manual"));
+    assert!(pipeline["deploy:test"]["rules"][0]["when"].is_null());
+    assert_eq!(
+        pipeline["preview:test"]["script"][1].as_str(),
+        Some("pulumi preview --stack 'test' --non-interactive")
+    );
+
+    // Every job sets the secret inputs from masked variables first
+    assert!(actual_content.contains(
+        "# Add masked variables for the secret inputs of the resources: DATABASE_ADMINISTRATOR_LOGIN_PASSWORD.\n"
+    ));
+    for job in ["preview:test", "deploy:prod"] {
+        assert_eq!(
+            pipeline[job]["script"][0].as_str(),
+            Some(
+                format!(
+                    "pulumi config set --secret --stack '{}' databaseAdministratorLoginPassword \"$DATABASE_ADMINISTRATOR_LOGIN_PASSWORD\"",
+                    job.split_once(':').unwrap().1
+                )
+                .as_str()
+            )
+        );
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_drift_detection() -> Result<(), anyhow::Error> {
+    use crate::tui::app::DriftDetection;
+    use tempfile::tempdir;
+
+    // Arrange
+    let config = ProjectConfig {
+        resource_name: "myapp".to_string(),
+        environments: vec!["dev".to_string(), "prod".to_string()],
+        owner_email: "owner@example.com".to_string(),
+        drift: Some(DriftDetection {
+            environments: vec!["prod".to_string()],
+            notify: DriftNotify::Email,
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    let temp_dir = tempdir()?;
+    let output_dir = temp_dir.path().to_str().unwrap();
+
+    // Act
+    create_pipeline_yaml(&config, Some(output_dir)).await?;
+
+    // Assert
+    let actual_content = fs::read_to_string(temp_dir.path().join("azure-pipelines.yaml"))?;
+    let pipeline: serde_yaml::Value = serde_yaml::from_str(&actual_content)?;
+    assert_eq!(pipeline["schedules"][0]["cron"].as_str(), Some("0 3 * * *"));
+    let drift = &pipeline["stages"][1];
+    assert_eq!(drift["stage"].as_str(), Some("Drift"));
+    assert_eq!(drift["jobs"].as_sequence().unwrap().len(), 1);
+    let steps = &drift["jobs"][0]["steps"];
+    assert_eq!(
+        steps[2]["inputs"]["args"].as_str(),
+        Some("--preview-only --expect-no-changes")
+    );
+    assert_eq!(steps[3]["condition"].as_str(), Some("failed()"));
+    assert!(actual_content.contains("--to 'owner@example.com'"));
+
+    // Settings that cannot produce a working check are rejected
+    let stages = deploy_stages(&config, &SubscriptionRegistry::load()?)?;
+    let invalid = |drift: DriftDetection, owner_email: &str| {
+        let config = ProjectConfig {
+            drift: Some(drift),
+            owner_email: owner_email.to_string(),
+            ..config.clone()
+        };
+        drift_stages(&config, &stages).is_err()
+    };
+    let schedule = DriftDetection {
+        schedule: "nightly".to_string(),
+        ..Default::default()
+    };
+    let environment = DriftDetection {
+        environments: vec!["stage".to_string()],
+        ..Default::default()
+    };
+    let email = DriftDetection {
+        notify: DriftNotify::Email,
+        ..Default::default()
+    };
+    assert!(invalid(schedule, "owner@example.com"));
+    assert!(invalid(environment, "owner@example.com"));
+    assert!(invalid(email, ""));
+    Ok(())
+}
@@ -0,0 +1,540 @@
+use anyhow::{anyhow, Error};
+use serde::Deserialize;
+use serde_yaml::{Mapping, Value};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use crate::helpers::subscription_registry::SubscriptionRegistry;
+
+/// Schema of the Azure Pipelines features pulumimi generates, embedded in the binary
+const PIPELINE_SCHEMA: &str = include_str!("../../config/azure-pipelines.schema.yaml");
+
+/// Task that pauses a run until someone approves it; it only runs in agentless jobs.
+const MANUAL_VALIDATION_TASK: &str = "ManualValidation@0";
+
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct NodeSchema {
+    /// Key that picks the variant -> node of the variant.
+    #[serde(default)]
+    variants: BTreeMap<String, String>,
+    #[serde(default)]
+    keys: BTreeMap<String, String>,
+    #[serde(default)]
+    required: Vec<String>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct TaskSchema {
+    #[serde(default)]
+    required: Vec<String>,
+    #[serde(default)]
+    inputs: Vec<String>,
+}
+
+/// The bundled subset of the Azure Pipelines YAML schema.
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct PipelineSchema {
+    nodes: BTreeMap<String, NodeSchema>,
+    tasks: BTreeMap<String, TaskSchema>,
+}
+
+/// A problem found in a pipeline definition.
+#[derive(Debug, PartialEq)]
+pub struct PipelineIssue {
+    /// Where the problem is, e.g. `stages[1].jobs[0]` or `line 12`.
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for PipelineIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.path.is_empty() {
+            true => write!(f, "{}", self.message),
+            false => write!(f, "{}: {}", self.path, self.message),
+        }
+    }
+}
+
+/// Service connections and variable groups a pipeline may reference.
+#[derive(Debug, Default)]
+pub struct PipelineReferences {
+    pub service_connections: BTreeSet<String>,
+    pub variable_groups: BTreeSet<String>,
+}
+
+impl PipelineReferences {
+    /// The service connections and variable groups of every environment in the registry.
+    pub fn from_registry(registry: &SubscriptionRegistry) -> Self {
+        let mut references = Self::default();
+        for subscription in registry.subscriptions() {
+            references
+                .service_connections
+                .insert(subscription.service_connection.clone());
+            references
+                .variable_groups
+                .extend(subscription.variable_groups.iter().cloned());
+        }
+        references
+    }
+}
+
+impl PipelineSchema {
+    /// Loads the embedded schema.
+    pub fn load() -> Result<Self, Error> {
+        serde_yaml::from_str(PIPELINE_SCHEMA)
+            .map_err(|err| anyhow!("Invalid pipeline schema: {}", err))
+    }
+
+    /// Checks a pipeline definition against the schema, then its stage and job
+    /// dependencies, tasks, service connections and variable groups.
+    pub fn validate(&self, content: &str, references: &PipelineReferences) -> Vec<PipelineIssue> {
+        let mut issues = unresolved_placeholders(content);
+
+        let pipeline: Value = match serde_yaml::from_str(content) {
+            Ok(pipeline) => pipeline,
+            Err(err) => {
+                issues.push(issue("", format!("not valid YAML: {}", err)));
+                return issues;
+            }
+        };
+        self.check_type(&pipeline, "pipeline", "", &mut issues);
+
+        check_variable_groups(&pipeline, "", references, &mut issues);
+        let stages = sequence(&pipeline, "stages");
+        check_dependencies(&stages, "stages", "stage", &mut issues);
+        for (stage_index, stage) in stages.iter().enumerate() {
+            let stage_path = format!("stages[{}]", stage_index);
+            check_variable_groups(stage, &stage_path, references, &mut issues);
+
+            let jobs = sequence(stage, "jobs");
+            let jobs_path = format!("{}.jobs", stage_path);
+            check_dependencies(&jobs, &jobs_path, "job", &mut issues);
+            for (job_index, job) in jobs.iter().enumerate() {
+                let job_path = format!("{}[{}]", jobs_path, job_index);
+                check_variable_groups(job, &job_path, references, &mut issues);
+                let agentless = job.get("pool").and_then(Value::as_str) == Some("server");
+                for (step_path, step) in job_steps(job, &job_path) {
+                    self.check_step(step, &step_path, agentless, references, &mut issues);
+                }
+            }
+        }
+        issues
+    }
+
+    /// Checks a value against a type of the schema, such as `[stage]` or `string|pool`.
+    fn check_type(
+        &self,
+        value: &Value,
+        type_name: &str,
+        path: &str,
+        issues: &mut Vec<PipelineIssue>,
+    ) {
+        let alternatives: Vec<&str> = type_name.split('|').collect();
+        let Some(matching) = alternatives
+            .iter()
+            .find(|alternative| self.has_shape(value, alternative))
+        else {
+            issues.push(issue(path, format!("expected {}", describe(type_name))));
+            return;
+        };
+
+        if let Some(item_type) = matching.strip_prefix('[').and_then(|t| t.strip_suffix(']')) {
+            for (index, item) in value.as_sequence().into_iter().flatten().enumerate() {
+                self.check_type(item, item_type, &format!("{}[{}]", path, index), issues);
+            }
+            return;
+        }
+        match *matching {
+            "identifier" if !is_identifier(value.as_str().unwrap_or_default()) => issues.push(
+                issue(
+                    path,
+                    "identifiers may only contain letters, digits and underscores, and cannot start with a digit".to_string(),
+                ),
+            ),
+            "map" => {
+                for (key, item) in value.as_mapping().into_iter().flatten() {
+                    if !is_scalar(item) {
+                        issues.push(issue(
+                            &join(path, key.as_str().unwrap_or_default()),
+                            "expected a plain value".to_string(),
+                        ));
+                    }
+                }
+            }
+            node => {
+                if let (Some(schema), Some(mapping)) = (self.nodes.get(node), value.as_mapping()) {
+                    self.check_node(schema, mapping, path, issues);
+                }
+            }
+        }
+    }
+
+    /// Whether a value has the shape of a type: a list, a mapping or a plain value.
+    fn has_shape(&self, value: &Value, type_name: &str) -> bool {
+        if type_name.starts_with('[') {
+            return value.is_sequence();
+        }
+        match type_name {
+            "any" => true,
+            "string" => is_scalar(value),
+            "identifier" => value.is_string(),
+            "boolean" => value.is_bool(),
+            "number" => value.is_number(),
+            _ => value.is_mapping(),
+        }
+    }
+
+    fn check_node(
+        &self,
+        schema: &NodeSchema,
+        mapping: &Mapping,
+        path: &str,
+        issues: &mut Vec<PipelineIssue>,
+    ) {
+        if !schema.variants.is_empty() {
+            let variant = schema
+                .variants
+                .iter()
+                .find(|(key, _)| mapping.contains_key(&Value::from(key.as_str())));
+            match variant.and_then(|(_, node)| self.nodes.get(node)) {
+                Some(variant) => self.check_node(variant, mapping, path, issues),
+                None => issues.push(issue(
+                    path,
+                    format!(
+                        "expected one of the keys {}",
+                        schema
+                            .variants
+                            .keys()
+                            .cloned()
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ),
+                )),
+            }
+            return;
+        }
+
+        for required in &schema.required {
+            if !mapping.contains_key(&Value::from(required.as_str())) {
+                issues.push(issue(path, format!("missing required key '{}'", required)));
+            }
+        }
+        for (key, value) in mapping {
+            let key = key.as_str().unwrap_or_default();
+            match schema.keys.get(key) {
+                Some(type_name) => self.check_type(value, type_name, &join(path, key), issues),
+                None => issues.push(issue(path, format!("unknown key '{}'", key))),
+            }
+        }
+    }
+
+    /// Checks a task step's inputs, service connection and where it runs.
+    fn check_step(
+        &self,
+        step: &Value,
+        path: &str,
+        agentless: bool,
+        references: &PipelineReferences,
+        issues: &mut Vec<PipelineIssue>,
+    ) {
+        let Some(task) = step.get("task").and_then(Value::as_str) else {
+            return;
+        };
+        let Some(schema) = self.tasks.get(task) else {
+            issues.push(issue(path, format!("task '{}' is not in the schema", task)));
+            return;
+        };
+        if (task == MANUAL_VALIDATION_TASK) != agentless {
+            issues.push(issue(
+                path,
+                match agentless {
+                    true => format!("task '{}' cannot run in an agentless job", task),
+                    false => format!(
+                        "task '{}' only runs in an agentless job (pool: server)",
+                        task
+                    ),
+                },
+            ));
+        }
+
+        let inputs = step.get("inputs").and_then(Value::as_mapping);
+        for required in &schema.required {
+            let value = inputs.and_then(|inputs| inputs.get(&Value::from(required.as_str())));
+            if value.is_none() {
+                issues.push(issue(
+                    path,
+                    format!("task '{}' is missing input '{}'", task, required),
+                ));
+            }
+        }
+        for (name, value) in inputs.into_iter().flatten() {
+            let name = name.as_str().unwrap_or_default();
+            if !schema.inputs.iter().any(|input| input == name) {
+                issues.push(issue(
+                    path,
+                    format!("task '{}' has no input '{}'", task, name),
+                ));
+            }
+            let connection = value.as_str().unwrap_or_default();
+            if name == "azureSubscription" && !references.service_connections.contains(connection) {
+                issues.push(issue(
+                    path,
+                    format!(
+                        "service connection '{}' is not in the subscription registry",
+                        connection
+                    ),
+                ));
+            }
+        }
+    }
+}
+
+fn issue(path: &str, message: String) -> PipelineIssue {
+    PipelineIssue {
+        path: path.to_string(),
+        message,
+    }
+}
+
+fn join(path: &str, key: &str) -> String {
+    match path.is_empty() {
+        true => key.to_string(),
+        false => format!("{}.{}", path, key),
+    }
+}
+
+fn describe(type_name: &str) -> String {
+    type_name
+        .split('|')
+        .map(|alternative| match alternative.strip_prefix('[') {
+            Some(item) => format!("a list of {}", item.trim_end_matches(']')),
+            None => alternative.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(" or ")
+}
+
+fn is_scalar(value: &Value) -> bool {
+    value.is_string() || value.is_number() || value.is_bool()
+}
+
+fn is_identifier(name: &str) -> bool {
+    name.chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Items of a list key, or none if the key is missing or not a list.
+fn sequence<'a>(value: &'a Value, key: &str) -> Vec<&'a Value> {
+    value
+        .get(key)
+        .and_then(Value::as_sequence)
+        .map(|items| items.iter().collect())
+        .unwrap_or_default()
+}
+
+/// Steps of a job, or of the deploy hook of a deployment job, with their paths.
+fn job_steps<'a>(job: &'a Value, path: &str) -> Vec<(String, &'a Value)> {
+    let (steps_path, steps) = match job.get("deployment") {
+        Some(_) => (
+            format!("{}.strategy.runOnce.deploy.steps", path),
+            job.get("strategy")
+                .and_then(|strategy| strategy.get("runOnce"))
+                .and_then(|run_once| run_once.get("deploy"))
+                .map(|deploy| sequence(deploy, "steps"))
+                .unwrap_or_default(),
+        ),
+        None => (format!("{}.steps", path), sequence(job, "steps")),
+    };
+    steps
+        .into_iter()
+        .enumerate()
+        .map(|(index, step)| (format!("{}[{}]", steps_path, index), step))
+        .collect()
+}
+
+/// Checks that stage or job names are unique and that every `dependsOn` names one
+/// of them without forming a cycle.
+fn check_dependencies(items: &[&Value], path: &str, kind: &str, issues: &mut Vec<PipelineIssue>) {
+    let name = |item: &Value| -> Option<String> {
+        ["stage", "job", "deployment"]
+            .iter()
+            .find_map(|key| item.get(*key).and_then(Value::as_str))
+            .map(str::to_string)
+    };
+    let names: Vec<Option<String>> = items.iter().map(|item| name(item)).collect();
+
+    let mut graph: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for (index, item) in items.iter().enumerate() {
+        let Some(own_name) = &names[index] else {
+            continue;
+        };
+        let item_path = format!("{}[{}]", path, index);
+        if graph.contains_key(own_name) {
+            issues.push(issue(
+                &item_path,
+                format!("duplicate {} '{}'", kind, own_name),
+            ));
+            continue;
+        }
+        let depends_on: Vec<String> = match item.get("dependsOn") {
+            Some(Value::String(dependency)) => vec![dependency.clone()],
+            Some(Value::Sequence(dependencies)) => dependencies
+                .iter()
+                .filter_map(|dependency| dependency.as_str().map(str::to_string))
+                .collect(),
+            _ => Vec::new(),
+        };
+        for dependency in &depends_on {
+            if !names.iter().flatten().any(|name| name == dependency) {
+                issues.push(issue(
+                    &item_path,
+                    format!("depends on unknown {} '{}'", kind, dependency),
+                ));
+            }
+        }
+        graph.insert(own_name.clone(), depends_on);
+    }
+
+    // Depth-first search for a dependency that leads back to itself
+    fn reaches(
+        graph: &BTreeMap<String, Vec<String>>,
+        from: &str,
+        to: &str,
+        seen: &mut BTreeSet<String>,
+    ) -> bool {
+        graph
+            .get(from)
+            .into_iter()
+            .flatten()
+            .any(|next| next == to || (seen.insert(next.clone()) && reaches(graph, next, to, seen)))
+    }
+    let in_cycle: Vec<&String> = graph
+        .keys()
+        .filter(|name| reaches(&graph, name, name, &mut BTreeSet::new()))
+        .collect();
+    if !in_cycle.is_empty() {
+        issues.push(issue(
+            path,
+            format!(
+                "{}s {} depend on each other",
+                kind,
+                in_cycle
+                    .iter()
+                    .map(|name| format!("'{}'", name))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        ));
+    }
+}
+
+/// Checks that every `group` in a `variables` list is known.
+fn check_variable_groups(
+    value: &Value,
+    path: &str,
+    references: &PipelineReferences,
+    issues: &mut Vec<PipelineIssue>,
+) {
+    for (index, variable) in sequence(value, "variables").iter().enumerate() {
+        let Some(group) = variable.get("group").and_then(Value::as_str) else {
+            continue;
+        };
+        if !references.variable_groups.contains(group) {
+            issues.push(issue(
+                &format!("{}[{}]", join(path, "variables"), index),
+                format!(
+                    "variable group '{}' is not in the subscription registry",
+                    group
+                ),
+            ));
+        }
+    }
+}
+
+/// Template placeholders such as `{{project_name}}` left in the text. Azure Pipelines
+/// template expressions (`${{ ... }}`) are not placeholders.
+fn unresolved_placeholders(content: &str) -> Vec<PipelineIssue> {
+    let mut issues = Vec::new();
+    for (line_index, line) in content.lines().enumerate() {
+        let mut rest = line;
+        while let Some(start) = rest.find("{{") {
+            let is_expression = rest[..start].ends_with('$');
+            let end = rest[start..]
+                .find("}}")
+                .map_or(rest.len(), |end| start + end + 2);
+            if !is_expression {
+                issues.push(issue(
+                    &format!("line {}", line_index + 1),
+                    format!("unresolved placeholder '{}'", &rest[start..end]),
+                ));
+            }
+            rest = &rest[end..];
+        }
+    }
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn references() -> PipelineReferences {
+        PipelineReferences {
+            service_connections: BTreeSet::from(["azure-dev01".to_string()]),
+            variable_groups: BTreeSet::from(["pulumi".to_string()]),
+        }
+    }
+
+    fn messages(content: &str) -> Vec<String> {
+        PipelineSchema::load()
+            .unwrap()
+            .validate(content, &references())
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn test_valid_pipeline() {
+        let content = "trigger:\n  - main\nvariables:\n  - group: pulumi\nstages:\n  - stage: Build\n    jobs:\n      - job: approve\n        pool: server\n        steps:\n          - task: ManualValidation@0\n            inputs:\n              notifyUsers: 'owner@example.com'\n  - stage: Deploy\n    dependsOn: Build\n    jobs:\n      - deployment: deploy\n        environment: 'myapp-dev'\n        strategy:\n          runOnce:\n            deploy:\n              steps:\n                - checkout: self\n                - task: Pulumi@1\n                  inputs:\n                    azureSubscription: 'azure-dev01'\n                    command: 'up'\n                    stack: 'dev'\n";
+        assert_eq!(messages(content), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_schema_issues() {
+        let content = "name: {{project_name}}\nstages:\n  - stage: Deploy\n    timeout: 10\n    jobs:\n      - steps: []\n      - job: deploy\n        steps:\n          - task: Pulumi@1\n            inputs:\n              command: up\n              stack: dev\n              color: never\n          - task: Unknown@1\n";
+        assert_eq!(
+            messages(content),
+            [
+                "line 1: unresolved placeholder '{{project_name}}'",
+                "unknown key 'name'",
+                "stages[0]: unknown key 'timeout'",
+                "stages[0].jobs[0]: expected one of the keys deployment, job",
+                "stages[0].jobs[1].steps[0]: task 'Pulumi@1' is missing input 'azureSubscription'",
+                "stages[0].jobs[1].steps[0]: task 'Pulumi@1' has no input 'color'",
+                "stages[0].jobs[1].steps[1]: task 'Unknown@1' is not in the schema",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_references_and_dependencies() {
+        let content = "stages:\n  - stage: A\n    dependsOn: B\n    variables:\n      - group: secrets\n    jobs:\n      - job: one\n        dependsOn: two\n        steps:\n          - task: Pulumi@1\n            inputs:\n              azureSubscription: 'azure-prod01'\n              command: up\n              stack: prod\n  - stage: B\n    dependsOn: [A, C]\n    jobs:\n      - job: one\n        steps:\n          - script: echo ${{ variables.stack }}\n  - stage: B\n    jobs:\n      - job: one\n        steps:\n          - checkout: self\n";
+        assert_eq!(
+            messages(content),
+            [
+                "stages[1]: depends on unknown stage 'C'",
+                "stages[2]: duplicate stage 'B'",
+                "stages: stages 'A', 'B' depend on each other",
+                "stages[0].variables[0]: variable group 'secrets' is not in the subscription registry",
+                "stages[0].jobs[0]: depends on unknown job 'two'",
+                "stages[0].jobs[0].steps[0]: service connection 'azure-prod01' is not in the subscription registry",
+            ]
+        );
+    }
+}
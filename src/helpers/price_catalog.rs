@@ -0,0 +1,412 @@
+use anyhow::{anyhow, Error};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::helpers::cost_helper::{Item, MeterUsage, PricingResponse, CURRENCY};
+use crate::helpers::subscription_registry::{user_config_dir, ConfigRoots};
+
+/// Price catalog shipped with the binary, so estimates work without network access
+const BUNDLED_CATALOG: &str = include_str!("../../config/price-catalog.json");
+
+/// File name of the catalog inside the user and project config directories
+const CATALOG_FILE_NAME: &str = "price-catalog.json";
+
+/// What a price is indexed by.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct PriceKey {
+    pub service_name: String,
+    pub sku_name: String,
+    pub meter_name: String,
+    pub region: String,
+}
+
+impl PriceKey {
+    fn of(item: &Item) -> Self {
+        Self {
+            service_name: item.service_name.clone(),
+            sku_name: item.sku_name.clone(),
+            meter_name: item.meter_name.clone(),
+            region: item.arm_region_name.clone(),
+        }
+    }
+}
+
+/// A catalog file: the prices and the date they were captured.
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+struct CatalogFile {
+    /// Capture date, e.g. `2026-10-01`.
+    captured: String,
+    #[serde(default)]
+    prices: Vec<Item>,
+}
+
+/// A catalog layer prices were read from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CatalogSource {
+    pub source: String,
+    pub captured: String,
+}
+
+/// Azure retail prices indexed by service, SKU, meter and region, merged from the
+/// bundled catalog, the user catalog and the project catalog.
+#[derive(Debug, Clone, Default)]
+pub struct PriceCatalog {
+    prices: BTreeMap<PriceKey, Vec<Item>>,
+    pub sources: Vec<CatalogSource>,
+}
+
+impl PriceCatalog {
+    /// Loads the bundled catalog and layers the user and project catalogs on top, if they exist.
+    pub fn load() -> Result<Self, Error> {
+        Self::load_from(&ConfigRoots::current()?)
+    }
+
+    /// Loads the bundled catalog and layers the catalogs under the given roots on top.
+    pub fn load_from(roots: &ConfigRoots) -> Result<Self, Error> {
+        Self::from_layers(
+            &roots.read_layers(("bundled catalog", BUNDLED_CATALOG), CATALOG_FILE_NAME)?,
+        )
+    }
+
+    /// Builds a catalog from (source, JSON) layers, later layers replacing the same prices.
+    fn from_layers(layers: &[(String, String)]) -> Result<Self, Error> {
+        let mut catalog = Self::default();
+        for (source, content) in layers {
+            let file: CatalogFile = serde_json::from_str(content)
+                .map_err(|err| anyhow!("Invalid price catalog {}: {}", source, err))?;
+            parse_captured(&file.captured)
+                .map_err(|err| anyhow!("Invalid price catalog {}: captured {}", source, err))?;
+            for item in file.prices {
+                check_currency(&item, source)?;
+                catalog.insert(item);
+            }
+            catalog.sources.push(CatalogSource {
+                source: source.clone(),
+                captured: file.captured,
+            });
+        }
+        Ok(catalog)
+    }
+
+    /// A catalog of the given prices, e.g. fetched from the Retail Prices API.
+    pub fn from_items(source: &str, captured: &str, items: Vec<Item>) -> Self {
+        let mut catalog = Self::default();
+        for item in items {
+            catalog.insert(item);
+        }
+        catalog.sources.push(CatalogSource {
+            source: source.to_string(),
+            captured: captured.to_string(),
+        });
+        catalog
+    }
+
+    /// Adds a price, replacing the price of the same product, type, term and usage tier.
+    pub fn insert(&mut self, item: Item) {
+        let prices = self.prices.entry(PriceKey::of(&item)).or_default();
+        match prices.iter_mut().find(|price| same_price(price, &item)) {
+            Some(existing) => *existing = item,
+            None => prices.push(item),
+        }
+    }
+
+    /// Whether the catalog has a price of the same product, type, term and usage tier.
+    fn contains(&self, item: &Item) -> bool {
+        self.prices
+            .get(&PriceKey::of(item))
+            .is_some_and(|prices| prices.iter().any(|price| same_price(price, item)))
+    }
+
+    /// Every price of a meter in its region: consumption, reservation and tiered prices.
+    pub fn lookup(&self, meter: &MeterUsage) -> &[Item] {
+        let key = PriceKey {
+            service_name: meter.service_name.to_string(),
+            sku_name: meter.sku_name.clone(),
+            meter_name: meter.meter_name.clone(),
+            region: meter.region.clone(),
+        };
+        self.prices.get(&key).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// Capture date of the prices, per layer when the layers were captured on different
+    /// dates, e.g. `2026-10-01 (bundled catalog), 2026-10-19 (./.pulumimi/price-catalog.json)`.
+    pub fn captured(&self) -> String {
+        let dates: BTreeSet<&str> = self
+            .sources
+            .iter()
+            .map(|source| source.captured.as_str())
+            .collect();
+        match dates.len() {
+            0 => "-".to_string(),
+            1 => dates.into_iter().collect(),
+            _ => self
+                .sources
+                .iter()
+                .map(|source| format!("{} ({})", source.captured, source.source))
+                .collect::<Vec<_>>()
+                .join(", "),
+        }
+    }
+}
+
+/// Estimates are in [`CURRENCY`], so a price in any other currency cannot be mixed in.
+fn check_currency(item: &Item, source: &str) -> Result<(), Error> {
+    match item.currency_code == CURRENCY {
+        true => Ok(()),
+        false => Err(anyhow!(
+            "{} has a price in '{}' for {} {} in {}; the catalog is in {}",
+            source,
+            item.currency_code,
+            item.product_name,
+            item.meter_name,
+            item.arm_region_name,
+            CURRENCY
+        )),
+    }
+}
+
+/// Whether two prices of a meter are for the same product, type, term and usage tier.
+fn same_price(price: &Item, item: &Item) -> bool {
+    price.product_name == item.product_name
+        && price.price_type == item.price_type
+        && price.reservation_term == item.reservation_term
+        && price.tier_minimum_units == item.tier_minimum_units
+}
+
+/// Parses a `--captured` argument such as `2026-10-01`. Capture dates are compared as
+/// text, so they must be ISO dates.
+pub fn parse_captured(value: &str) -> Result<String, String> {
+    chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map(|date| date.format("%Y-%m-%d").to_string())
+        .map_err(|_| format!("'{}' is not a date of the form YYYY-MM-DD", value))
+}
+
+/// Path of the user catalog that `pulumimi cost import` writes by default.
+pub fn user_catalog_path() -> Result<PathBuf, Error> {
+    user_config_dir()
+        .map(|dir| dir.join(CATALOG_FILE_NAME))
+        .ok_or_else(|| anyhow!("Cannot locate the user config directory; pass --output"))
+}
+
+/// Summary of an import.
+#[derive(Debug, PartialEq)]
+pub struct ImportSummary {
+    pub pages: usize,
+    pub prices: usize,
+    /// Pages that link to a next page, i.e. the query had more results.
+    pub pages_with_next: usize,
+}
+
+/// Adds the prices of saved Retail Prices API pages to a catalog file, creating it
+/// if needed, and records the capture date. Pages with prices in another currency than
+/// [`CURRENCY`] are rejected. While the file keeps prices the pages do not replace, it
+/// keeps the older capture date.
+///
+/// # Arguments
+/// - `pages`: Saved API responses with `Items` and `NextPageLink`.
+/// - `output`: The catalog file to update.
+/// - `captured`: Date the pages were saved, e.g. `2026-10-01`.
+pub fn import_pages(
+    pages: &[PathBuf],
+    output: &Path,
+    captured: &str,
+) -> Result<ImportSummary, Error> {
+    let mut file: CatalogFile = match output.exists() {
+        true => {
+            let content = fs::read_to_string(output)
+                .map_err(|err| anyhow!("Failed to read {}: {}", output.display(), err))?;
+            serde_json::from_str(&content)
+                .map_err(|err| anyhow!("Invalid price catalog {}: {}", output.display(), err))?
+        }
+        false => CatalogFile::default(),
+    };
+
+    let mut catalog = PriceCatalog::default();
+    let mut summary = ImportSummary {
+        pages: 0,
+        prices: 0,
+        pages_with_next: 0,
+    };
+    for path in pages {
+        let content = fs::read_to_string(path)
+            .map_err(|err| anyhow!("Failed to read {}: {}", path.display(), err))?;
+        let page: PricingResponse = serde_json::from_str(&content).map_err(|err| {
+            anyhow!(
+                "{} is not a Retail Prices API page: {}",
+                path.display(),
+                err
+            )
+        })?;
+        let source = path.display().to_string();
+        for item in &page.items {
+            check_currency(item, &source)?;
+        }
+        summary.pages += 1;
+        summary.prices += page.items.len();
+        if page.next_page_link.is_some() {
+            summary.pages_with_next += 1;
+        }
+        for item in page.items {
+            catalog.insert(item);
+        }
+    }
+
+    let mut kept = false;
+    for item in file.prices.drain(..) {
+        if !catalog.contains(&item) {
+            catalog.insert(item);
+            kept = true;
+        }
+    }
+    if !kept || file.captured.is_empty() || captured < file.captured.as_str() {
+        file.captured = captured.to_string();
+    }
+    file.prices = catalog.prices.into_values().flatten().collect();
+    if let Some(parent) = output.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(output, serde_json::to_string_pretty(&file)? + "\n")
+        .map_err(|err| anyhow!("Failed to write to {}: {}", output.display(), err))?;
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(items: &str, next: &str) -> String {
+        format!(r#"{{"Items": [{}], "NextPageLink": {}}}"#, items, next)
+    }
+
+    const CACHE_PRICE: &str = r#"{"currencyCode": "USD", "retailPrice": 0.2, "armRegionName": "westeurope",
+        "serviceName": "Redis Cache", "productName": "Azure Redis Cache Standard", "skuName": "C1",
+        "meterName": "C1 Cache Instance", "unitOfMeasure": "1 Hour", "type": "Consumption"}"#;
+
+    #[test]
+    fn test_bundled_catalog() {
+        let catalog =
+            PriceCatalog::from_layers(&[("bundled".to_string(), BUNDLED_CATALOG.to_string())])
+                .unwrap();
+        assert_eq!(catalog.captured(), "2026-10-01");
+
+        let meter = MeterUsage {
+            service_name: "Service Bus",
+            product_name: None,
+            sku_name: "Standard".to_string(),
+            meter_name: "Standard Base Unit".to_string(),
+            region: "eastus2".to_string(),
+            quantity: 730.0,
+        };
+        assert_eq!(meter.unit_price(catalog.lookup(&meter)), Some(0.0135));
+    }
+
+    #[test]
+    fn test_import_pages() {
+        let dir = tempfile::tempdir().unwrap();
+        let first = dir.path().join("page-1.json");
+        let second = dir.path().join("page-2.json");
+        fs::write(
+            &first,
+            page(
+                CACHE_PRICE,
+                "\"https://prices.azure.com/api/retail/prices?$skip=100\"",
+            ),
+        )
+        .unwrap();
+        fs::write(&second, page(&CACHE_PRICE.replace("0.2", "0.25"), "null")).unwrap();
+        let output = dir.path().join("catalog").join(CATALOG_FILE_NAME);
+
+        let summary =
+            import_pages(&[first.clone(), second.clone()], &output, "2026-10-19").unwrap();
+        assert_eq!(
+            summary,
+            ImportSummary {
+                pages: 2,
+                prices: 2,
+                pages_with_next: 1,
+            }
+        );
+
+        // The later page replaces the same price, and imports add to the existing file
+        let content = fs::read_to_string(&output).unwrap();
+        let catalog = PriceCatalog::from_layers(&[("user".to_string(), content)]).unwrap();
+        assert_eq!(catalog.prices.len(), 1);
+        assert_eq!(catalog.captured(), "2026-10-19");
+        let key = PriceKey {
+            service_name: "Redis Cache".to_string(),
+            sku_name: "C1".to_string(),
+            meter_name: "C1 Cache Instance".to_string(),
+            region: "westeurope".to_string(),
+        };
+        assert_eq!(catalog.prices[&key][0].retail_price, 0.25);
+
+        fs::write(
+            &second,
+            page(&CACHE_PRICE.replace("westeurope", "northeurope"), "null"),
+        )
+        .unwrap();
+        import_pages(std::slice::from_ref(&second), &output, "2026-10-20").unwrap();
+        let content = fs::read_to_string(&output).unwrap();
+        let catalog = PriceCatalog::from_layers(&[("user".to_string(), content)]).unwrap();
+        assert_eq!(catalog.prices.len(), 2);
+        // The westeurope price is still from the first import
+        assert_eq!(catalog.captured(), "2026-10-19");
+
+        // Once every price is replaced, the file has the date of the latest import
+        fs::write(&first, page(&CACHE_PRICE.replace("0.2", "0.3"), "null")).unwrap();
+        import_pages(&[first, second], &output, "2026-10-21").unwrap();
+        let content = fs::read_to_string(&output).unwrap();
+        let catalog = PriceCatalog::from_layers(&[("user".to_string(), content)]).unwrap();
+        assert_eq!(catalog.captured(), "2026-10-21");
+    }
+
+    #[test]
+    fn test_parse_captured() {
+        assert_eq!(parse_captured("2026-10-19"), Ok("2026-10-19".to_string()));
+        assert_eq!(parse_captured("2026-1-5"), Ok("2026-01-05".to_string()));
+        assert!(parse_captured("19/10/2026").is_err());
+        assert!(parse_captured("2026-02-30").is_err());
+        assert!(parse_captured("yesterday").is_err());
+
+        let layer = r#"{"captured": "last week", "prices": []}"#.to_string();
+        assert!(PriceCatalog::from_layers(&[("user".to_string(), layer)]).is_err());
+    }
+
+    #[test]
+    fn test_import_rejects_other_currency() {
+        let dir = tempfile::tempdir().unwrap();
+        let eur = dir.path().join("page-eur.json");
+        fs::write(&eur, page(&CACHE_PRICE.replace("USD", "EUR"), "null")).unwrap();
+        let output = dir.path().join(CATALOG_FILE_NAME);
+
+        let err = import_pages(&[eur], &output, "2026-10-19").unwrap_err();
+        assert!(err.to_string().contains("'EUR'"));
+        assert!(!output.exists());
+
+        let layer = format!(
+            r#"{{"captured": "2026-10-19", "prices": [{}]}}"#,
+            CACHE_PRICE.replace("USD", "EUR")
+        );
+        assert!(PriceCatalog::from_layers(&[("user".to_string(), layer)]).is_err());
+    }
+
+    #[test]
+    fn test_captured_per_layer() {
+        let user = format!(
+            r#"{{"captured": "2026-10-19", "prices": [{}]}}"#,
+            CACHE_PRICE
+        );
+        let catalog = PriceCatalog::from_layers(&[
+            ("bundled catalog".to_string(), BUNDLED_CATALOG.to_string()),
+            ("user catalog".to_string(), user),
+        ])
+        .unwrap();
+        assert_eq!(
+            catalog.captured(),
+            "2026-10-01 (bundled catalog), 2026-10-19 (user catalog)"
+        );
+    }
+}
@@ -0,0 +1,208 @@
+use anyhow::Ok;
+use std::{env, fs};
+
+use crate::helpers::config_helper::{get_subscription_info, stack_file_name, stack_name};
+use crate::helpers::snippet_catalog::resolve_in_dependency_order;
+use crate::tui::app::{OutputLayout, ProjectConfig, SecretMode};
+
+/// Default embedded README template for generated projects
+const README_TEMPLATE: &str = include_str!("../../snippets/README.project.md");
+
+/// Creates a README describing the generated project, saving it in the specified or default directory.
+///
+/// # Arguments
+/// - `config`: The project configuration the rest of the project was generated from.
+/// - `output_dir`: An optional directory where the README will be saved. If not provided, it defaults to a subdirectory named after the app.
+///
+/// # Returns
+/// - `Result<(), anyhow::Error>`: Returns a result indicating success or failure.
+pub async fn create_readme(
+    config: &ProjectConfig,
+    output_dir: Option<&str>,
+) -> Result<(), anyhow::Error> {
+    println!("Creating README for {}", &config.resource_name);
+
+    // Default the output directory to a subdirectory named after the app if not provided
+    let output_dir: String = match output_dir {
+        Some(dir) => dir.to_string(),
+        None => {
+            let current_dir = env::current_dir()?.to_string_lossy().to_string();
+            format!("{}/{}", current_dir, &config.resource_name)
+        }
+    };
+    fs::create_dir_all(&output_dir)?;
+
+    let readme_path = format!("{}/README.md", output_dir);
+    fs::write(&readme_path, render_readme(config)?)
+        .map_err(|err| anyhow::anyhow!("Failed to write to {}: {}", readme_path, err))?;
+
+    Ok(())
+}
+
+/// Renders the README template from the project configuration, the subscription
+/// of each selected environment and the snippet catalog metadata.
+fn render_readme(config: &ProjectConfig) -> Result<String, anyhow::Error> {
+    let app_name = &config.resource_name;
+
+    let snippets = resolve_in_dependency_order(&config.additional_resources, config.secret_mode)?;
+
+    let mut environment_rows = String::new();
+    let mut commands = String::new();
+    for env in &config.environments {
+        let subscription = get_subscription_info(env)?;
+        let stack = stack_name(config, env);
+        environment_rows.push_str(&format!(
+            "| {} | `{}` | `{}` | {} | `{}` | {} |\n",
+            subscription.env,
+            stack,
+            stack_file_name(config, env),
+            subscription.name,
+            subscription.id,
+            subscription.tier
+        ));
+        commands.push_str(&format!(
+            "# {}\npulumi stack select --create {}\n",
+            env, stack
+        ));
+        if config.secret_mode == SecretMode::Placeholder {
+            for def in &snippets {
+                for secret in def.secrets {
+                    commands.push_str(&format!(
+                        "pulumi config set --secret {} --stack {}\n",
+                        def.config_key(secret.name),
+                        stack
+                    ));
+                }
+            }
+        }
+        commands.push_str(&format!(
+            "pulumi preview --stack {}\npulumi up --stack {}\n",
+            stack, stack
+        ));
+    }
+
+    let mut resources = String::new();
+    if snippets.is_empty() {
+        resources.push_str("\nNo additional resources were selected.\n");
+    }
+    for def in snippets {
+        resources.push_str(&format!(
+            "\n### {}\n\n{}\n\n| Config key | Type | Default | Description |\n| --- | --- | --- | --- |\n",
+            def.display_name, def.description
+        ));
+        for parameter in def.parameters {
+            resources.push_str(&format!(
+                "| `{}:{}` | {} | `{}` | {} |\n",
+                app_name,
+                def.config_key(parameter.name),
+                parameter.param_type.ts_type(),
+                parameter.default,
+                parameter.description
+            ));
+        }
+        for secret in def.secrets {
+            let (key, default) = match config.secret_mode {
+                SecretMode::Placeholder => (
+                    format!("`{}:{}`", app_name, def.config_key(secret.name)),
+                    "_set with `pulumi config set --secret`_",
+                ),
+                SecretMode::KeyVault => (
+                    format!("Key Vault `{}`", def.key_vault_secret_name(secret.name)),
+                    "_generated_",
+                ),
+            };
+            resources.push_str(&format!(
+                "| {} | secret | {} | {} |\n",
+                key, default, secret.description
+            ));
+        }
+    }
+
+    let layout = match config.layout {
+        OutputLayout::SingleFile => "single file, every resource is defined in `index.ts`",
+        OutputLayout::Modular => {
+            "modular, each resource lives in `resources/<resource>.ts` and is wired up in `index.ts`"
+        }
+        OutputLayout::Component => {
+            "component, every resource is a child of the `ComponentResource` in `component.ts`"
+        }
+    };
+
+    let owner_email = if config.owner_email.is_empty() {
+        "_not set_"
+    } else {
+        &config.owner_email
+    };
+
+    Ok(README_TEMPLATE
+        .replace("{{project_name}}", app_name)
+        .replace("{{owner_email}}", owner_email)
+        .replace("{{layout}}", layout)
+        .replace("{{environment_rows}}", &environment_rows)
+        .replace("{{resources}}", &resources)
+        .replace("{{commands}}", &commands))
+}
+
+#[tokio::test]
+async fn test_create_readme() -> Result<(), anyhow::Error> {
+    use crate::helpers::subscription_registry::isolate_user_config;
+    use tempfile::tempdir;
+
+    isolate_user_config();
+
+    // Arrange
+    let config = ProjectConfig {
+        resource_name: "myapp".to_string(),
+        environments: vec!["dev".to_string(), "prod".to_string()],
+        additional_resources: vec!["cache".to_string()],
+        owner_email: "owner@example.com".to_string(),
+        ..Default::default()
+    };
+    let temp_dir = tempdir()?; // Create a temporary directory
+    let output_dir = temp_dir.path().to_str().unwrap(); // Convert path to a string
+
+    // Act
+    create_readme(&config, Some(output_dir)).await?;
+
+    // Assert
+    let readme = fs::read_to_string(format!("{}/README.md", output_dir))?;
+    assert!(readme.starts_with("# myapp\n"));
+    assert!(readme.contains("- **Owner:** owner@example.com"));
+    assert!(
+        readme.contains("| dev | `dev` | `Pulumi.dev.yaml` | dev01 | `1234-5678-91011` | bronze |")
+    );
+    assert!(readme.contains(
+        "| prod | `prod` | `Pulumi.prod.yaml` | prod01 | `1234-5678-91011` | platinum |"
+    ));
+    assert!(!readme.contains("| test |"));
+    assert!(readme.contains("### Azure Cache for Redis"));
+    assert!(readme.contains("| `myapp:cacheSkuName` | string | `Standard` |"));
+    assert!(readme.contains("pulumi up --stack prod"));
+    assert!(!readme.contains("{{"), "README has unresolved placeholders");
+
+    Ok(())
+}
+
+#[test]
+fn test_render_readme_secrets() -> Result<(), anyhow::Error> {
+    let mut config = ProjectConfig {
+        resource_name: "myapp".to_string(),
+        environments: vec!["dev".to_string()],
+        additional_resources: vec!["database".to_string()],
+        ..Default::default()
+    };
+
+    let readme = render_readme(&config)?;
+    assert!(readme.contains("| `myapp:databaseAdministratorLoginPassword` | secret |"));
+    assert!(readme
+        .contains("pulumi config set --secret databaseAdministratorLoginPassword --stack dev\n"));
+
+    config.secret_mode = SecretMode::KeyVault;
+    let readme = render_readme(&config)?;
+    assert!(readme
+        .contains("| Key Vault `database-administrator-login-password` | secret | _generated_ |"));
+    assert!(readme.contains("### Azure Key Vault"));
+    assert!(!readme.contains("config set --secret"));
+
+    Ok(())
+}
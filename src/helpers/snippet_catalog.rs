@@ -0,0 +1,805 @@
+use crate::helpers::network_plan::SubnetRole;
+use crate::tui::app::{ProjectConfig, SecretMode};
+
+/// Embedded resource snippet templates
+pub const DATABASE_CODE_TEMPLATE: &str = include_str!("../../snippets/database.ts");
+pub const STORAGE_CODE_TEMPLATE: &str = include_str!("../../snippets/storage.ts");
+pub const SERVICEBUS_CODE_TEMPLATE: &str = include_str!("../../snippets/service_bus.ts");
+pub const CACHE_CODE_TEMPLATE: &str = include_str!("../../snippets/cache.ts");
+pub const KEYVAULT_CODE_TEMPLATE: &str = include_str!("../../snippets/keyvault.ts");
+pub const REGISTRY_CODE_TEMPLATE: &str = include_str!("../../snippets/container-registry.ts");
+
+/// Embedded Bicep modules, one per snippet
+const DATABASE_BICEP_TEMPLATE: &str = include_str!("../../snippets/bicep/database.bicep");
+const STORAGE_BICEP_TEMPLATE: &str = include_str!("../../snippets/bicep/storage.bicep");
+const SERVICEBUS_BICEP_TEMPLATE: &str = include_str!("../../snippets/bicep/service-bus.bicep");
+const CACHE_BICEP_TEMPLATE: &str = include_str!("../../snippets/bicep/cache.bicep");
+const KEYVAULT_BICEP_TEMPLATE: &str = include_str!("../../snippets/bicep/keyvault.bicep");
+const REGISTRY_BICEP_TEMPLATE: &str = include_str!("../../snippets/bicep/container-registry.bicep");
+
+/// Value type of a snippet parameter.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ParameterType {
+    String,
+    Number,
+    Boolean,
+}
+
+impl ParameterType {
+    /// TypeScript type used for the parameter in generated args interfaces.
+    pub fn ts_type(&self) -> &'static str {
+        match self {
+            ParameterType::String => "string",
+            ParameterType::Number => "number",
+            ParameterType::Boolean => "boolean",
+        }
+    }
+
+    /// `pulumi.Config` getter that reads a value of this type.
+    pub fn config_getter(&self) -> &'static str {
+        match self {
+            ParameterType::String => "get",
+            ParameterType::Number => "getNumber",
+            ParameterType::Boolean => "getBoolean",
+        }
+    }
+
+    /// Renders a raw default value as a TypeScript literal.
+    pub fn ts_literal(&self, value: &str) -> String {
+        match self {
+            ParameterType::String => format!("\"{}\"", value),
+            ParameterType::Number | ParameterType::Boolean => value.to_string(),
+        }
+    }
+
+    /// Parses a raw default value into a typed stack config value.
+    pub fn yaml_value(&self, value: &str) -> serde_yaml::Value {
+        match self {
+            ParameterType::String => serde_yaml::Value::from(value),
+            ParameterType::Number | ParameterType::Boolean => {
+                serde_yaml::from_str(value).unwrap_or_else(|_| serde_yaml::Value::from(value))
+            }
+        }
+    }
+}
+
+/// A tunable value in a snippet, referenced in the template as `{{param:<name>}}`.
+#[derive(Debug)]
+pub struct SnippetParameter {
+    pub name: &'static str,
+    pub param_type: ParameterType,
+    pub default: &'static str,
+    /// Accepted values; empty when any value of the type is accepted.
+    pub allowed: &'static [&'static str],
+    pub description: &'static str,
+}
+
+/// A credential a snippet needs, referenced in the template as `{{secret:<name>}}`.
+/// Secrets never have a default; they are read as Pulumi secrets or generated.
+#[derive(Debug)]
+pub struct SnippetSecret {
+    pub name: &'static str,
+    pub description: &'static str,
+}
+
+/// Where rendered `{{param:<name>}}` placeholders read their values from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ParameterSource {
+    /// Stack config, e.g. `config.get("cacheSkuName") ?? "Standard"`.
+    Config,
+    /// The component's args, e.g. `args.cache?.skuName ?? "Standard"`.
+    ComponentArgs,
+}
+
+/// Describes a resource snippet that can be added to a generated project.
+#[derive(Debug)]
+pub struct SnippetDefinition {
+    /// Lowercase identifier stored in `ProjectConfig.additional_resources` (e.g. "servicebus").
+    pub key: &'static str,
+    /// Human readable name shown in the TUI and generated docs.
+    pub display_name: &'static str,
+    pub description: &'static str,
+    /// File name (without extension) used for the module in the modular layout.
+    pub module_name: &'static str,
+    /// Name of the exported factory function in the modular layout.
+    pub factory_name: &'static str,
+    /// Keys of snippets that must be created before this one.
+    pub depends_on: &'static [&'static str],
+    /// Whether multi-region projects get one copy of the resources per deployment region.
+    pub region_scoped: bool,
+    /// Subnet the resources are placed in by the network planner.
+    pub subnet_role: SubnetRole,
+    pub parameters: &'static [SnippetParameter],
+    pub secrets: &'static [SnippetSecret],
+    pub template: &'static str,
+    /// Bicep module with the same resources, used by the Bicep exporter.
+    pub bicep_template: &'static str,
+}
+
+/// All snippets known to the tool, in their default creation order.
+pub const SNIPPET_CATALOG: &[SnippetDefinition] = &[
+    SnippetDefinition {
+        key: "keyvault",
+        display_name: "Azure Key Vault",
+        description: "Key Vault for storing secrets, keys and certificates.",
+        module_name: "keyvault",
+        factory_name: "createKeyvault",
+        depends_on: &[],
+        region_scoped: false,
+        subnet_role: SubnetRole::PrivateEndpoints,
+        parameters: &[
+            SnippetParameter {
+                name: "skuName",
+                param_type: ParameterType::String,
+                default: "standard",
+                allowed: &["standard", "premium"],
+                description: "Key Vault SKU (standard or premium).",
+            },
+            SnippetParameter {
+                name: "tenantId",
+                param_type: ParameterType::String,
+                default: "YOUR_TENANT_ID",
+                allowed: &[],
+                description: "Azure AD tenant that authenticates requests to the vault.",
+            },
+        ],
+        secrets: &[],
+        template: KEYVAULT_CODE_TEMPLATE,
+        bicep_template: KEYVAULT_BICEP_TEMPLATE,
+    },
+    SnippetDefinition {
+        key: "database",
+        display_name: "Azure Database for PostgreSQL",
+        description: "PostgreSQL flexible server for relational data.",
+        module_name: "database",
+        factory_name: "createDatabase",
+        depends_on: &[],
+        region_scoped: false,
+        subnet_role: SubnetRole::Data,
+        parameters: &[
+            SnippetParameter {
+                name: "administratorLogin",
+                param_type: ParameterType::String,
+                default: "adminUser",
+                allowed: &[],
+                description: "Administrator login for the server.",
+            },
+            SnippetParameter {
+                name: "version",
+                param_type: ParameterType::String,
+                default: "13",
+                allowed: &["11", "12", "13", "14", "15", "16"],
+                description: "PostgreSQL major version.",
+            },
+            SnippetParameter {
+                name: "skuName",
+                param_type: ParameterType::String,
+                default: "Standard_D2s_v3",
+                allowed: &[],
+                description: "Compute SKU of the server.",
+            },
+            SnippetParameter {
+                name: "skuTier",
+                param_type: ParameterType::String,
+                default: "GeneralPurpose",
+                allowed: &["Burstable", "GeneralPurpose", "MemoryOptimized"],
+                description: "Compute tier (Burstable, GeneralPurpose or MemoryOptimized).",
+            },
+            SnippetParameter {
+                name: "storageSizeGb",
+                param_type: ParameterType::Number,
+                default: "32",
+                allowed: &[],
+                description: "Storage size in GB.",
+            },
+        ],
+        secrets: &[SnippetSecret {
+            name: "administratorLoginPassword",
+            description: "Administrator password for the server.",
+        }],
+        template: DATABASE_CODE_TEMPLATE,
+        bicep_template: DATABASE_BICEP_TEMPLATE,
+    },
+    SnippetDefinition {
+        key: "cache",
+        display_name: "Azure Cache for Redis",
+        description: "In-memory Redis cache.",
+        module_name: "cache",
+        factory_name: "createCache",
+        depends_on: &[],
+        region_scoped: true,
+        subnet_role: SubnetRole::PrivateEndpoints,
+        parameters: &[
+            SnippetParameter {
+                name: "skuName",
+                param_type: ParameterType::String,
+                default: "Standard",
+                allowed: &["Basic", "Standard", "Premium"],
+                description: "Redis SKU (Basic, Standard or Premium).",
+            },
+            SnippetParameter {
+                name: "skuFamily",
+                param_type: ParameterType::String,
+                default: "C",
+                allowed: &["C", "P"],
+                description: "SKU family (C for Basic/Standard, P for Premium).",
+            },
+            SnippetParameter {
+                name: "capacity",
+                param_type: ParameterType::Number,
+                default: "1",
+                allowed: &["0", "1", "2", "3", "4", "5", "6"],
+                description: "Cache size within the SKU family.",
+            },
+        ],
+        secrets: &[],
+        template: CACHE_CODE_TEMPLATE,
+        bicep_template: CACHE_BICEP_TEMPLATE,
+    },
+    SnippetDefinition {
+        key: "servicebus",
+        display_name: "Azure Service Bus",
+        description: "Service Bus namespace with a partitioned queue.",
+        module_name: "service-bus",
+        factory_name: "createServiceBus",
+        depends_on: &[],
+        region_scoped: true,
+        subnet_role: SubnetRole::PrivateEndpoints,
+        parameters: &[
+            SnippetParameter {
+                name: "skuName",
+                param_type: ParameterType::String,
+                default: "Standard",
+                allowed: &["Basic", "Standard", "Premium"],
+                description: "Namespace SKU (Basic, Standard or Premium).",
+            },
+            SnippetParameter {
+                name: "enablePartitioning",
+                param_type: ParameterType::Boolean,
+                default: "true",
+                allowed: &[],
+                description: "Partition the queue across message brokers.",
+            },
+        ],
+        secrets: &[],
+        template: SERVICEBUS_CODE_TEMPLATE,
+        bicep_template: SERVICEBUS_BICEP_TEMPLATE,
+    },
+    SnippetDefinition {
+        key: "storage",
+        display_name: "Azure Storage",
+        description: "StorageV2 account with a blob container.",
+        module_name: "storage",
+        factory_name: "createStorage",
+        depends_on: &[],
+        region_scoped: true,
+        subnet_role: SubnetRole::PrivateEndpoints,
+        parameters: &[
+            SnippetParameter {
+                name: "skuName",
+                param_type: ParameterType::String,
+                default: "Standard_LRS",
+                allowed: &[
+                    "Standard_LRS",
+                    "Standard_GRS",
+                    "Standard_RAGRS",
+                    "Standard_ZRS",
+                    "Premium_LRS",
+                ],
+                description: "Storage account replication SKU.",
+            },
+            SnippetParameter {
+                name: "publicAccess",
+                param_type: ParameterType::String,
+                default: "Blob",
+                allowed: &["None", "Blob", "Container"],
+                description: "Public access level of the blob container (None, Blob or Container).",
+            },
+        ],
+        secrets: &[],
+        template: STORAGE_CODE_TEMPLATE,
+        bicep_template: STORAGE_BICEP_TEMPLATE,
+    },
+    SnippetDefinition {
+        key: "containerregistry",
+        display_name: "Azure Container Registry",
+        description: "Registry for storing and managing container images.",
+        module_name: "container-registry",
+        factory_name: "createContainerRegistry",
+        depends_on: &[],
+        region_scoped: false,
+        subnet_role: SubnetRole::PrivateEndpoints,
+        parameters: &[
+            SnippetParameter {
+                name: "skuName",
+                param_type: ParameterType::String,
+                default: "Basic",
+                allowed: &["Basic", "Standard", "Premium"],
+                description: "Registry SKU (Basic, Standard or Premium).",
+            },
+            SnippetParameter {
+                name: "adminUserEnabled",
+                param_type: ParameterType::Boolean,
+                default: "true",
+                allowed: &[],
+                description: "Enable the registry admin user.",
+            },
+        ],
+        secrets: &[],
+        template: REGISTRY_CODE_TEMPLATE,
+        bicep_template: REGISTRY_BICEP_TEMPLATE,
+    },
+];
+
+impl SnippetDefinition {
+    /// Variable name used for this resource in generated code (e.g. "serviceBus").
+    pub fn instance_name(&self) -> String {
+        let name = self.factory_name.trim_start_matches("create");
+        let mut chars = name.chars();
+        match chars.next() {
+            Some(first) => first.to_lowercase().chain(chars).collect(),
+            None => self.key.to_string(),
+        }
+    }
+
+    /// Stack config key for a parameter name, e.g. "cacheSkuName".
+    pub fn config_key(&self, parameter_name: &str) -> String {
+        let mut chars = parameter_name.chars();
+        let pascal: String = match chars.next() {
+            Some(first) => first.to_uppercase().chain(chars).collect(),
+            None => String::new(),
+        };
+        format!("{}{}", self.instance_name(), pascal)
+    }
+
+    /// Name of the Key Vault secret a generated `secret_name` is stored under.
+    pub fn key_vault_secret_name(&self, secret_name: &str) -> String {
+        kebab_case(&self.config_key(secret_name))
+    }
+
+    /// Name of the pipeline variable a `secret_name` is supplied from, e.g.
+    /// `DATABASE_ADMINISTRATOR_LOGIN_PASSWORD`.
+    pub fn pipeline_variable_name(&self, secret_name: &str) -> String {
+        self.key_vault_secret_name(secret_name)
+            .replace('-', "_")
+            .to_uppercase()
+    }
+
+    /// Keys of the snippets that must be created before this one. Snippets with
+    /// secrets also depend on the Key Vault that stores them in `SecretMode::KeyVault`.
+    pub fn dependencies(&self, secret_mode: SecretMode) -> Vec<&'static str> {
+        let mut dependencies = self.depends_on.to_vec();
+        if secret_mode == SecretMode::KeyVault && !self.secrets.is_empty() {
+            dependencies.push("keyvault");
+        }
+        dependencies
+    }
+
+    /// Renders the template, replacing `{{param:<name>}}` placeholders with an
+    /// expression that reads the value from `source` and falls back to the default,
+    /// and `{{secret:<name>}}` placeholders with a required Pulumi secret.
+    pub fn render(&self, source: ParameterSource) -> String {
+        self.render_with(source, SecretMode::Placeholder)
+    }
+
+    /// Like `render`, with secrets supplied according to `secret_mode`. In
+    /// `SecretMode::KeyVault` each secret is a generated password that is stored in
+    /// the Key Vault created by the keyvault snippet (`keyVaultName`).
+    pub fn render_with(&self, source: ParameterSource, secret_mode: SecretMode) -> String {
+        let mut rendered = self.template.to_string();
+        let mut generated = String::new();
+        for secret in self.secrets {
+            let key = self.config_key(secret.name);
+            let expression = match (secret_mode, source) {
+                (SecretMode::KeyVault, _) => {
+                    generated.push_str(&format!(
+                        "\n// Generated {}, stored in Key Vault\n\
+                         const {key} = new random.RandomPassword(\"{key}\", {{\n    length: 32,\n    special: true,\n}}, resourceOptions);\n\n\
+                         const {key}Secret = new azure.keyvault.Secret(\"{key}\", {{\n    resourceGroupName: resourceGroup.name,\n    tags,\n    vaultName: keyVaultName,\n    secretName: \"{}\",\n    properties: {{\n        value: {key}.result,\n    }},\n}}, resourceOptions);\n",
+                        secret.name,
+                        self.key_vault_secret_name(secret.name),
+                        key = key
+                    ));
+                    format!("{}.result", key)
+                }
+                (SecretMode::Placeholder, ParameterSource::Config) => {
+                    format!("config.requireSecret(\"{}\")", key)
+                }
+                (SecretMode::Placeholder, ParameterSource::ComponentArgs) => {
+                    format!("args.{}?.{}", self.instance_name(), secret.name)
+                }
+            };
+            rendered = rendered.replace(&format!("{{{{secret:{}}}}}", secret.name), &expression);
+        }
+        rendered.insert_str(0, &generated);
+        for parameter in self.parameters {
+            let default = parameter.param_type.ts_literal(parameter.default);
+            let expression = match source {
+                ParameterSource::Config => format!(
+                    "config.{}(\"{}\") ?? {}",
+                    parameter.param_type.config_getter(),
+                    self.config_key(parameter.name),
+                    default
+                ),
+                ParameterSource::ComponentArgs => format!(
+                    "args.{}?.{} ?? {}",
+                    self.instance_name(),
+                    parameter.name,
+                    default
+                ),
+            };
+            rendered = rendered.replace(&format!("{{{{param:{}}}}}", parameter.name), &expression);
+        }
+        rendered
+    }
+
+    /// Renders the snippet once per deployment region: the resources are created inside
+    /// `regions.map(...)` with the region as their location and logical-name suffix, and
+    /// each output is exported as the list of per-region values.
+    pub fn render_regional(&self, source: ParameterSource, secret_mode: SecretMode) -> String {
+        let instance = self.instance_name();
+        let outputs = exported_names(self.template);
+
+        let mut body = String::new();
+        for line in self.render_with(source, secret_mode).trim().lines() {
+            let mut line = line.replace("location: resourceGroup.location", "location: region");
+            if let Some((head, rest)) = line.split_once("(\"") {
+                if let (true, Some((name, tail))) = (head.contains("new "), rest.split_once('"')) {
+                    line = format!("{}(`{}-${{region}}`{}", head, name, tail);
+                }
+            }
+            if let Some(rest) = line.strip_prefix("export const ") {
+                line = format!("const {}", rest);
+            }
+            if line.trim().is_empty() {
+                body.push('\n');
+            } else {
+                body.push_str(&format!("    {}\n", line));
+            }
+        }
+
+        let mut rendered = format!(
+            "\n// {}: one copy per deployment region\nconst {}ByRegion = regions.map((region) => {{\n{}\n    return {{ {} }};\n}});\n\n",
+            self.display_name,
+            instance,
+            body,
+            outputs.join(", ")
+        );
+        for output in outputs {
+            rendered.push_str(&format!(
+                "export const {} = pulumi.all({}ByRegion.map((r) => r.{}));\n",
+                output, instance, output
+            ));
+        }
+        rendered
+    }
+
+    /// Renders the snippet for a project: with the project's secret mode, and per
+    /// region when the project is multi-region and the snippet is region-scoped.
+    pub fn render_for(&self, source: ParameterSource, config: &ProjectConfig) -> String {
+        if config.multi_region && self.region_scoped {
+            self.render_regional(source, config.secret_mode)
+        } else {
+            self.render_with(source, config.secret_mode)
+        }
+    }
+}
+
+/// Looks up a snippet by name, ignoring case, spaces, dashes and underscores
+/// so "Service Bus", "service_bus" and "servicebus" all resolve to the same entry.
+pub fn find_snippet(name: &str) -> Option<&'static SnippetDefinition> {
+    let normalized: String = name
+        .chars()
+        .filter(|c| !matches!(c, ' ' | '-' | '_'))
+        .collect::<String>()
+        .to_lowercase();
+    SNIPPET_CATALOG.iter().find(|def| def.key == normalized)
+}
+
+/// Resolves the selected resources to catalog entries ordered so that every
+/// snippet comes after the snippets it depends on.
+///
+/// Unknown resource names are skipped. Dependencies that were not selected are
+/// added automatically, including the Key Vault that stores generated secrets.
+pub fn resolve_in_dependency_order(
+    resources: &[String],
+    secret_mode: SecretMode,
+) -> Result<Vec<&'static SnippetDefinition>, anyhow::Error> {
+    let mut ordered: Vec<&'static SnippetDefinition> = Vec::new();
+    let mut visiting: Vec<&'static str> = Vec::new();
+
+    fn visit(
+        def: &'static SnippetDefinition,
+        secret_mode: SecretMode,
+        ordered: &mut Vec<&'static SnippetDefinition>,
+        visiting: &mut Vec<&'static str>,
+    ) -> Result<(), anyhow::Error> {
+        if ordered.iter().any(|d| d.key == def.key) {
+            return Ok(());
+        }
+        if visiting.contains(&def.key) {
+            return Err(anyhow::anyhow!(
+                "Circular snippet dependency involving '{}'",
+                def.key
+            ));
+        }
+        visiting.push(def.key);
+        for dep in def.dependencies(secret_mode) {
+            let dep_def = find_snippet(dep)
+                .ok_or_else(|| anyhow::anyhow!("Unknown snippet dependency '{}'", dep))?;
+            visit(dep_def, secret_mode, ordered, visiting)?;
+        }
+        visiting.retain(|key| key != &def.key);
+        ordered.push(def);
+        Ok(())
+    }
+
+    for res in resources {
+        if let Some(def) = find_snippet(res) {
+            visit(def, secret_mode, &mut ordered, &mut visiting)?;
+        }
+    }
+
+    Ok(ordered)
+}
+
+/// Property names that hold credentials, compared case-insensitively.
+const CREDENTIAL_WORDS: &[&str] = &[
+    "password",
+    "secret",
+    "token",
+    "apikey",
+    "accesskey",
+    "primarykey",
+    "secondarykey",
+    "connectionstring",
+];
+
+/// Fails if generated code assigns a string literal to a property or variable whose
+/// name looks like a credential, e.g. `administratorLoginPassword: "hunter2"`.
+/// Names ending in `name` or `id` (such as `secretName`) are not credentials.
+pub fn lint_credentials(code: &str, file_name: &str) -> Result<(), anyhow::Error> {
+    for (number, line) in code.lines().enumerate() {
+        let line = line.trim();
+        if line.starts_with("//") {
+            continue;
+        }
+        let (name, value) = match (line.find(':'), line.find('=')) {
+            // A type annotation, e.g. `const apiToken: string = "abc"`
+            (Some(colon), Some(equals))
+                if colon < equals && !line[colon..equals].contains(['"', '\'', '`']) =>
+            {
+                (&line[..colon], &line[equals + 1..])
+            }
+            (Some(colon), _) => (&line[..colon], &line[colon + 1..]),
+            (None, Some(equals)) => (&line[..equals], &line[equals + 1..]),
+            (None, None) => continue,
+        };
+        let name = name
+            .trim()
+            .rsplit(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .next()
+            .unwrap_or_default()
+            .to_lowercase();
+        let value = value.trim_start();
+        let is_literal = ['"', '\'', '`']
+            .iter()
+            .any(|quote| value.starts_with(*quote) && value[1..].starts_with(|c| c != *quote));
+        if is_literal
+            && !name.ends_with("name")
+            && !name.ends_with("id")
+            && CREDENTIAL_WORDS.iter().any(|word| name.contains(word))
+        {
+            return Err(anyhow::anyhow!(
+                "{} line {} assigns a literal to '{}', which looks like a credential; declare it as a snippet secret instead",
+                file_name,
+                number + 1,
+                name
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Turns a camelCase name into kebab-case, e.g. "databasePassword" -> "database-password".
+fn kebab_case(name: &str) -> String {
+    let mut kebab = String::new();
+    for c in name.chars() {
+        if c.is_uppercase() {
+            kebab.push('-');
+        }
+        kebab.extend(c.to_lowercase());
+    }
+    kebab
+}
+
+/// Returns the names of the `export const` declarations in a snippet template.
+pub fn exported_names(template: &str) -> Vec<String> {
+    template
+        .lines()
+        .filter_map(|line| line.trim_start().strip_prefix("export const "))
+        .filter_map(|rest| {
+            let name: String = rest
+                .chars()
+                .take_while(|c| c.is_alphanumeric() || *c == '_')
+                .collect();
+            (!name.is_empty()).then_some(name)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_snippet_normalizes_names() {
+        assert_eq!(find_snippet("Service Bus").unwrap().key, "servicebus");
+        assert_eq!(
+            find_snippet("container_registry").unwrap().key,
+            "containerregistry"
+        );
+        assert_eq!(find_snippet("KeyVault").unwrap().key, "keyvault");
+        assert!(find_snippet("cosmosdb").is_none());
+    }
+
+    #[test]
+    fn test_resolve_in_dependency_order_skips_unknown() {
+        let resources = vec![
+            "storage".to_string(),
+            "unknown".to_string(),
+            "database".to_string(),
+            "storage".to_string(),
+        ];
+
+        let ordered = resolve_in_dependency_order(&resources, SecretMode::Placeholder).unwrap();
+        let keys: Vec<&str> = ordered.iter().map(|def| def.key).collect();
+
+        assert_eq!(keys, vec!["storage", "database"]);
+
+        let ordered = resolve_in_dependency_order(&resources, SecretMode::KeyVault).unwrap();
+        let keys: Vec<&str> = ordered.iter().map(|def| def.key).collect();
+
+        assert_eq!(keys, vec!["storage", "keyvault", "database"]);
+    }
+
+    #[test]
+    fn test_render_replaces_every_parameter() {
+        for def in SNIPPET_CATALOG {
+            for source in [ParameterSource::Config, ParameterSource::ComponentArgs] {
+                for secret_mode in [SecretMode::Placeholder, SecretMode::KeyVault] {
+                    let rendered = def.render_with(source, secret_mode);
+                    assert!(
+                        !rendered.contains("{{param:") && !rendered.contains("{{secret:"),
+                        "{} has a placeholder without a matching parameter or secret",
+                        def.key
+                    );
+                    lint_credentials(&rendered, def.key).unwrap();
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_render_regional_creates_one_copy_per_region() {
+        let storage = find_snippet("storage").unwrap();
+        let rendered = storage.render_regional(ParameterSource::Config, SecretMode::Placeholder);
+
+        assert!(rendered.contains("const storageByRegion = regions.map((region) => {"));
+        assert!(
+            rendered.contains("new azure.storage.StorageAccount(`myStorageAccount-${region}`, {")
+        );
+        assert!(rendered.contains("new azure.storage.BlobContainer(`myContainer-${region}`, {"));
+        assert!(rendered.contains("        location: region,"));
+        assert!(!rendered.contains("resourceGroup.location"));
+        assert!(rendered.contains("    return { storageAccountName, containerName };"));
+        assert!(rendered.contains(
+            "export const storageAccountName = pulumi.all(storageByRegion.map((r) => r.storageAccountName));"
+        ));
+        assert_eq!(exported_names(&rendered), exported_names(storage.template));
+
+        let keyvault = find_snippet("keyvault").unwrap();
+        let config = ProjectConfig {
+            multi_region: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            keyvault.render_for(ParameterSource::Config, &config),
+            keyvault.render(ParameterSource::Config)
+        );
+    }
+
+    #[test]
+    fn test_defaults_are_allowed_values() {
+        for def in SNIPPET_CATALOG {
+            for parameter in def.parameters {
+                assert!(
+                    parameter.allowed.is_empty() || parameter.allowed.contains(&parameter.default),
+                    "{}.{} default is not an allowed value",
+                    def.key,
+                    parameter.name
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_every_located_resource_is_tagged() {
+        for def in SNIPPET_CATALOG {
+            for template in [def.template, def.bicep_template] {
+                let tagged = template
+                    .lines()
+                    .filter(|line| matches!(line.trim(), "tags," | "tags: tags"))
+                    .count();
+                assert_eq!(
+                    template.matches("location: ").count(),
+                    tagged,
+                    "{} has a resource without tags",
+                    def.key
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_render_secrets() {
+        let database = find_snippet("database").unwrap();
+
+        let placeholder = database.render(ParameterSource::Config);
+        assert!(placeholder.contains(
+            "administratorLoginPassword: config.requireSecret(\"databaseAdministratorLoginPassword\"),"
+        ));
+        let from_args = database.render(ParameterSource::ComponentArgs);
+        assert!(from_args
+            .contains("administratorLoginPassword: args.database?.administratorLoginPassword,"));
+
+        let generated = database.render_with(ParameterSource::Config, SecretMode::KeyVault);
+        assert!(generated.contains(
+            "const databaseAdministratorLoginPassword = new random.RandomPassword(\"databaseAdministratorLoginPassword\", {"
+        ));
+        assert!(generated.contains("    vaultName: keyVaultName,"));
+        assert!(generated.contains("    secretName: \"database-administrator-login-password\","));
+        assert!(generated
+            .contains("administratorLoginPassword: databaseAdministratorLoginPassword.result,"));
+    }
+
+    #[test]
+    fn test_lint_credentials() {
+        assert!(lint_credentials(
+            "    administratorLoginPassword: \"strongPassword123!\",",
+            "db.ts"
+        )
+        .is_err());
+        assert!(lint_credentials("const apiToken = 'abc';", "index.ts").is_err());
+        assert!(lint_credentials("const apiToken: string = \"abc\";", "index.ts").is_err());
+        assert!(lint_credentials(
+            "const apiToken: pulumi.Output<string> = config.requireSecret(\"apiToken\");",
+            "index.ts"
+        )
+        .is_ok());
+        assert!(lint_credentials("    secretName: \"db-password\",", "db.ts").is_ok());
+        assert!(
+            lint_credentials("    password: config.requireSecret(\"password\"),", "db.ts").is_ok()
+        );
+        assert!(lint_credentials("    primaryKey: \"\",", "db.ts").is_ok());
+        assert!(lint_credentials("// password: \"example\"", "db.ts").is_ok());
+    }
+
+    #[test]
+    fn test_render_parameter_sources() {
+        let cache = find_snippet("cache").unwrap();
+
+        let from_config = cache.render(ParameterSource::Config);
+        assert!(from_config.contains("name: config.get(\"cacheSkuName\") ?? \"Standard\","));
+        assert!(from_config.contains("capacity: config.getNumber(\"cacheCapacity\") ?? 1,"));
+
+        let from_args = cache.render(ParameterSource::ComponentArgs);
+        assert!(from_args.contains("name: args.cache?.skuName ?? \"Standard\","));
+    }
+
+    #[test]
+    fn test_exported_names() {
+        let names = exported_names(DATABASE_CODE_TEMPLATE);
+        assert_eq!(names, vec!["postgresHost", "postgresAdmin"]);
+    }
+}
@@ -0,0 +1,303 @@
+use serde_yaml::Value;
+use std::fmt;
+use std::ops::Range;
+
+/// Indent of entries added to a `config:` block that has none yet.
+const DEFAULT_INDENT: usize = 2;
+
+/// A stack settings file edited line by line, so that changing, adding or removing
+/// a config key leaves the order of the other keys and every comment untouched.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StackDocument {
+    lines: Vec<String>,
+}
+
+impl StackDocument {
+    pub fn parse(content: &str) -> Self {
+        Self {
+            lines: content.lines().map(str::to_string).collect(),
+        }
+    }
+
+    /// Sets a config key, replacing its value in place or appending it to the end
+    /// of the `config:` block.
+    pub fn set(&mut self, key: &str, value: &Value) {
+        let (block, indent) = self.config_block();
+        match self.find_entry(key) {
+            Some(entry) => {
+                let first = &self.lines[entry.start];
+                let key_text = first.trim_start()[..key_token_len(first.trim_start())].to_string();
+                let comment = trailing_comment(first);
+                let mut rendered = render_entry(indent, &key_text, value);
+                if let (Some(comment), 1) = (comment, rendered.len()) {
+                    rendered[0] = format!("{} {}", rendered[0], comment);
+                }
+                self.lines.splice(entry, rendered);
+            }
+            None => {
+                let rendered = render_entry(indent, &quote_key(key), value);
+                let at = self.last_entry_end(block);
+                self.lines.splice(at..at, rendered);
+            }
+        }
+    }
+
+    /// Removes a config key with its value. Returns false if the key is not set.
+    pub fn remove(&mut self, key: &str) -> bool {
+        match self.find_entry(key) {
+            Some(entry) => {
+                self.lines.drain(entry);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Range of the lines inside the `config:` block, adding an empty block if the
+    /// file has none, and the indent of its entries.
+    fn config_block(&mut self) -> (Range<usize>, usize) {
+        let start = match self.lines.iter().position(|line| is_config_line(line)) {
+            Some(index) => index + 1,
+            None => {
+                self.lines.push("config:".to_string());
+                self.lines.len()
+            }
+        };
+        let end = self.lines[start..]
+            .iter()
+            .position(|line| indent_of(line) == 0 && !is_blank_or_comment(line))
+            .map_or(self.lines.len(), |offset| start + offset);
+        let indent = self.lines[start..end]
+            .iter()
+            .find(|line| !is_blank_or_comment(line))
+            .map_or(DEFAULT_INDENT, |line| indent_of(line));
+        (start..end, indent)
+    }
+
+    /// Lines of a config entry: its key line and its value lines, which are more deeply
+    /// indented, sequence items at the key's indent, or blank lines between them (e.g.
+    /// inside a block scalar). Blank lines after the value are not part of the entry.
+    fn find_entry(&mut self, key: &str) -> Option<Range<usize>> {
+        let (block, indent) = self.config_block();
+        let start = block.clone().find(|&index| {
+            let line = &self.lines[index];
+            indent_of(line) == indent
+                && !is_blank_or_comment(line)
+                && unquote(&line.trim_start()[..key_token_len(line.trim_start())]) == key
+        })?;
+        let is_value_line = |line: &str| {
+            let trimmed = line.trim_start();
+            trimmed.is_empty()
+                || indent_of(line) > indent
+                || (indent_of(line) == indent && (trimmed == "-" || trimmed.starts_with("- ")))
+        };
+        let end = (start + 1..block.end)
+            .take_while(|&index| is_value_line(&self.lines[index]))
+            .filter(|&index| !self.lines[index].trim().is_empty())
+            .last()
+            .map_or(start + 1, |index| index + 1);
+        Some(start..end)
+    }
+
+    /// End of the last entry of the block, so new keys go after it rather than after
+    /// trailing blank lines or comments.
+    fn last_entry_end(&self, block: Range<usize>) -> usize {
+        (block.start..block.end)
+            .rev()
+            .find(|&index| !is_blank_or_comment(&self.lines[index]))
+            .map_or(block.start, |index| index + 1)
+    }
+}
+
+impl fmt::Display for StackDocument {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for line in &self.lines {
+            writeln!(f, "{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+fn indent_of(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+fn is_blank_or_comment(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    trimmed.is_empty() || trimmed.starts_with('#')
+}
+
+fn is_config_line(line: &str) -> bool {
+    let code = line.split(" #").next().unwrap_or_default().trim_end();
+    indent_of(line) == 0 && code == "config:"
+}
+
+/// Length of the key at the start of an entry line: a quoted key, or everything up
+/// to the first `:` that is followed by a space or ends the line.
+fn key_token_len(line: &str) -> usize {
+    if let Some(quote) = line.chars().next().filter(|c| *c == '"' || *c == '\'') {
+        return line[1..].find(quote).map_or(line.len(), |end| end + 2);
+    }
+    line.char_indices()
+        .find(|&(index, c)| c == ':' && line[index + 1..].chars().next().is_none_or(|n| n == ' '))
+        .map_or(line.len(), |(index, _)| index)
+}
+
+fn unquote(key: &str) -> &str {
+    key.trim_matches(|c| c == '"' || c == '\'')
+}
+
+/// Quotes keys such as `myapp:location` the way serde_yaml writes them.
+fn quote_key(key: &str) -> String {
+    if key.contains(':') {
+        format!("\"{}\"", key)
+    } else {
+        key.to_string()
+    }
+}
+
+/// Comment after a scalar value on an entry line, e.g. `# primary region`.
+fn trailing_comment(line: &str) -> Option<&str> {
+    let trimmed = line.trim_start();
+    let rest = &trimmed[key_token_len(trimmed)..];
+    let value = rest.strip_prefix(':')?.trim_start();
+    let search_from = match value.chars().next() {
+        Some(quote @ ('"' | '\'')) => value[1..].find(quote).map_or(value.len(), |end| end + 2),
+        _ => 0,
+    };
+    value[search_from..]
+        .find(" #")
+        .or_else(|| value[search_from..].starts_with('#').then_some(0))
+        .map(|offset| value[search_from + offset..].trim_start())
+}
+
+/// Renders an entry, with nested values on indented lines below the key.
+fn render_entry(indent: usize, key_text: &str, value: &Value) -> Vec<String> {
+    let yaml = serde_yaml::to_string(value).unwrap_or_default();
+    let yaml = yaml.trim_start_matches("---").trim();
+    let pad = " ".repeat(indent);
+    let nested = matches!(value, Value::Mapping(m) if !m.is_empty())
+        || matches!(value, Value::Sequence(s) if !s.is_empty());
+    if nested {
+        let mut lines = vec![format!("{}{}:", pad, key_text)];
+        lines.extend(yaml.lines().map(|line| format!("{}  {}", pad, line)));
+        lines
+    } else {
+        vec![format!("{}{}: {}", pad, key_text, yaml)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STACK_FILE: &str = "# Owned by the platform team
+environment:
+  - shared/azure
+config:
+  \"myapp:env\": dev
+  # Primary region
+  \"myapp:location\": eastus2 # paired with centralus
+  \"myapp:tags\":
+    costCenter: \"42\"
+  \"myapp:cacheCapacity\": 1
+
+  # trailing note
+";
+
+    #[test]
+    fn test_set_keeps_order_and_comments() {
+        let mut document = StackDocument::parse(STACK_FILE);
+
+        document.set("myapp:location", &Value::from("westus3"));
+        document.set("myapp:cacheCapacity", &Value::from(2));
+        document.set(
+            "myapp:virtualSubnetworks",
+            &serde_yaml::from_str("[10.0.0.0/24]").unwrap(),
+        );
+
+        assert_eq!(
+            document.to_string(),
+            "# Owned by the platform team
+environment:
+  - shared/azure
+config:
+  \"myapp:env\": dev
+  # Primary region
+  \"myapp:location\": westus3 # paired with centralus
+  \"myapp:tags\":
+    costCenter: \"42\"
+  \"myapp:cacheCapacity\": 2
+  \"myapp:virtualSubnetworks\":
+    - 10.0.0.0/24
+
+  # trailing note
+"
+        );
+        let parsed: Value = serde_yaml::from_str(&document.to_string()).unwrap();
+        assert_eq!(
+            parsed["config"]["myapp:virtualSubnetworks"][0],
+            "10.0.0.0/24"
+        );
+    }
+
+    #[test]
+    fn test_remove_nested_entry() {
+        let mut document = StackDocument::parse(STACK_FILE);
+
+        assert!(document.remove("myapp:tags"));
+        assert!(!document.remove("myapp:tags"));
+
+        let content = document.to_string();
+        assert!(!content.contains("costCenter"));
+        assert!(content.contains(
+            "  \"myapp:location\": eastus2 # paired with centralus\n  \"myapp:cacheCapacity\": 1\n"
+        ));
+    }
+
+    #[test]
+    fn test_replace_block_scalar_with_blank_line() {
+        let mut document = StackDocument::parse(
+            "config:\n  myapp:motd: |\n    first paragraph\n\n    second paragraph\n\n  myapp:env: dev\n",
+        );
+
+        document.set("myapp:motd", &Value::from("short"));
+        assert_eq!(
+            document.to_string(),
+            "config:\n  myapp:motd: short\n\n  myapp:env: dev\n"
+        );
+
+        assert!(document.remove("myapp:motd"));
+        assert_eq!(document.to_string(), "config:\n\n  myapp:env: dev\n");
+    }
+
+    #[test]
+    fn test_remove_same_indent_sequence() {
+        let mut document = StackDocument::parse(
+            "config:\n  myapp:regions:\n  - eastus2\n  - centralus\n  myapp:env: dev\n",
+        );
+
+        assert!(document.remove("myapp:regions"));
+        assert_eq!(document.to_string(), "config:\n  myapp:env: dev\n");
+
+        let mut document =
+            StackDocument::parse("config:\n  myapp:env: dev\n  myapp:regions:\n  - eastus2\n");
+        document.set("myapp:regions", &serde_yaml::from_str("[westus3]").unwrap());
+        assert_eq!(
+            document.to_string(),
+            "config:\n  myapp:env: dev\n  myapp:regions:\n    - westus3\n"
+        );
+    }
+
+    #[test]
+    fn test_set_adds_config_block() {
+        let mut document = StackDocument::parse("environment:\n  - shared/azure\n");
+
+        document.set("myapp:version", &Value::from("13"));
+
+        assert_eq!(
+            document.to_string(),
+            "environment:\n  - shared/azure\nconfig:\n  \"myapp:version\": \"13\"\n"
+        );
+    }
+}
@@ -0,0 +1,496 @@
+use anyhow::{anyhow, Error};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::net::Ipv4Addr;
+use std::path::{Path, PathBuf};
+
+use crate::helpers::network_plan::Ipv4Cidr;
+
+/// Organization default registry embedded in the binary
+const ORG_REGISTRY: &str = include_str!("../../config/subscriptions.yaml");
+
+/// File name of the registry inside the user and project config directories
+const REGISTRY_FILE_NAME: &str = "subscriptions.yaml";
+
+/// Pulumi backend used when a registry entry does not set `backend_url`
+const DEFAULT_BACKEND_URL: &str = "https://api.pulumi.com";
+
+/// Subscription tiers, ordered from least to most critical.
+pub const TIERS: &[&str] = &["bronze", "silver", "gold", "platinum"];
+
+/// Azure subscription an environment deploys to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AzureSubscription {
+    pub env: String,
+    pub id: String,
+    pub name: String,
+    pub tier: String,
+    pub location: String,
+    /// Address range the subscription's virtual network was given, from which
+    /// subnets are planned when `virtual_subnets` is empty.
+    pub address_space: Option<String>,
+    pub virtual_subnets: Vec<String>,
+    pub tags: BTreeMap<String, String>,
+    /// Azure DevOps service connection that deploys to the subscription.
+    pub service_connection: String,
+    /// Azure DevOps variable groups linked to the environment's pipeline stages.
+    pub variable_groups: Vec<String>,
+    /// Pulumi backend the environment's stacks are stored in.
+    pub backend_url: String,
+    /// Monthly budget of the environment, or else of its tier.
+    pub budget: Option<f64>,
+}
+
+/// One entry of a registry file. Every field except `env` is optional so that
+/// user and project files only need to list what they override.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(deny_unknown_fields)]
+struct SubscriptionEntry {
+    env: String,
+    id: Option<String>,
+    name: Option<String>,
+    tier: Option<String>,
+    location: Option<String>,
+    address_space: Option<String>,
+    subnets: Option<Vec<String>>,
+    tags: Option<BTreeMap<String, String>>,
+    service_connection: Option<String>,
+    variable_groups: Option<Vec<String>>,
+    backend_url: Option<String>,
+    budget: Option<f64>,
+}
+
+impl SubscriptionEntry {
+    /// Overwrites the fields that are set in `other`.
+    fn merge(&mut self, other: SubscriptionEntry) {
+        self.id = other.id.or(self.id.take());
+        self.name = other.name.or(self.name.take());
+        self.tier = other.tier.or(self.tier.take());
+        self.location = other.location.or(self.location.take());
+        self.address_space = other.address_space.or(self.address_space.take());
+        self.subnets = other.subnets.or(self.subnets.take());
+        self.tags = other.tags.or(self.tags.take());
+        self.service_connection = other.service_connection.or(self.service_connection.take());
+        self.variable_groups = other.variable_groups.or(self.variable_groups.take());
+        self.backend_url = other.backend_url.or(self.backend_url.take());
+        self.budget = other.budget.or(self.budget.take());
+    }
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+struct RegistryFile {
+    #[serde(default)]
+    subscriptions: Vec<SubscriptionEntry>,
+    /// Monthly budgets of the environments of a tier that set no budget of their own.
+    #[serde(default)]
+    tier_budgets: BTreeMap<String, f64>,
+}
+
+/// The subscriptions each environment deploys to, merged from the organization
+/// default, the user file and the project file.
+#[derive(Debug, Clone)]
+pub struct SubscriptionRegistry {
+    subscriptions: Vec<AzureSubscription>,
+}
+
+impl SubscriptionRegistry {
+    /// Loads the organization default and layers the user and project files on top, if they exist.
+    pub fn load() -> Result<Self, Error> {
+        Self::load_from(&ConfigRoots::current()?)
+    }
+
+    /// Loads the organization default and layers the files under the given roots on top.
+    pub fn load_from(roots: &ConfigRoots) -> Result<Self, Error> {
+        Self::from_layers(
+            &roots.read_layers(("organization default", ORG_REGISTRY), REGISTRY_FILE_NAME)?,
+        )
+    }
+
+    /// Builds a registry from (source, YAML) layers, later layers overriding earlier ones.
+    pub(crate) fn from_layers(layers: &[(String, String)]) -> Result<Self, Error> {
+        let mut entries: Vec<SubscriptionEntry> = Vec::new();
+        let mut tier_budgets: BTreeMap<String, f64> = BTreeMap::new();
+
+        for (source, content) in layers {
+            let file: RegistryFile = serde_yaml::from_str(content)
+                .map_err(|err| anyhow!("Invalid subscription registry {}: {}", source, err))?;
+            for (tier, budget) in file.tier_budgets {
+                if !TIERS.contains(&tier.as_str()) {
+                    return Err(anyhow!(
+                        "Invalid subscription registry {}: unknown tier '{}' in tier_budgets, expected one of {}",
+                        source,
+                        tier,
+                        TIERS.join(", ")
+                    ));
+                }
+                tier_budgets.insert(tier, budget);
+            }
+            for entry in file.subscriptions {
+                match entries.iter_mut().find(|e| e.env == entry.env) {
+                    Some(existing) => existing.merge(entry),
+                    None => entries.push(entry),
+                }
+            }
+        }
+
+        let subscriptions = entries
+            .into_iter()
+            .map(|entry| {
+                let mut subscription = validate_entry(entry)?;
+                if subscription.budget.is_none() {
+                    subscription.budget = tier_budgets.get(&subscription.tier).copied();
+                }
+                Ok(subscription)
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        Ok(Self { subscriptions })
+    }
+
+    /// Returns the subscription for an environment.
+    pub fn get(&self, environment: &str) -> Result<AzureSubscription, Error> {
+        self.subscriptions
+            .iter()
+            .find(|sub| sub.env == environment)
+            .cloned()
+            .ok_or_else(|| {
+                anyhow!(
+                    "Unknown environment '{}'. Known environments: {}",
+                    environment,
+                    self.environment_names().join(", ")
+                )
+            })
+    }
+
+    /// Every subscription in the registry, in registry order.
+    pub fn subscriptions(&self) -> &[AzureSubscription] {
+        &self.subscriptions
+    }
+
+    /// Names of every environment in the registry, in registry order.
+    pub fn environment_names(&self) -> Vec<String> {
+        self.subscriptions
+            .iter()
+            .map(|sub| sub.env.clone())
+            .collect()
+    }
+}
+
+/// Directories the user and project config files are read from.
+#[derive(Debug, Clone)]
+pub struct ConfigRoots {
+    /// User config directory, see [`user_config_dir`].
+    pub user_dir: Option<PathBuf>,
+    /// Project root, whose `.pulumimi` directory holds the project files.
+    pub project_root: PathBuf,
+}
+
+impl ConfigRoots {
+    /// The user config directory and the current directory.
+    pub fn current() -> Result<Self, Error> {
+        Ok(Self {
+            user_dir: user_config_dir(),
+            project_root: env::current_dir()?,
+        })
+    }
+
+    /// Paths of a config file in the user and project config directories, in the order
+    /// they are applied.
+    pub fn layer_paths(&self, file_name: &str) -> Vec<PathBuf> {
+        let mut paths = Vec::new();
+        if let Some(dir) = &self.user_dir {
+            paths.push(dir.join(file_name));
+        }
+        paths.push(project_config_dir(&self.project_root).join(file_name));
+        paths
+    }
+
+    /// The (source, content) layers of a config file: the embedded default, followed by
+    /// the user and project files that exist.
+    pub fn read_layers(
+        &self,
+        default: (&str, &str),
+        file_name: &str,
+    ) -> Result<Vec<(String, String)>, Error> {
+        let mut layers = vec![(default.0.to_string(), default.1.to_string())];
+        for path in self.layer_paths(file_name) {
+            if path.exists() {
+                let content = fs::read_to_string(&path)
+                    .map_err(|err| anyhow!("Failed to read {}: {}", path.display(), err))?;
+                layers.push((path.display().to_string(), content));
+            }
+        }
+        Ok(layers)
+    }
+}
+
+/// Per-user config directory: `$PULUMIMI_CONFIG_DIR`, else `$XDG_CONFIG_HOME/pulumimi`,
+/// else `~/.config/pulumimi` (`%APPDATA%\pulumimi` on Windows).
+pub fn user_config_dir() -> Option<PathBuf> {
+    if let Ok(dir) = env::var("PULUMIMI_CONFIG_DIR") {
+        return Some(PathBuf::from(dir));
+    }
+    if let Ok(dir) = env::var("XDG_CONFIG_HOME") {
+        return Some(PathBuf::from(dir).join("pulumimi"));
+    }
+    if let Ok(dir) = env::var("APPDATA") {
+        return Some(PathBuf::from(dir).join("pulumimi"));
+    }
+    env::var("HOME")
+        .ok()
+        .map(|home| PathBuf::from(home).join(".config").join("pulumimi"))
+}
+
+/// Project config directory (`.pulumimi`) under the given root.
+pub fn project_config_dir(root: &Path) -> PathBuf {
+    root.join(".pulumimi")
+}
+
+/// Checks that a merged entry is complete and well formed.
+fn validate_entry(entry: SubscriptionEntry) -> Result<AzureSubscription, Error> {
+    let env = entry.env;
+    let invalid =
+        |reason: String| anyhow!("Invalid subscription for environment '{}': {}", env, reason);
+
+    if env.is_empty()
+        || !env
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+    {
+        return Err(invalid(
+            "environment names may only contain lowercase letters, digits and dashes".to_string(),
+        ));
+    }
+
+    let required = |field: Option<String>, name: &str| match field {
+        Some(value) if !value.trim().is_empty() => Ok(value),
+        _ => Err(invalid(format!("'{}' is required", name))),
+    };
+    let id = required(entry.id, "id")?;
+    let name = required(entry.name, "name")?;
+    let tier = required(entry.tier, "tier")?;
+    let location = required(entry.location, "location")?;
+
+    if !TIERS.contains(&tier.as_str()) {
+        return Err(invalid(format!(
+            "unknown tier '{}', expected one of {}",
+            tier,
+            TIERS.join(", ")
+        )));
+    }
+    if location.contains(char::is_whitespace) || location != location.to_lowercase() {
+        return Err(invalid(format!(
+            "location '{}' must be an Azure region name such as 'eastus2'",
+            location
+        )));
+    }
+
+    if let Some(address_space) = &entry.address_space {
+        Ipv4Cidr::parse(address_space).map_err(|err| invalid(format!("address space {}", err)))?;
+    }
+
+    let subnets = entry.subnets.unwrap_or_default();
+    if let Some(subnet) = subnets.iter().find(|subnet| !is_ipv4_cidr(subnet)) {
+        return Err(invalid(format!("subnet '{}' is not an IPv4 CIDR", subnet)));
+    }
+
+    let tags = entry.tags.unwrap_or_default();
+    if let Some((key, _)) = tags
+        .iter()
+        .find(|(key, value)| key.trim().is_empty() || value.trim().is_empty())
+    {
+        return Err(invalid(format!(
+            "tag '{}' must have a non-empty name and value",
+            key
+        )));
+    }
+
+    if let Some(budget) = entry
+        .budget
+        .filter(|budget| !budget.is_finite() || *budget < 0.0)
+    {
+        return Err(invalid(format!("budget {} must not be negative", budget)));
+    }
+
+    // Defaults to a connection named after the subscription, e.g. `azure-dev01`
+    let service_connection = entry
+        .service_connection
+        .filter(|connection| !connection.trim().is_empty())
+        .unwrap_or_else(|| format!("azure-{}", name));
+
+    // Defaults to Pulumi Cloud
+    let backend_url = entry
+        .backend_url
+        .filter(|url| !url.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_BACKEND_URL.to_string());
+
+    Ok(AzureSubscription {
+        env,
+        id,
+        name,
+        tier,
+        location,
+        address_space: entry.address_space,
+        virtual_subnets: subnets,
+        tags,
+        service_connection,
+        variable_groups: entry.variable_groups.unwrap_or_default(),
+        backend_url,
+        budget: entry.budget,
+    })
+}
+
+/// Returns true for strings such as "10.0.0.0/24".
+fn is_ipv4_cidr(value: &str) -> bool {
+    match value.split_once('/') {
+        Some((addr, prefix)) => {
+            addr.parse::<Ipv4Addr>().is_ok() && prefix.parse::<u8>().is_ok_and(|p| p <= 32)
+        }
+        None => false,
+    }
+}
+
+/// Points the user config directory of the test process at a directory without config
+/// files, for tests of code that loads its own registry, tag policy or price catalog.
+#[cfg(test)]
+pub fn isolate_user_config() {
+    static ISOLATE: std::sync::Once = std::sync::Once::new();
+    ISOLATE.call_once(|| {
+        let dir = env::temp_dir().join(format!("pulumimi-tests-{}", std::process::id()));
+        env::set_var("PULUMIMI_CONFIG_DIR", dir);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layer(source: &str, content: &str) -> (String, String) {
+        (source.to_string(), content.to_string())
+    }
+
+    #[test]
+    fn test_org_registry_is_valid() {
+        let registry = SubscriptionRegistry::from_layers(&[layer("org", ORG_REGISTRY)]).unwrap();
+
+        assert_eq!(
+            registry.environment_names(),
+            vec!["dev", "test", "stage", "prod"]
+        );
+        let prod = registry.get("prod").unwrap();
+        assert_eq!(prod.name, "prod01");
+        assert_eq!(prod.tier, "platinum");
+        assert_eq!(prod.location, "eastus2");
+        assert_eq!(prod.service_connection, "azure-prod01");
+    }
+
+    #[test]
+    fn test_load_from_user_and_project_dirs() {
+        let user = tempfile::tempdir().unwrap();
+        let project = tempfile::tempdir().unwrap();
+        let roots = ConfigRoots {
+            user_dir: Some(user.path().to_path_buf()),
+            project_root: project.path().to_path_buf(),
+        };
+        assert_eq!(
+            roots.layer_paths(REGISTRY_FILE_NAME),
+            [
+                user.path().join(REGISTRY_FILE_NAME),
+                project.path().join(".pulumimi").join(REGISTRY_FILE_NAME)
+            ]
+        );
+
+        // Missing files are skipped
+        let registry = SubscriptionRegistry::load_from(&roots).unwrap();
+        assert_eq!(registry.get("dev").unwrap().location, "eastus2");
+
+        fs::write(
+            user.path().join(REGISTRY_FILE_NAME),
+            "subscriptions:\n  - env: dev\n    location: westeurope\n    name: user-dev\n",
+        )
+        .unwrap();
+        fs::create_dir_all(project_config_dir(project.path())).unwrap();
+        fs::write(
+            project_config_dir(project.path()).join(REGISTRY_FILE_NAME),
+            "subscriptions:\n  - env: dev\n    name: project-dev\n",
+        )
+        .unwrap();
+        let dev = SubscriptionRegistry::load_from(&roots)
+            .unwrap()
+            .get("dev")
+            .unwrap();
+        assert_eq!(dev.location, "westeurope");
+        assert_eq!(dev.name, "project-dev");
+    }
+
+    #[test]
+    fn test_later_layers_override_fields_and_add_environments() {
+        let user = "tier_budgets:\n  bronze: 300\n  platinum: 5000\nsubscriptions:\n  - env: dev\n    id: user-dev-id\n";
+        let project = "subscriptions:\n  - env: dev\n    subnets: [10.1.0.0/24]\n  - env: qa\n    id: qa-id\n    name: qa01\n    tier: silver\n    location: centralus\n  - env: prod\n    budget: 8000\n";
+
+        let registry = SubscriptionRegistry::from_layers(&[
+            layer("org", ORG_REGISTRY),
+            layer("user", user),
+            layer("project", project),
+        ])
+        .unwrap();
+
+        let dev = registry.get("dev").unwrap();
+        assert_eq!(dev.id, "user-dev-id");
+        assert_eq!(dev.name, "dev01");
+        assert_eq!(dev.virtual_subnets, vec!["10.1.0.0/24"]);
+        assert_eq!(registry.get("qa").unwrap().location, "centralus");
+        assert_eq!(registry.environment_names().last().unwrap(), "qa");
+
+        // An environment's own budget wins over the budget of its tier
+        assert_eq!(dev.budget, Some(300.0));
+        assert_eq!(registry.get("prod").unwrap().budget, Some(8000.0));
+        assert_eq!(registry.get("qa").unwrap().budget, None);
+    }
+
+    #[test]
+    fn test_invalid_entries_are_rejected() {
+        let missing_id = "subscriptions:\n  - env: qa\n    name: qa01\n    tier: silver\n    location: eastus2\n";
+        let err = SubscriptionRegistry::from_layers(&[layer("project", missing_id)]).unwrap_err();
+        assert!(err.to_string().contains("'id' is required"));
+
+        let bad_tier = "subscriptions:\n  - env: qa\n    id: x\n    name: qa01\n    tier: tin\n    location: eastus2\n";
+        let err = SubscriptionRegistry::from_layers(&[layer("project", bad_tier)]).unwrap_err();
+        assert!(err.to_string().contains("unknown tier 'tin'"));
+
+        let bad_subnet = "subscriptions:\n  - env: dev\n    subnets: ['']\n";
+        let err = SubscriptionRegistry::from_layers(&[
+            layer("org", ORG_REGISTRY),
+            layer("project", bad_subnet),
+        ])
+        .unwrap_err();
+        assert!(err.to_string().contains("is not an IPv4 CIDR"));
+
+        let bad_space = "subscriptions:\n  - env: dev\n    address_space: 10.1.0.1/16\n";
+        let err = SubscriptionRegistry::from_layers(&[
+            layer("org", ORG_REGISTRY),
+            layer("project", bad_space),
+        ])
+        .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("address space '10.1.0.1/16' has host bits set"));
+
+        let unknown_field = "subscriptions:\n  - env: dev\n    region: eastus\n";
+        assert!(SubscriptionRegistry::from_layers(&[layer("project", unknown_field)]).is_err());
+    }
+
+    #[test]
+    fn test_unknown_environment_is_an_error() {
+        let registry = SubscriptionRegistry::from_layers(&[layer("org", ORG_REGISTRY)]).unwrap();
+
+        let err = registry.get("sandbox").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Unknown environment 'sandbox'. Known environments: dev, test, stage, prod"
+        );
+    }
+}
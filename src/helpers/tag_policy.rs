@@ -0,0 +1,372 @@
+use anyhow::{anyhow, Error};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::helpers::subscription_registry::{AzureSubscription, ConfigRoots};
+use crate::tui::app::ProjectConfig;
+
+/// Organization default tag policy embedded in the binary
+const ORG_POLICY: &str = include_str!("../../config/tag-policy.yaml");
+
+/// File name of the policy inside the user and project config directories
+const POLICY_FILE_NAME: &str = "tag-policy.yaml";
+
+/// Characters Azure does not allow in tag names.
+const FORBIDDEN_TAG_CHARS: &[char] = &['<', '>', '%', '&', '\\', '?', '/'];
+
+/// Longest tag value Azure accepts.
+const MAX_TAG_VALUE_LEN: usize = 256;
+
+/// Where the value of a tag comes from.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum TagSource {
+    /// The application name.
+    App,
+    /// The owner email.
+    Owner,
+    /// The environment, e.g. `dev`.
+    Environment,
+    /// The subscription tier, e.g. `bronze`.
+    Tier,
+    /// The environment's primary Azure region.
+    Location,
+    /// The subscription name, e.g. `dev01`.
+    Subscription,
+    /// A tag of the environment in the subscription registry.
+    RegistryTag(String),
+    /// A fixed value.
+    Value(String),
+    /// Only set with `--tag <name>=<value>`.
+    Input,
+}
+
+impl TagSource {
+    fn value(&self, config: &ProjectConfig, azure_subscription: &AzureSubscription) -> String {
+        match self {
+            TagSource::App => config.resource_name.clone(),
+            TagSource::Owner => config.owner_email.clone(),
+            TagSource::Environment => azure_subscription.env.clone(),
+            TagSource::Tier => azure_subscription.tier.clone(),
+            TagSource::Location => azure_subscription.location.clone(),
+            TagSource::Subscription => azure_subscription.name.clone(),
+            TagSource::RegistryTag(tag) => azure_subscription
+                .tags
+                .get(tag)
+                .cloned()
+                .unwrap_or_default(),
+            TagSource::Value(value) => value.clone(),
+            TagSource::Input => String::new(),
+        }
+    }
+}
+
+impl fmt::Display for TagSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TagSource::App => write!(f, "the app name"),
+            TagSource::Owner => write!(f, "the owner email"),
+            TagSource::Environment => write!(f, "the environment"),
+            TagSource::Tier => write!(f, "the subscription tier"),
+            TagSource::Location => write!(f, "the location"),
+            TagSource::Subscription => write!(f, "the subscription name"),
+            TagSource::RegistryTag(tag) => write!(f, "subscription registry tag '{}'", tag),
+            TagSource::Value(_) => write!(f, "a fixed value"),
+            TagSource::Input => write!(f, "--tag only"),
+        }
+    }
+}
+
+/// One tag of the policy.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct TagRule {
+    pub name: String,
+    pub source: TagSource,
+    #[serde(default = "default_required")]
+    pub required: bool,
+}
+
+fn default_required() -> bool {
+    true
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+struct PolicyFile {
+    #[serde(default)]
+    tags: Vec<TagRule>,
+}
+
+/// The tags every generated resource carries, merged from the organization default,
+/// the user file and the project file.
+#[derive(Debug, Clone)]
+pub struct TagPolicy {
+    rules: Vec<TagRule>,
+}
+
+impl TagPolicy {
+    /// Loads the organization default and layers the user and project files on top, if they exist.
+    pub fn load() -> Result<Self, Error> {
+        Self::load_from(&ConfigRoots::current()?)
+    }
+
+    /// Loads the organization default and layers the files under the given roots on top.
+    pub fn load_from(roots: &ConfigRoots) -> Result<Self, Error> {
+        Self::from_layers(
+            &roots.read_layers(("organization default", ORG_POLICY), POLICY_FILE_NAME)?,
+        )
+    }
+
+    /// Builds a policy from (source, YAML) layers, later layers replacing rules with the same name.
+    fn from_layers(layers: &[(String, String)]) -> Result<Self, Error> {
+        let mut rules: Vec<TagRule> = Vec::new();
+        for (source, content) in layers {
+            let file: PolicyFile = serde_yaml::from_str(content)
+                .map_err(|err| anyhow!("Invalid tag policy {}: {}", source, err))?;
+            for rule in file.tags {
+                validate_tag_name(&rule.name)
+                    .map_err(|err| anyhow!("Invalid tag policy {}: {}", source, err))?;
+                match rules.iter_mut().find(|r| r.name == rule.name) {
+                    Some(existing) => *existing = rule,
+                    None => rules.push(rule),
+                }
+            }
+        }
+        Ok(Self { rules })
+    }
+
+    /// Required tags the TUI cannot give a value and `tags` does not set: `input` tags,
+    /// and registry tags that no subscription has. Interactive mode checks them before
+    /// the session starts.
+    pub fn missing_inputs(
+        &self,
+        tags: &BTreeMap<String, String>,
+        subscriptions: &[AzureSubscription],
+    ) -> Vec<String> {
+        self.rules
+            .iter()
+            .filter(|rule| rule.required)
+            .filter(|rule| match &rule.source {
+                TagSource::Input => true,
+                TagSource::RegistryTag(name) => subscriptions
+                    .iter()
+                    .all(|subscription| !subscription.tags.contains_key(name)),
+                _ => false,
+            })
+            .filter(|rule| {
+                tags.get(&rule.name)
+                    .is_none_or(|value| value.trim().is_empty())
+            })
+            .map(|rule| rule.name.clone())
+            .collect()
+    }
+
+    /// Works out the tags of an environment. Values given with `--tag` override the
+    /// policy sources and may add tags the policy does not list.
+    ///
+    /// # Returns
+    /// - An error listing every required tag without a value.
+    pub fn resolve(
+        &self,
+        config: &ProjectConfig,
+        azure_subscription: &AzureSubscription,
+    ) -> Result<BTreeMap<String, String>, Error> {
+        let mut tags = BTreeMap::new();
+        let mut missing = Vec::new();
+
+        for rule in &self.rules {
+            let value = match config.tags.get(&rule.name) {
+                Some(value) => value.clone(),
+                None => rule.source.value(config, azure_subscription),
+            };
+            if !value.trim().is_empty() {
+                tags.insert(rule.name.clone(), value);
+            } else if rule.required {
+                missing.push(match rule.source {
+                    TagSource::Input => rule.name.clone(),
+                    _ => format!("{} ({})", rule.name, rule.source),
+                });
+            }
+        }
+        if !missing.is_empty() {
+            return Err(anyhow!(
+                "Required tags have no value in environment '{}': {}. Set them with --tag <name>=<value>",
+                azure_subscription.env,
+                missing.join(", ")
+            ));
+        }
+
+        for (name, value) in &config.tags {
+            validate_tag_name(name)?;
+            tags.insert(name.clone(), value.clone());
+        }
+        if let Some((name, _)) = tags
+            .iter()
+            .find(|(_, value)| value.chars().count() > MAX_TAG_VALUE_LEN)
+        {
+            return Err(anyhow!(
+                "Tag '{}' is longer than {} characters",
+                name,
+                MAX_TAG_VALUE_LEN
+            ));
+        }
+        Ok(tags)
+    }
+}
+
+fn validate_tag_name(name: &str) -> Result<(), Error> {
+    if name.trim().is_empty() || name.contains(FORBIDDEN_TAG_CHARS) {
+        return Err(anyhow!(
+            "'{}' is not a valid tag name; names cannot be empty or contain {}",
+            name,
+            FORBIDDEN_TAG_CHARS.iter().collect::<String>()
+        ));
+    }
+    Ok(())
+}
+
+/// Parses a `--tag` argument such as `cost-center=1234`.
+pub fn parse_tag(value: &str) -> Result<(String, String), String> {
+    match value.split_once('=') {
+        Some((name, tag_value)) if !name.trim().is_empty() => {
+            Ok((name.trim().to_string(), tag_value.trim().to_string()))
+        }
+        _ => Err(format!("'{}' is not of the form <name>=<value>", value)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layer(source: &str, content: &str) -> (String, String) {
+        (source.to_string(), content.to_string())
+    }
+
+    fn subscription() -> AzureSubscription {
+        AzureSubscription {
+            env: "dev".to_string(),
+            id: "1234".to_string(),
+            name: "dev01".to_string(),
+            tier: "bronze".to_string(),
+            location: "eastus2".to_string(),
+            address_space: None,
+            virtual_subnets: Vec::new(),
+            tags: BTreeMap::from([("costCenter".to_string(), "42".to_string())]),
+            service_connection: "azure-dev01".to_string(),
+            variable_groups: Vec::new(),
+            backend_url: "https://api.pulumi.com".to_string(),
+            budget: None,
+        }
+    }
+
+    #[test]
+    fn test_resolve_org_policy() {
+        let policy = TagPolicy::from_layers(&[layer("org", ORG_POLICY)]).unwrap();
+        let mut config = ProjectConfig {
+            resource_name: "myapp".to_string(),
+            owner_email: "owner@example.com".to_string(),
+            ..Default::default()
+        };
+        config
+            .tags
+            .insert("data-classification".to_string(), "internal".to_string());
+        config
+            .tags
+            .insert("project".to_string(), "apollo".to_string());
+
+        let tags = policy.resolve(&config, &subscription()).unwrap();
+
+        assert_eq!(
+            tags,
+            BTreeMap::from(
+                [
+                    ("app", "myapp"),
+                    ("cost-center", "42"),
+                    ("data-classification", "internal"),
+                    ("env", "dev"),
+                    ("owner", "owner@example.com"),
+                    ("project", "apollo"),
+                    ("tier", "bronze"),
+                ]
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+            )
+        );
+    }
+
+    #[test]
+    fn test_missing_required_tags_are_an_error() {
+        let policy = TagPolicy::from_layers(&[layer("org", ORG_POLICY)]).unwrap();
+        let config = ProjectConfig {
+            resource_name: "myapp".to_string(),
+            ..Default::default()
+        };
+        let mut azure_subscription = subscription();
+        azure_subscription.tags.clear();
+
+        let err = policy.resolve(&config, &azure_subscription).unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "Required tags have no value in environment 'dev': owner (the owner email), cost-center (subscription registry tag 'costCenter'), data-classification. Set them with --tag <name>=<value>"
+        );
+        assert_eq!(
+            policy.missing_inputs(&config.tags, &[azure_subscription.clone()]),
+            ["cost-center", "data-classification"]
+        );
+        assert_eq!(
+            policy.missing_inputs(&config.tags, &[subscription()]),
+            ["data-classification"]
+        );
+    }
+
+    #[test]
+    fn test_load_from_project_dir() {
+        let project = tempfile::tempdir().unwrap();
+        let roots = ConfigRoots {
+            user_dir: None,
+            project_root: project.path().to_path_buf(),
+        };
+        assert_eq!(TagPolicy::load_from(&roots).unwrap().rules.len(), 6);
+
+        let dir = project.path().join(".pulumimi");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join(POLICY_FILE_NAME),
+            "tags:\n  - name: team\n    source: input\n",
+        )
+        .unwrap();
+        assert_eq!(TagPolicy::load_from(&roots).unwrap().rules.len(), 7);
+    }
+
+    #[test]
+    fn test_later_layers_replace_rules() {
+        let project = "tags:\n  - name: data-classification\n    source:\n      value: public\n  - name: team\n    source: input\n    required: false\n";
+        let policy =
+            TagPolicy::from_layers(&[layer("org", ORG_POLICY), layer("project", project)]).unwrap();
+        assert_eq!(
+            policy.rules[5].source,
+            TagSource::Value("public".to_string())
+        );
+        assert_eq!(policy.rules.len(), 7);
+
+        let config = ProjectConfig {
+            resource_name: "myapp".to_string(),
+            owner_email: "owner@example.com".to_string(),
+            ..Default::default()
+        };
+        let tags = policy.resolve(&config, &subscription()).unwrap();
+        assert_eq!(tags["data-classification"], "public");
+        assert!(!tags.contains_key("team"));
+
+        let bad_name = "tags:\n  - name: a/b\n    source: app\n";
+        assert!(TagPolicy::from_layers(&[layer("project", bad_name)]).is_err());
+        assert_eq!(
+            parse_tag("cost-center=1234").unwrap(),
+            ("cost-center".to_string(), "1234".to_string())
+        );
+        assert!(parse_tag("cost-center").is_err());
+    }
+}
@@ -0,0 +1,216 @@
+use anyhow::{anyhow, Error};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use crate::helpers::config_helper::stack_name;
+use crate::helpers::snippet_catalog::resolve_in_dependency_order;
+use crate::helpers::subscription_registry::{AzureSubscription, SubscriptionRegistry};
+use crate::tui::app::{DriftNotify, ProjectConfig, SecretMode};
+
+/// Directory of the variable group definitions inside the project
+pub const VARIABLE_GROUP_DIR: &str = "variable-groups";
+
+/// A secret snippet input supplied to Pulumi from a pipeline variable.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SecretInput {
+    /// Stack config key, e.g. `databaseAdministratorLoginPassword`.
+    pub config_key: String,
+    /// Pipeline variable, e.g. `DATABASE_ADMINISTRATOR_LOGIN_PASSWORD`.
+    pub variable: String,
+}
+
+/// One variable of a group.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct GroupVariable {
+    pub value: String,
+    pub is_secret: bool,
+}
+
+/// An Azure DevOps variable group, in the shape the variable groups REST API takes.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct VariableGroup {
+    pub name: String,
+    pub description: String,
+    #[serde(rename = "type")]
+    pub group_type: String,
+    pub variables: BTreeMap<String, GroupVariable>,
+}
+
+/// Name of the variable group of an environment, e.g. `myapp-dev`.
+pub fn variable_group_name(config: &ProjectConfig, env: &str) -> String {
+    format!("{}-{}", config.resource_name, env)
+}
+
+/// Secret snippet inputs the pipeline supplies. Generated secrets (`SecretMode::KeyVault`)
+/// never leave Azure, so there are none to supply.
+pub fn secret_inputs(config: &ProjectConfig) -> Result<Vec<SecretInput>, Error> {
+    if config.secret_mode == SecretMode::KeyVault {
+        return Ok(Vec::new());
+    }
+    let snippets = resolve_in_dependency_order(&config.additional_resources, config.secret_mode)?;
+    Ok(snippets
+        .iter()
+        .flat_map(|snippet| {
+            snippet.secrets.iter().map(|secret| SecretInput {
+                config_key: snippet.config_key(secret.name),
+                variable: snippet.pipeline_variable_name(secret.name),
+            })
+        })
+        .collect())
+}
+
+/// Builds the variable group of an environment: its subscription and Pulumi backend,
+/// plus empty secret variables for the Pulumi access token and every secret input.
+pub fn variable_group(
+    config: &ProjectConfig,
+    azure_subscription: &AzureSubscription,
+    secrets: &[SecretInput],
+) -> VariableGroup {
+    let plain = |value: &str| GroupVariable {
+        value: value.to_string(),
+        is_secret: false,
+    };
+    let secret = || GroupVariable {
+        value: String::new(),
+        is_secret: true,
+    };
+
+    let mut variables = BTreeMap::from([
+        (
+            "ARM_SUBSCRIPTION_ID".to_string(),
+            plain(&azure_subscription.id),
+        ),
+        ("PULUMI_ACCESS_TOKEN".to_string(), secret()),
+        (
+            "PULUMI_BACKEND_URL".to_string(),
+            plain(&azure_subscription.backend_url),
+        ),
+        (
+            "PULUMI_STACK".to_string(),
+            plain(&stack_name(config, &azure_subscription.env)),
+        ),
+    ]);
+    for input in secrets {
+        variables.insert(input.variable.clone(), secret());
+    }
+    let drift = config.drift.as_ref().filter(|drift| {
+        drift.notify == DriftNotify::Email
+            && (drift.environments.is_empty()
+                || drift.environments.contains(&azure_subscription.env))
+    });
+    if drift.is_some() {
+        variables.insert("DRIFT_EMAIL_CONNECTION_STRING".to_string(), secret());
+        variables.insert("DRIFT_EMAIL_SENDER".to_string(), plain(""));
+    }
+
+    VariableGroup {
+        name: variable_group_name(config, &azure_subscription.env),
+        description: format!(
+            "Pulumi settings and secrets of {} in {}, generated by pulumimi",
+            config.resource_name, azure_subscription.env
+        ),
+        group_type: "Vsts".to_string(),
+        variables,
+    }
+}
+
+/// Writes `variable-groups/<app>-<env>.json` for every selected environment.
+///
+/// # Arguments
+/// - `config`: The project configuration, including the selected environments.
+/// - `output_dir`: The project directory.
+pub async fn create_variable_groups(
+    config: &ProjectConfig,
+    output_dir: Option<&str>,
+) -> Result<(), Error> {
+    let registry = SubscriptionRegistry::load()?;
+    let secrets = secret_inputs(config)?;
+    let dir = Path::new(output_dir.unwrap_or(".")).join(VARIABLE_GROUP_DIR);
+    fs::create_dir_all(&dir)?;
+
+    for env in &config.environments {
+        let group = variable_group(config, &registry.get(env)?, &secrets);
+        let path = dir.join(format!("{}.json", group.name));
+        fs::write(&path, serde_json::to_string_pretty(&group)? + "\n")
+            .map_err(|err| anyhow!("Failed to write to {}: {}", path.display(), err))?;
+    }
+    Ok(())
+}
+
+/// Names of the variable groups defined in a project's `variable-groups` directory.
+pub fn load_variable_group_names(dir: &Path) -> Result<Vec<String>, Error> {
+    let dir = dir.join(VARIABLE_GROUP_DIR);
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut names = Vec::new();
+    for entry in fs::read_dir(&dir)? {
+        let path = entry?.path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+            continue;
+        }
+        let content = fs::read_to_string(&path)?;
+        let group: VariableGroup = serde_json::from_str(&content)
+            .map_err(|err| anyhow!("Invalid variable group {}: {}", path.display(), err))?;
+        names.push(group.name);
+    }
+    names.sort();
+    Ok(names)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_variable_group() {
+        let config = ProjectConfig {
+            resource_name: "myapp".to_string(),
+            environments: vec!["dev".to_string()],
+            additional_resources: vec!["database".to_string(), "cache".to_string()],
+            ..Default::default()
+        };
+        let secrets = secret_inputs(&config).unwrap();
+        assert_eq!(
+            secrets,
+            [SecretInput {
+                config_key: "databaseAdministratorLoginPassword".to_string(),
+                variable: "DATABASE_ADMINISTRATOR_LOGIN_PASSWORD".to_string(),
+            }]
+        );
+
+        let registry = SubscriptionRegistry::from_layers(&[(
+            "org".to_string(),
+            include_str!("../../config/subscriptions.yaml").to_string(),
+        )])
+        .unwrap();
+        let group = variable_group(&config, &registry.get("dev").unwrap(), &secrets);
+
+        assert_eq!(group.name, "myapp-dev");
+        assert_eq!(
+            group.variables.keys().collect::<Vec<_>>(),
+            [
+                "ARM_SUBSCRIPTION_ID",
+                "DATABASE_ADMINISTRATOR_LOGIN_PASSWORD",
+                "PULUMI_ACCESS_TOKEN",
+                "PULUMI_BACKEND_URL",
+                "PULUMI_STACK",
+            ]
+        );
+        assert!(group.variables["PULUMI_ACCESS_TOKEN"].is_secret);
+        assert_eq!(
+            group.variables["PULUMI_BACKEND_URL"].value,
+            "https://api.pulumi.com"
+        );
+
+        // Generated secrets stay in Key Vault
+        let key_vault = ProjectConfig {
+            secret_mode: SecretMode::KeyVault,
+            ..config
+        };
+        assert!(secret_inputs(&key_vault).unwrap().is_empty());
+    }
+}
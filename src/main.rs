@@ -3,19 +3,37 @@ use cli::Cli;
 
 mod commands {
     pub mod config;
+    pub mod cost;
+    pub mod pipeline;
     pub mod project;
     pub mod snippet;
 }
 
 mod helpers {
+    pub mod bicep_helper;
+    pub mod budget;
+    pub mod config_diff;
     pub mod config_helper;
+    pub mod config_schema;
+    pub mod cost_diff;
+    pub mod cost_helper;
     pub mod entrypoint_helper;
+    pub mod network_plan;
     pub mod pipeline_helper;
+    pub mod pipeline_schema;
+    pub mod price_catalog;
+    pub mod readme_helper;
+    pub mod snippet_catalog;
+    pub mod stack_document;
+    pub mod subscription_registry;
+    pub mod tag_policy;
+    pub mod variable_group;
 }
 
 mod cli;
 mod tui {
     pub mod app;
+    pub mod config_editor;
     mod constants;
     mod enums;
     pub mod snippet_select;
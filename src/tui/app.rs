@@ -1,4 +1,4 @@
-use std::{io, vec};
+use std::{collections::BTreeMap, io, ops::Range, vec};
 
 use crossterm::event::{Event, KeyCode, KeyEventKind};
 use ratatui::{
@@ -8,31 +8,164 @@ use ratatui::{
 };
 use serde::{Deserialize, Serialize};
 
+use crate::helpers::budget::{check_budgets, BudgetCheck};
+use crate::helpers::cost_helper::{usage_from_config, CostEstimate};
+use crate::helpers::price_catalog::PriceCatalog;
+use crate::helpers::subscription_registry::{AzureSubscription, SubscriptionRegistry};
+
 use super::{
-    constants::{ENVIRONMENT_OPTIONS, RESOURCE_OPTIONS, SELECTION_OFFSET},
-    enums::{AppEnvironment, ResourceType},
+    constants::{RESOURCE_OPTIONS, SELECTION_OFFSET},
+    enums::ResourceType,
     ui::{self},
     validation::{self},
 };
 
+/// How the generated Pulumi program is laid out on disk.
+#[derive(Serialize, Deserialize, Default, Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum OutputLayout {
+    /// Every resource appended to a single `index.ts`.
+    #[default]
+    SingleFile,
+    /// One `resources/<instance>.ts` module per resource, wired together by `index.ts`.
+    Modular,
+    /// A single `ComponentResource` class that owns every resource, created by `index.ts`.
+    Component,
+}
+
+/// How secret snippet inputs, such as the database administrator password, are supplied.
+#[derive(Serialize, Deserialize, Default, Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum SecretMode {
+    /// Read with `config.requireSecret`; every stack file gets a secure placeholder to replace.
+    #[default]
+    Placeholder,
+    /// Generated with `@pulumi/random` and stored in the project's Key Vault.
+    KeyVault,
+}
+
+/// CI system the deployment pipeline is generated for.
+#[derive(Serialize, Deserialize, Default, Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum CiTarget {
+    /// `azure-pipelines.yaml` for Azure DevOps.
+    #[default]
+    AzurePipelines,
+    /// `.github/workflows/infra.yml` for GitHub Actions.
+    Github,
+    /// `.gitlab-ci.yml` for GitLab CI/CD.
+    Gitlab,
+}
+
+/// How a scheduled drift check reports drift.
+#[derive(Serialize, Deserialize, Default, Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum DriftNotify {
+    /// The check fails, showing up in the pipeline's run history.
+    #[default]
+    Fail,
+    /// The check fails and emails the owner through Azure Communication Services.
+    Email,
+}
+
+/// What happens when an environment's estimated cost is above its monthly budget.
+#[derive(Serialize, Deserialize, Default, Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum BudgetPolicy {
+    /// Generate anyway and print the overage.
+    #[default]
+    Warn,
+    /// Refuse to generate the project.
+    Enforce,
+}
+
+/// Scheduled job that looks for resources changed outside Pulumi.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DriftDetection {
+    /// Cron schedule, in UTC.
+    #[serde(default = "default_drift_schedule")]
+    pub schedule: String,
+    /// Environments checked; every selected environment when empty.
+    #[serde(default)]
+    pub environments: Vec<String>,
+    #[serde(default)]
+    pub notify: DriftNotify,
+}
+
+fn default_drift_schedule() -> String {
+    "0 3 * * *".to_string()
+}
+
+impl Default for DriftDetection {
+    fn default() -> Self {
+        Self {
+            schedule: default_drift_schedule(),
+            environments: Vec::new(),
+            notify: DriftNotify::default(),
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Default, Debug, Clone)]
 pub struct ProjectConfig {
     pub resource_name: String,
     pub environments: Vec<String>,
     pub additional_resources: Vec<String>,
     pub owner_email: String,
+    #[serde(default)]
+    pub layout: OutputLayout,
+    /// Also export the project as Bicep alongside the Pulumi program.
+    #[serde(default)]
+    pub export_bicep: bool,
+    #[serde(default)]
+    pub secret_mode: SecretMode,
+    /// Pulumi organization substituted for `{org}` in stack names.
+    #[serde(default)]
+    pub organization: Option<String>,
+    /// Stack name pattern using `{org}`, `{app}` and `{env}`; defaults to `{env}`.
+    #[serde(default)]
+    pub stack_pattern: Option<String>,
+    /// ESC environments imported by every stack, e.g. `{app}/{env}`.
+    #[serde(default)]
+    pub esc_environments: Vec<String>,
+    /// Deploy region-scoped resources to several regions and a paired DR region.
+    #[serde(default)]
+    pub multi_region: bool,
+    /// Regions deployed alongside each environment's primary location in multi-region mode.
+    #[serde(default)]
+    pub regions: Vec<String>,
+    /// DR region; defaults to the Azure pair of each environment's primary location.
+    #[serde(default)]
+    pub dr_region: Option<String>,
+    /// Tag values given on the command line, overriding the tag policy sources.
+    #[serde(default)]
+    pub tags: BTreeMap<String, String>,
+    /// Environments whose tier is above this one get a manual approval gate in the
+    /// generated pipeline; defaults to `silver`.
+    #[serde(default)]
+    pub approval_tier: Option<String>,
+    #[serde(default)]
+    pub ci: CiTarget,
+    /// Nightly drift detection in the generated pipeline; off when `None`.
+    #[serde(default)]
+    pub drift: Option<DriftDetection>,
+    /// Monthly budgets by environment or tier, e.g. `prod: 2000` or `bronze: 300`,
+    /// taking precedence over the budgets of the subscription registry.
+    #[serde(default)]
+    pub budgets: BTreeMap<String, f64>,
+    #[serde(default)]
+    pub budget_policy: BudgetPolicy,
 }
 
 impl ProjectConfig {
+    /// Defaults to every environment in the subscription registry. If the registry
+    /// cannot be loaded no environment is selected; generation reports the registry error.
     pub fn default(name: &str) -> Self {
         Self {
             resource_name: name.to_owned(),
-            environments: vec![
-                AppEnvironment::Dev.to_string().to_lowercase(),
-                AppEnvironment::Test.to_string().to_lowercase(),
-                AppEnvironment::Stage.to_string().to_lowercase(),
-                AppEnvironment::Prod.to_string().to_lowercase(),
-            ],
+            environments: SubscriptionRegistry::load()
+                .map(|registry| registry.environment_names())
+                .unwrap_or_default(),
             additional_resources: vec![
                 ResourceType::Database.to_string().to_lowercase(),
                 ResourceType::Cache.to_string().to_lowercase(),
@@ -42,6 +175,21 @@ impl ProjectConfig {
                 ResourceType::ContainerRegistry.to_string().to_lowercase(),
             ],
             owner_email: String::new(),
+            layout: OutputLayout::default(),
+            export_bicep: false,
+            secret_mode: SecretMode::default(),
+            organization: None,
+            stack_pattern: None,
+            esc_environments: Vec::new(),
+            multi_region: false,
+            regions: Vec::new(),
+            dr_region: None,
+            tags: BTreeMap::new(),
+            approval_tier: None,
+            ci: CiTarget::default(),
+            drift: None,
+            budgets: BTreeMap::new(),
+            budget_policy: BudgetPolicy::default(),
         }
     }
 }
@@ -53,22 +201,113 @@ pub struct App {
     pub config: ProjectConfig,
     pub focus: usize, // Index of the currently focused input field
     pub user_input_container: Vec<String>,
+    /// Environments offered for selection, in subscription registry order.
+    pub environment_options: Vec<AzureSubscription>,
     pub validation_error: Option<String>,
     pub show_exit_screen: bool,
+    registry: Option<SubscriptionRegistry>,
+    /// Offline prices the cost chart is estimated from, or why they could not be loaded.
+    pub price_catalog: Result<PriceCatalog, String>,
+    /// Cost estimate of the last selection, recomputed only when the selection changes.
+    cost: Option<CostCache>,
+}
+
+/// What a cost estimate depends on: the selected environments, resources and regions.
+#[derive(Debug, Clone, PartialEq)]
+struct CostSelection {
+    environments: Vec<String>,
+    resources: Vec<String>,
+    multi_region: bool,
+    regions: Vec<String>,
+    dr_region: Option<String>,
+}
+
+impl CostSelection {
+    fn of(config: &ProjectConfig) -> Self {
+        Self {
+            environments: config.environments.clone(),
+            resources: config.additional_resources.clone(),
+            multi_region: config.multi_region,
+            regions: config.regions.clone(),
+            dr_region: config.dr_region.clone(),
+        }
+    }
+}
+
+/// A cost estimate with its budget checks, and the selection it was made for.
+struct CostCache {
+    selection: CostSelection,
+    estimate: Option<CostEstimate>,
+    checks: Vec<BudgetCheck>,
 }
 
 impl App {
     pub fn new() -> Self {
-        Self {
+        let registry = SubscriptionRegistry::load().ok();
+        let environment_options = registry
+            .as_ref()
+            .map(|registry| registry.subscriptions().to_vec())
+            .unwrap_or_default();
+
+        let mut app = Self {
             exit: false,
             confirmation: false,
             _input_buffer: String::new(),
             config: ProjectConfig::default("default_name"),
             focus: 0, // Start focus on the first input field
             user_input_container: Vec::new(),
+            environment_options,
             validation_error: None,
             show_exit_screen: false,
+            registry,
+            price_catalog: PriceCatalog::load().map_err(|err| err.to_string()),
+            cost: None,
+        };
+        app.refresh_cost_estimate();
+        app
+    }
+
+    /// Estimates the current selection again if it changed since the last estimate.
+    pub fn refresh_cost_estimate(&mut self) {
+        let selection = CostSelection::of(&self.config);
+        if self
+            .cost
+            .as_ref()
+            .is_some_and(|cost| cost.selection == selection)
+        {
+            return;
         }
+
+        let estimate = match (&self.registry, &self.price_catalog) {
+            (Some(registry), Ok(catalog)) => usage_from_config(&self.config, registry)
+                .ok()
+                .map(|usage| CostEstimate::new(&usage, catalog)),
+            _ => None,
+        };
+        let checks = match (&self.registry, &estimate) {
+            (Some(registry), Some(estimate)) => {
+                check_budgets(&self.config.budgets, registry, estimate).unwrap_or_default()
+            }
+            _ => Vec::new(),
+        };
+        self.cost = Some(CostCache {
+            selection,
+            estimate,
+            checks,
+        });
+    }
+
+    /// Estimated monthly cost of the current selection, or `None` if it cannot be estimated.
+    pub fn cost_estimate(&self) -> Option<&CostEstimate> {
+        self.cost.as_ref()?.estimate.as_ref()
+    }
+
+    /// Budget checks of the estimated environments that have a budget.
+    pub fn budget_checks(&self) -> &[BudgetCheck] {
+        self.cost
+            .as_ref()
+            .map(|cost| cost.checks.as_slice())
+            .unwrap_or_default()
     }
 
     pub fn run<B: Backend>(&mut self, terminal: &mut Terminal<B>) -> io::Result<()> {
@@ -79,6 +318,8 @@ impl App {
                 _ => &App::new(), // This seems unnecessary as it's just a placeholder; can be removed
             };
 
+            self.refresh_cost_estimate();
+
             // Draw the UI on each iteration, passing the error message if validation failed
             terminal.draw(|frame| {
                 if let Some(error_message) = &self.validation_error {
@@ -119,18 +360,18 @@ impl App {
             .constraints(
                 [
                     Constraint::Percentage(50), // Input Blade (left)
-                    Constraint::Percentage(50), // Review and Chart (right)
+                    Constraint::Percentage(50), // Help and Chart (right)
                 ]
                 .as_ref(),
             )
             .split(frame.area());
 
-        // Divide the right column into two rows: Config Review and Chart View
+        // Divide the right column into two rows: Help & Guidance and Chart View
         let right_chunks = Layout::default()
             .direction(Direction::Vertical)
             .constraints(
                 [
-                    Constraint::Percentage(70), // JSON/YAML Review
+                    Constraint::Percentage(70), // Help & Guidance
                     Constraint::Percentage(30), // Chart View
                 ]
                 .as_ref(),
@@ -147,57 +388,54 @@ impl App {
             // Render the Help Context, passing the error message if present
             ui::render_help_context::<B>(self, frame, right_chunks[0], err_message);
 
-            // Render the Config Review
-            ui::render_config_review::<B>(self, frame, right_chunks[1]);
+            // Render the Cost Chart
+            ui::render_cost_chart::<B>(self, frame, right_chunks[1]);
         }
     }
 
-    fn handle_keycode_right_env(&mut self, environment: AppEnvironment) {
-        if self
-            .config
-            .environments
-            .contains(&environment.to_string().to_lowercase())
-        {
-            self.config
-                .environments
-                .retain(|env| env != &environment.to_string().to_lowercase());
+    /// Focus indices of the environment toggles, which follow the two text inputs and the header.
+    pub fn environment_focus_range(&self) -> Range<usize> {
+        3..3 + self.environment_options.len()
+    }
+
+    /// Focus indices of the resource toggles, which follow the environments and their header.
+    pub fn resource_focus_range(&self) -> Range<usize> {
+        let start = self.environment_focus_range().end + 1;
+        start..start + RESOURCE_OPTIONS.len()
+    }
+
+    fn handle_keycode_right_env(&mut self, index: usize) {
+        let environment = self.environment_options[index].env.clone();
+        if self.config.environments.contains(&environment) {
+            self.config.environments.retain(|env| env != &environment);
         } else {
-            self.config
-                .environments
-                .push(environment.to_string().to_lowercase())
+            self.config.environments.push(environment)
         }
     }
 
-    fn handle_keycode_right_resource(&mut self, resource: ResourceType) {
-        if self
-            .config
-            .additional_resources
-            .contains(&resource.to_string().to_lowercase())
-        {
+    fn handle_keycode_right_resource(&mut self, index: usize) {
+        let resource = RESOURCE_OPTIONS[index].to_lowercase();
+        if self.config.additional_resources.contains(&resource) {
             self.config
                 .additional_resources
-                .retain(|res| res != &resource.to_string().to_lowercase());
+                .retain(|res| res != &resource);
         } else {
-            self.config
-                .additional_resources
-                .push(resource.to_string().to_lowercase())
+            self.config.additional_resources.push(resource)
         }
     }
 
     fn environments_toggle_all(&mut self) {
-        let all_selected: bool = ENVIRONMENT_OPTIONS.iter().all(|env| {
-            self.config
-                .environments
-                .contains(&env.to_string().to_lowercase())
-        });
+        let all_selected: bool = self
+            .environment_options
+            .iter()
+            .all(|option| self.config.environments.contains(&option.env));
 
         if all_selected {
             self.config.environments = Vec::new();
         } else {
-            for env in ENVIRONMENT_OPTIONS {
-                let env_str = env.to_string().to_lowercase();
-                if !self.config.environments.contains(&env_str) {
-                    self.config.environments.push(env_str);
+            for option in &self.environment_options {
+                if !self.config.environments.contains(&option.env) {
+                    self.config.environments.push(option.env.clone());
                 }
             }
         }
@@ -223,11 +461,15 @@ impl App {
     }
 
     fn cycle_focus(&mut self, direction: isize) {
-        let total_options = ENVIRONMENT_OPTIONS.len() + RESOURCE_OPTIONS.len() + SELECTION_OFFSET;
-        self.focus = ((self.focus as isize + direction + total_options as isize)
-            % total_options as isize) as usize;
-        if self.focus == 2 || self.focus == 7 {
-            self.focus = (self.focus as isize + direction) as usize;
+        let total_options =
+            self.environment_options.len() + RESOURCE_OPTIONS.len() + SELECTION_OFFSET;
+        let headers = [2, self.environment_focus_range().end];
+        loop {
+            self.focus = ((self.focus as isize + direction + total_options as isize)
+                % total_options as isize) as usize;
+            if !headers.contains(&self.focus) {
+                break;
+            }
         }
     }
 
@@ -246,25 +488,22 @@ impl App {
                     self.cycle_focus(-1);
                 }
 
-                KeyCode::Right => match self.focus {
-                    3 | 4 | 5 | 6 => self.environments_toggle_all(),
-                    8 | 9 | 10 | 11 | 12 | 13 => self.resources_toggle_all(),
-                    _ => (),
-                },
+                KeyCode::Right => {
+                    if self.environment_focus_range().contains(&self.focus) {
+                        self.environments_toggle_all()
+                    } else if self.resource_focus_range().contains(&self.focus) {
+                        self.resources_toggle_all()
+                    }
+                }
 
                 KeyCode::Char(c) => match self.focus {
                     0 => self.config.resource_name.push(c),
                     1 => self.config.owner_email.push(c),
-                    3 => self.handle_keycode_right_env(AppEnvironment::Dev),
-                    4 => self.handle_keycode_right_env(AppEnvironment::Test),
-                    5 => self.handle_keycode_right_env(AppEnvironment::Stage),
-                    6 => self.handle_keycode_right_env(AppEnvironment::Prod),
-                    8 => self.handle_keycode_right_resource(ResourceType::Database),
-                    9 => self.handle_keycode_right_resource(ResourceType::Cache),
-                    10 => self.handle_keycode_right_resource(ResourceType::ServiceBus),
-                    11 => self.handle_keycode_right_resource(ResourceType::Storage),
-                    12 => self.handle_keycode_right_resource(ResourceType::Keyvault),
-                    13 => self.handle_keycode_right_resource(ResourceType::ContainerRegistry),
+                    focus if self.environment_focus_range().contains(&focus) => {
+                        self.handle_keycode_right_env(focus - self.environment_focus_range().start)
+                    }
+                    focus if self.resource_focus_range().contains(&focus) => self
+                        .handle_keycode_right_resource(focus - self.resource_focus_range().start),
                     _ => {}
                 },
 
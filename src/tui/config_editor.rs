@@ -0,0 +1,733 @@
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use crossterm::{
+    event::{Event, KeyCode, KeyEventKind},
+    terminal,
+};
+use ratatui::{
+    layout::{Constraint, Direction, Layout},
+    prelude::{Backend, CrosstermBackend},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Cell, Paragraph, Row, Table},
+    Frame, Terminal,
+};
+use serde_yaml::Value;
+
+use crate::helpers::config_diff::environment_label;
+use crate::helpers::config_helper::{load_stack_files, AzureConfigYaml};
+use crate::helpers::config_schema::{infer_project, ConfigSchema, ValueKind};
+use crate::helpers::snippet_catalog::find_snippet;
+use crate::helpers::stack_document::StackDocument;
+
+/// Group of keys that do not belong to the project namespace, e.g. `azure-native:location`.
+const PROVIDER_GROUP: &str = "Providers";
+/// Group of project keys that no selected resource reads.
+const UNKNOWN_GROUP: &str = "Unknown";
+/// Group of the stack metadata written by pulumimi.
+const STACK_GROUP: &str = "Stack";
+
+/// One stack file open in the editor.
+struct EditorStack {
+    file_name: String,
+    environment: String,
+    /// Source of truth for saving, so that key order and comments survive edits.
+    document: StackDocument,
+    /// Parsed from `document` after every edit.
+    settings: AzureConfigYaml,
+    dirty: bool,
+}
+
+impl EditorStack {
+    fn value(&self, key: &str) -> Option<&Value> {
+        self.settings.config.get(&Value::from(key))
+    }
+
+    fn reparse(&mut self) {
+        self.settings = serde_yaml::from_str(&self.document.to_string()).unwrap_or_default();
+        self.dirty = true;
+    }
+}
+
+/// A line of the key list: a resource heading or a namespaced config key.
+#[derive(Debug, PartialEq)]
+enum EditorRow {
+    Group(String),
+    Key(String),
+}
+
+#[derive(Debug, PartialEq)]
+pub enum EditorMode {
+    Browse,
+    /// Editing the selected cell; holds the text typed so far.
+    Edit(String),
+    /// Typing the name of a key to add to every stack file.
+    AddKey(String),
+    /// Waiting for `y` to remove the selected key from every stack file.
+    ConfirmRemove,
+}
+
+/// Stack-config editor: one row per config key, grouped by resource, and one
+/// column per environment.
+pub struct ConfigEditor {
+    dir: PathBuf,
+    schema: ConfigSchema,
+    stacks: Vec<EditorStack>,
+    /// Only show the keys of this snippet, if set.
+    resource: Option<&'static str>,
+    rows: Vec<EditorRow>,
+    row: usize,
+    column: usize,
+    pub mode: EditorMode,
+    /// Status line message and whether it is an error.
+    message: Option<(String, bool)>,
+    quit_requested: bool,
+    pub exit: bool,
+}
+
+impl ConfigEditor {
+    /// Opens every stack file in a project directory.
+    pub fn load(dir: &Path, resource: Option<&str>) -> Result<Self, anyhow::Error> {
+        let resource = match resource {
+            Some(name) => Some(
+                find_snippet(name)
+                    .ok_or_else(|| anyhow::anyhow!("Unknown resource '{}'", name))?
+                    .key,
+            ),
+            None => None,
+        };
+
+        let stack_files = load_stack_files(dir)?;
+        let schema = ConfigSchema::for_project(&infer_project(dir, &stack_files)?)?;
+        let mut stacks = Vec::new();
+        for stack_file in stack_files {
+            let content = fs::read_to_string(dir.join(&stack_file.file_name))?;
+            stacks.push(EditorStack {
+                environment: environment_label(&stack_file),
+                file_name: stack_file.file_name,
+                document: StackDocument::parse(&content),
+                settings: stack_file.settings,
+                dirty: false,
+            });
+        }
+
+        let mut editor = Self {
+            dir: dir.to_path_buf(),
+            schema,
+            stacks,
+            resource,
+            rows: Vec::new(),
+            row: 0,
+            column: 0,
+            mode: EditorMode::Browse,
+            message: None,
+            quit_requested: false,
+            exit: false,
+        };
+        editor.rebuild_rows();
+        Ok(editor)
+    }
+
+    /// Heading a key is listed under.
+    fn group_of(&self, key: &str) -> String {
+        match self.schema.get_full(key) {
+            Some(schema_key) => match schema_key.resource.and_then(find_snippet) {
+                Some(def) => def.display_name.to_string(),
+                None => STACK_GROUP.to_string(),
+            },
+            None if key.starts_with(&format!("{}:", self.schema.namespace)) => {
+                UNKNOWN_GROUP.to_string()
+            }
+            None => PROVIDER_GROUP.to_string(),
+        }
+    }
+
+    /// Lists every key set in any stack file plus the required keys set in none,
+    /// grouped by resource in schema order.
+    fn rebuild_rows(&mut self) {
+        let mut keys: Vec<String> = Vec::new();
+        let present = self.stacks.iter().flat_map(|stack| {
+            stack
+                .settings
+                .config
+                .iter()
+                .filter_map(|(key, _)| key.as_str().map(str::to_string))
+        });
+        let required = self
+            .schema
+            .keys
+            .iter()
+            .filter(|schema_key| schema_key.required)
+            .map(|schema_key| format!("{}:{}", self.schema.namespace, schema_key.key));
+        for key in present.chain(required) {
+            let in_filter = match self.resource {
+                Some(resource) => self
+                    .schema
+                    .get_full(&key)
+                    .is_some_and(|schema_key| schema_key.resource == Some(resource)),
+                None => true,
+            };
+            if in_filter && !keys.contains(&key) {
+                keys.push(key);
+            }
+        }
+
+        let mut groups: Vec<String> = vec![STACK_GROUP.to_string()];
+        for schema_key in &self.schema.keys {
+            if let Some(def) = schema_key.resource.and_then(find_snippet) {
+                if !groups.iter().any(|group| group == def.display_name) {
+                    groups.push(def.display_name.to_string());
+                }
+            }
+        }
+        groups.push(PROVIDER_GROUP.to_string());
+        groups.push(UNKNOWN_GROUP.to_string());
+
+        let selected = self.selected_key().map(str::to_string);
+        self.rows.clear();
+        for group in groups {
+            let group_keys: Vec<&String> = keys
+                .iter()
+                .filter(|key| self.group_of(key) == group)
+                .collect();
+            if group_keys.is_empty() {
+                continue;
+            }
+            self.rows.push(EditorRow::Group(group));
+            for key in group_keys {
+                self.rows.push(EditorRow::Key(key.clone()));
+            }
+        }
+
+        self.row = selected
+            .and_then(|key| {
+                self.rows
+                    .iter()
+                    .position(|row| row == &EditorRow::Key(key.clone()))
+            })
+            .or_else(|| {
+                self.rows
+                    .iter()
+                    .position(|row| matches!(row, EditorRow::Key(_)))
+            })
+            .unwrap_or(0);
+    }
+
+    fn selected_key(&self) -> Option<&str> {
+        match self.rows.get(self.row) {
+            Some(EditorRow::Key(key)) => Some(key),
+            _ => None,
+        }
+    }
+
+    fn move_row(&mut self, direction: isize) {
+        let mut row = self.row as isize;
+        loop {
+            row += direction;
+            if row < 0 || row >= self.rows.len() as isize {
+                return;
+            }
+            if matches!(self.rows[row as usize], EditorRow::Key(_)) {
+                self.row = row as usize;
+                return;
+            }
+        }
+    }
+
+    fn move_column(&mut self, direction: isize) {
+        let columns = self.stacks.len() as isize;
+        self.column = ((self.column as isize + direction + columns) % columns) as usize;
+    }
+
+    fn set_message(&mut self, message: String, is_error: bool) {
+        self.message = Some((message, is_error));
+    }
+
+    fn start_edit(&mut self) {
+        let Some(key) = self.selected_key() else {
+            return;
+        };
+        let is_secret = self.schema.get_full(key).map(|k| k.kind) == Some(ValueKind::Secret)
+            || matches!(self.stacks[self.column].value(key), Some(Value::Mapping(m)) if m.contains_key(&Value::from("secure")));
+        if is_secret {
+            let stack = self.stacks[self.column].environment.clone();
+            let message = format!(
+                "Secrets are encrypted, set them with `pulumi config set --secret {} --stack {}`",
+                key.split_once(':').map_or(key, |(_, name)| name),
+                stack
+            );
+            self.set_message(message, true);
+            return;
+        }
+        let text = match self.stacks[self.column].value(key) {
+            Some(Value::String(text)) => text.clone(),
+            Some(Value::Null) | None => String::new(),
+            Some(value) => serde_json::to_string(value).unwrap_or_default(),
+        };
+        self.message = None;
+        self.mode = EditorMode::Edit(text);
+    }
+
+    /// Parses the typed text as the kind of value the key holds.
+    fn parse_input(&self, key: &str, text: &str) -> Result<Value, String> {
+        let kind = self.schema.get_full(key).map(|schema_key| schema_key.kind);
+        if kind == Some(ValueKind::String) || text.trim().is_empty() {
+            return Ok(Value::from(text.trim()));
+        }
+        serde_yaml::from_str(text).map_err(|err| format!("Invalid value: {}", err))
+    }
+
+    /// Writes the edited value to the selected cell if it passes the schema, and
+    /// otherwise keeps editing with the issue shown.
+    fn commit_edit(&mut self, text: String) {
+        let Some(key) = self.selected_key().map(str::to_string) else {
+            return;
+        };
+        let checked =
+            self.parse_input(&key, &text)
+                .and_then(|value| match self.schema.check(&key, &value) {
+                    Some(issue) => Err(issue.to_string()),
+                    None => Ok(value),
+                });
+        let value = match checked {
+            Ok(value) => value,
+            Err(err) => {
+                self.mode = EditorMode::Edit(text);
+                return self.set_message(err, true);
+            }
+        };
+
+        let stack = &mut self.stacks[self.column];
+        stack.document.set(&key, &value);
+        stack.reparse();
+        let message = format!("Set {} in {}", key, stack.file_name);
+        self.mode = EditorMode::Browse;
+        self.set_message(message, false);
+    }
+
+    /// Adds a schema key to every stack file that lacks it, with its generated default.
+    fn commit_add(&mut self, name: &str) {
+        let name = name.trim();
+        let key = if name.contains(':') {
+            name.to_string()
+        } else {
+            format!("{}:{}", self.schema.namespace, name)
+        };
+        let Some(schema_key) = self.schema.get_full(&key) else {
+            return self.set_message(
+                format!("'{}' is not a config key of this project", key),
+                true,
+            );
+        };
+        let value = schema_key
+            .default
+            .clone()
+            .unwrap_or_else(|| match schema_key.kind {
+                ValueKind::Number => Value::from(0),
+                ValueKind::Boolean => Value::from(false),
+                ValueKind::List => Value::Sequence(Vec::new()),
+                ValueKind::Object | ValueKind::Secret => Value::Mapping(Default::default()),
+                ValueKind::String => Value::from(""),
+            });
+
+        let mut added = 0;
+        for stack in self
+            .stacks
+            .iter_mut()
+            .filter(|stack| stack.value(&key).is_none())
+        {
+            stack.document.set(&key, &value);
+            stack.reparse();
+            added += 1;
+        }
+        self.mode = EditorMode::Browse;
+        self.rebuild_rows();
+        if let Some(row) = self
+            .rows
+            .iter()
+            .position(|row| row == &EditorRow::Key(key.clone()))
+        {
+            self.row = row;
+        }
+        self.set_message(format!("Added {} to {} stack file(s)", key, added), false);
+    }
+
+    /// Removes the selected key from every stack file.
+    fn remove_selected(&mut self) {
+        let Some(key) = self.selected_key().map(str::to_string) else {
+            return;
+        };
+        let mut removed = 0;
+        for stack in &mut self.stacks {
+            if stack.document.remove(&key) {
+                stack.reparse();
+                removed += 1;
+            }
+        }
+        self.mode = EditorMode::Browse;
+        self.move_row(1);
+        self.rebuild_rows();
+        self.set_message(
+            format!("Removed {} from {} stack file(s)", key, removed),
+            false,
+        );
+    }
+
+    /// Writes every changed stack file.
+    pub fn save(&mut self) -> io::Result<()> {
+        let mut saved = Vec::new();
+        for stack in self.stacks.iter_mut().filter(|stack| stack.dirty) {
+            fs::write(self.dir.join(&stack.file_name), stack.document.to_string())?;
+            stack.dirty = false;
+            saved.push(stack.file_name.clone());
+        }
+        self.quit_requested = false;
+        let message = match saved.is_empty() {
+            true => "No changes to save".to_string(),
+            false => format!("Saved {}", saved.join(", ")),
+        };
+        self.set_message(message, false);
+        Ok(())
+    }
+
+    pub fn handle_key(&mut self, code: KeyCode) -> io::Result<()> {
+        match std::mem::replace(&mut self.mode, EditorMode::Browse) {
+            EditorMode::Edit(mut text) => match code {
+                KeyCode::Enter => self.commit_edit(text),
+                KeyCode::Esc => {}
+                KeyCode::Backspace => {
+                    text.pop();
+                    self.mode = EditorMode::Edit(text);
+                }
+                KeyCode::Char(c) => {
+                    text.push(c);
+                    self.mode = EditorMode::Edit(text);
+                }
+                _ => self.mode = EditorMode::Edit(text),
+            },
+            EditorMode::AddKey(mut text) => match code {
+                KeyCode::Enter => self.commit_add(&text.clone()),
+                KeyCode::Esc => {}
+                KeyCode::Backspace => {
+                    text.pop();
+                    self.mode = EditorMode::AddKey(text);
+                }
+                KeyCode::Char(c) => {
+                    text.push(c);
+                    self.mode = EditorMode::AddKey(text);
+                }
+                _ => self.mode = EditorMode::AddKey(text),
+            },
+            EditorMode::ConfirmRemove => {
+                if code == KeyCode::Char('y') {
+                    self.remove_selected();
+                } else {
+                    self.message = None;
+                }
+            }
+            EditorMode::Browse => match code {
+                KeyCode::Up => self.move_row(-1),
+                KeyCode::Down => self.move_row(1),
+                KeyCode::Left => self.move_column(-1),
+                KeyCode::Right | KeyCode::Tab => self.move_column(1),
+                KeyCode::Enter => self.start_edit(),
+                KeyCode::Char('a') => {
+                    self.message = None;
+                    self.mode = EditorMode::AddKey(String::new());
+                }
+                KeyCode::Char('d') if self.selected_key().is_some() => {
+                    self.mode = EditorMode::ConfirmRemove;
+                }
+                KeyCode::Char('s') => self.save()?,
+                KeyCode::Char('q') | KeyCode::Esc => {
+                    if self.stacks.iter().any(|stack| stack.dirty) && !self.quit_requested {
+                        self.quit_requested = true;
+                        self.set_message(
+                            "Unsaved changes: press 's' to save or 'q' again to quit".to_string(),
+                            true,
+                        );
+                    } else {
+                        self.exit = true;
+                    }
+                }
+                _ => {}
+            },
+        }
+        Ok(())
+    }
+
+    pub fn run<B: Backend>(&mut self, terminal: &mut Terminal<B>) -> io::Result<()> {
+        while !self.exit {
+            terminal.draw(|frame| self.draw(frame))?;
+            if let Event::Key(key_event) = crossterm::event::read()? {
+                if key_event.kind == KeyEventKind::Press {
+                    self.handle_key(key_event.code)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(5), Constraint::Length(3)].as_ref())
+            .split(frame.area());
+
+        let header_style = Style::default()
+            .fg(Color::Rgb(45, 152, 218))
+            .add_modifier(Modifier::BOLD);
+        let header = Row::new(
+            std::iter::once(Cell::from("Key")).chain(self.stacks.iter().map(|stack| {
+                let marker = if stack.dirty { " *" } else { "" };
+                Cell::from(format!("{}{}", stack.environment, marker))
+            })),
+        )
+        .style(header_style);
+
+        let rows = self.rows.iter().enumerate().map(|(index, row)| match row {
+            EditorRow::Group(group) => Row::new(vec![Cell::from(format!("▸ {}", group))]).style(
+                Style::default()
+                    .fg(Color::Rgb(120, 111, 166))
+                    .add_modifier(Modifier::BOLD),
+            ),
+            EditorRow::Key(key) => {
+                let mut cells = vec![Cell::from(format!("  {}", key))];
+                for (column, stack) in self.stacks.iter().enumerate() {
+                    let (text, mut style) = match stack.value(key) {
+                        None => ("-".to_string(), Style::default().fg(Color::LightRed)),
+                        Some(value) => {
+                            let text = match value {
+                                Value::Mapping(m) if m.contains_key(&Value::from("secure")) => {
+                                    "[secret]".to_string()
+                                }
+                                Value::String(text) => text.clone(),
+                                value => serde_json::to_string(value).unwrap_or_default(),
+                            };
+                            let style = match self.schema.check(key, value) {
+                                Some(_) => Style::default().fg(Color::LightRed),
+                                None => Style::default().fg(Color::Rgb(189, 197, 129)),
+                            };
+                            (text, style)
+                        }
+                    };
+                    if index == self.row && column == self.column {
+                        style = style.add_modifier(Modifier::REVERSED);
+                    }
+                    cells.push(Cell::from(text).style(style));
+                }
+                Row::new(cells)
+            }
+        });
+
+        let mut widths = vec![Constraint::Percentage(40)];
+        let column_width = 60 / self.stacks.len().max(1) as u16;
+        widths.extend(
+            self.stacks
+                .iter()
+                .map(|_| Constraint::Percentage(column_width)),
+        );
+
+        let title = match self.resource.and_then(find_snippet) {
+            Some(def) => format!("Stack Config: {}", def.display_name),
+            None => "Stack Config".to_string(),
+        };
+        let table = Table::new(rows, widths).header(header).block(
+            Block::default()
+                .title(Span::styled(
+                    title,
+                    Style::default()
+                        .fg(Color::Rgb(74, 105, 189))
+                        .add_modifier(Modifier::BOLD),
+                ))
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Rgb(112, 161, 255))),
+        );
+        frame.render_widget(table, chunks[0]);
+
+        let environment = &self.stacks[self.column].environment;
+        let status = match (&self.mode, &self.message) {
+            (EditorMode::Edit(text), _) => Line::from(format!(
+                "{} in {}: {}▏",
+                self.selected_key().unwrap_or_default(),
+                environment,
+                text
+            )),
+            (EditorMode::AddKey(text), _) => {
+                Line::from(format!("Key to add to every environment: {}▏", text))
+            }
+            (EditorMode::ConfirmRemove, _) => Line::from(format!(
+                "Remove {} from every stack file? (y/n)",
+                self.selected_key().unwrap_or_default()
+            )),
+            (EditorMode::Browse, Some((message, true))) => Line::from(Span::styled(
+                format!("⚠  {}", message),
+                Style::default().fg(Color::LightRed),
+            )),
+            (EditorMode::Browse, Some((message, false))) => Line::from(message.as_str()),
+            (EditorMode::Browse, None) => Line::from(Span::styled(
+                "↑/↓/←/→ move  Enter edit  a add key  d remove key  s save  q quit",
+                Style::default().fg(Color::LightYellow),
+            )),
+        };
+        let status = match (&self.mode, &self.message) {
+            (EditorMode::Edit(_) | EditorMode::AddKey(_), Some((message, true))) => {
+                vec![
+                    status,
+                    Line::from(Span::styled(
+                        format!("⚠  {}", message),
+                        Style::default().fg(Color::LightRed),
+                    )),
+                ]
+            }
+            _ => vec![status],
+        };
+        frame.render_widget(
+            Paragraph::new(status).block(Block::default().borders(Borders::TOP)),
+            chunks[1],
+        );
+    }
+}
+
+/// Opens the stack-config editor on a project directory.
+pub fn run_config_editor(dir: &Path, resource: Option<&str>) -> Result<(), anyhow::Error> {
+    // Load before taking over the terminal so that errors print normally
+    let mut editor = ConfigEditor::load(dir, resource)?;
+
+    let backend = CrosstermBackend::new(io::stdout());
+    let mut terminal = Terminal::new(backend)?;
+    terminal::enable_raw_mode()?;
+    terminal.hide_cursor()?;
+    terminal.clear()?;
+    io::stdout().flush()?;
+
+    let result = editor.run(&mut terminal);
+
+    terminal.clear()?;
+    terminal.show_cursor()?;
+    ratatui::restore();
+    Ok(result?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DEV: &str = "# dev stack\nconfig:\n  \"myapp:app\": myapp\n  \"myapp:env\": dev\n  \"myapp:tier\": bronze # cheapest\n  \"myapp:cacheSkuName\": Standard\n  \"myapp:cacheCapacity\": 1\n  \"azure-native:location\": eastus2\n";
+    const PROD: &str = "config:\n  \"myapp:app\": myapp\n  \"myapp:env\": prod\n  \"myapp:tier\": platinum\n  \"myapp:cacheSkuName\": Premium\n  \"azure-native:location\": eastus2\n";
+
+    fn editor() -> (tempfile::TempDir, ConfigEditor) {
+        let temp_dir = tempfile::tempdir().unwrap();
+        fs::write(temp_dir.path().join("Pulumi.dev.yaml"), DEV).unwrap();
+        fs::write(temp_dir.path().join("Pulumi.prod.yaml"), PROD).unwrap();
+        let editor = ConfigEditor::load(temp_dir.path(), None).unwrap();
+        (temp_dir, editor)
+    }
+
+    fn select(editor: &mut ConfigEditor, key: &str) {
+        editor.row = editor
+            .rows
+            .iter()
+            .position(|row| row == &EditorRow::Key(key.to_string()))
+            .unwrap();
+    }
+
+    fn type_text(editor: &mut ConfigEditor, text: &str) {
+        for c in text.chars() {
+            editor.handle_key(KeyCode::Char(c)).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_rows_are_grouped_by_resource() {
+        let (_temp_dir, editor) = editor();
+
+        let groups: Vec<&str> = editor
+            .rows
+            .iter()
+            .filter_map(|row| match row {
+                EditorRow::Group(group) => Some(group.as_str()),
+                EditorRow::Key(_) => None,
+            })
+            .collect();
+        assert_eq!(groups, vec!["Stack", "Azure Cache for Redis", "Providers"]);
+        assert_eq!(editor.rows[1], EditorRow::Key("myapp:app".to_string()));
+        // Required keys set in no stack file are listed so they can be filled in
+        assert!(editor
+            .rows
+            .contains(&EditorRow::Key("myapp:location".to_string())));
+    }
+
+    #[test]
+    fn test_edit_is_validated_against_the_schema() {
+        let (temp_dir, mut editor) = editor();
+        select(&mut editor, "myapp:cacheSkuName");
+
+        editor.handle_key(KeyCode::Enter).unwrap();
+        assert_eq!(editor.mode, EditorMode::Edit("Standard".to_string()));
+        for _ in 0.."Standard".len() {
+            editor.handle_key(KeyCode::Backspace).unwrap();
+        }
+        type_text(&mut editor, "Gold");
+        editor.handle_key(KeyCode::Enter).unwrap();
+        assert_eq!(editor.mode, EditorMode::Edit("Gold".to_string()));
+        assert!(editor
+            .message
+            .as_ref()
+            .unwrap()
+            .0
+            .contains("expected one of"));
+
+        editor.handle_key(KeyCode::Esc).unwrap();
+        select(&mut editor, "myapp:tier");
+        editor.handle_key(KeyCode::Enter).unwrap();
+        for _ in 0.."bronze".len() {
+            editor.handle_key(KeyCode::Backspace).unwrap();
+        }
+        type_text(&mut editor, "silver");
+        editor.handle_key(KeyCode::Enter).unwrap();
+        assert_eq!(editor.mode, EditorMode::Browse);
+
+        editor.save().unwrap();
+        let saved = fs::read_to_string(temp_dir.path().join("Pulumi.dev.yaml")).unwrap();
+        assert_eq!(saved, DEV.replace("bronze # cheapest", "silver # cheapest"));
+    }
+
+    #[test]
+    fn test_add_and_remove_keys_in_every_environment() {
+        let (temp_dir, mut editor) = editor();
+
+        editor.handle_key(KeyCode::Char('a')).unwrap();
+        type_text(&mut editor, "cacheCapacity");
+        editor.handle_key(KeyCode::Enter).unwrap();
+        assert_eq!(
+            editor.message.as_ref().unwrap().0,
+            "Added myapp:cacheCapacity to 1 stack file(s)"
+        );
+
+        select(&mut editor, "myapp:cacheSkuName");
+        editor.handle_key(KeyCode::Char('d')).unwrap();
+        editor.handle_key(KeyCode::Char('y')).unwrap();
+        editor.handle_key(KeyCode::Char('q')).unwrap();
+        assert!(
+            !editor.exit,
+            "quitting with unsaved changes needs a second 'q'"
+        );
+        editor.handle_key(KeyCode::Char('s')).unwrap();
+        editor.handle_key(KeyCode::Char('q')).unwrap();
+        assert!(editor.exit);
+
+        let prod = fs::read_to_string(temp_dir.path().join("Pulumi.prod.yaml")).unwrap();
+        assert_eq!(
+            prod,
+            "config:\n  \"myapp:app\": myapp\n  \"myapp:env\": prod\n  \"myapp:tier\": platinum\n  \"azure-native:location\": eastus2\n  \"myapp:cacheCapacity\": 1\n"
+        );
+        let dev = fs::read_to_string(temp_dir.path().join("Pulumi.dev.yaml")).unwrap();
+        assert!(dev.starts_with("# dev stack\n"));
+        assert!(!dev.contains("cacheSkuName"));
+    }
+}
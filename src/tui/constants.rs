@@ -1,7 +1,5 @@
 pub const SELECTION_OFFSET: usize = 4;
 
-pub const ENVIRONMENT_OPTIONS: &[&str] = &["dev", "test", "stage", "prod"];
-
 pub const RESOURCE_OPTIONS: &[&str] = &[
     "Database",
     "Cache",
@@ -237,22 +237,14 @@ impl<'a, 'b> UiLineLabel<'a> for UiToggleLine<'a, 'b> {
 //     }
 // }
 
-#[derive(Display, Debug, PartialEq)]
-pub enum AppEnvironment {
-    Dev = 3,
-    Prod = 6,
-    Stage = 5,
-    Test = 4,
-}
-
 #[derive(Clone, Display, Debug, PartialEq)]
 pub enum ResourceType {
-    Database = 8,
-    Cache = 9,
-    ServiceBus = 10,
-    Storage = 11,
-    Keyvault = 12,
-    ContainerRegistry = 13,
+    Database,
+    Cache,
+    ServiceBus,
+    Storage,
+    Keyvault,
+    ContainerRegistry,
 }
 
 #[derive(Debug)]
@@ -261,56 +253,6 @@ pub struct TextInputFieldProperties {
     pub value: &'static str,
 }
 
-pub trait SetUserOption {
-    fn set_user_option(&self, app_state: &App) -> String;
-}
-
-impl SetUserOption for AppEnvironment {
-    fn set_user_option(&self, app_state: &App) -> String {
-        format!(
-            "{}{} {}",
-            if app_state.focus == self.value() {
-                "> "
-            } else {
-                "  "
-            },
-            if app_state
-                .config
-                .environments
-                .contains(&self.to_string().to_lowercase())
-            {
-                "[x]"
-            } else {
-                "[ ]"
-            },
-            self.to_string().to_lowercase()
-        )
-    }
-}
-
-impl SetUserOption for ResourceType {
-    fn set_user_option(&self, app_state: &App) -> String {
-        format!(
-            "{}{} {}",
-            if app_state.focus == self.value() {
-                "> "
-            } else {
-                "  "
-            },
-            if app_state
-                .config
-                .additional_resources
-                .contains(&self.to_string().to_lowercase())
-            {
-                "[x]"
-            } else {
-                "[ ]"
-            },
-            self.to_string().to_lowercase()
-        )
-    }
-}
-
 // impl SetUserOption for TextInputFields {
 //     fn set_user_option(&self, app_state: &App) -> String {
 //         match self {
@@ -341,31 +283,3 @@ impl SetUserOption for ResourceType {
 //         }
 //     }
 // }
-
-pub trait EnumIndex {
-    fn value(&self) -> usize;
-}
-
-impl EnumIndex for AppEnvironment {
-    fn value(&self) -> usize {
-        match self {
-            AppEnvironment::Dev => 3,
-            AppEnvironment::Test => 4,
-            AppEnvironment::Stage => 5,
-            AppEnvironment::Prod => 6,
-        }
-    }
-}
-
-impl EnumIndex for ResourceType {
-    fn value(&self) -> usize {
-        match self {
-            ResourceType::Database => 8,
-            ResourceType::Cache => 9,
-            ResourceType::ServiceBus => 10,
-            ResourceType::Storage => 11,
-            ResourceType::Keyvault => 12,
-            ResourceType::ContainerRegistry => 13,
-        }
-    }
-}
@@ -3,6 +3,9 @@ use super::{
     app::App,
     enums::{UiInputLineAppState, UiInputLineValue, UiLineLabel, UiTextInputLine, UiToggleLine},
 };
+use crate::helpers::budget::BudgetCheck;
+use crate::helpers::cost_helper::{format_amount, CostEstimate, EnvironmentCost, CURRENCY};
+use crate::helpers::snippet_catalog::SNIPPET_CATALOG;
 
 use ratatui::{
     layout::{Constraint, Layout, Rect},
@@ -28,7 +31,7 @@ use ratatui::{
 /// The rendered component consists of:
 /// - A bordered block titled "Azure Configuration"
 /// - Editable input fields (e.g., Application Name, Owner's Email)
-/// - Toggleable options for environment selection (e.g., Dev, Test, Stage, Prod)
+/// - Toggleable options for each environment in the subscription registry
 /// - Additional resource options (e.g., Database, Cache, Storage)
 pub fn render_input_blade<B: Backend>(app_state: &App, frame: &mut Frame, area: Rect) {
     let mut ui_input_lines: Vec<Line> = Vec::new();
@@ -54,25 +57,12 @@ pub fn render_input_blade<B: Backend>(app_state: &App, frame: &mut Frame, area:
     )]);
     ui_input_lines.push(env_header);
 
-    UiToggleLine::new()
-        .app_state(app_state)
-        .label("Dev")
-        .container(&mut ui_input_lines);
-
-    UiToggleLine::new()
-        .app_state(app_state)
-        .label("Test")
-        .container(&mut ui_input_lines);
-
-    UiToggleLine::new()
-        .app_state(app_state)
-        .label("Stage")
-        .container(&mut ui_input_lines);
-
-    UiToggleLine::new()
-        .app_state(app_state)
-        .label("Prod")
-        .container(&mut ui_input_lines);
+    for option in &app_state.environment_options {
+        UiToggleLine::new()
+            .app_state(app_state)
+            .label(&option.env)
+            .container(&mut ui_input_lines);
+    }
 
     let resources_header = Line::from(vec![Span::styled(
         "  Additional Resources:",
@@ -127,52 +117,176 @@ pub fn render_input_blade<B: Backend>(app_state: &App, frame: &mut Frame, area:
     frame.render_widget(input_widget, area);
 }
 
-pub fn render_config_review<B: Backend>(app_state: &App, frame: &mut Frame, area: Rect) {
-    let config_json = serde_json::to_string_pretty(&app_state.config)
-        .unwrap_or_else(|_| "Invalid JSON".to_string());
-    let styled_json = styled_json(&config_json);
+/// Colors of the resources in the cost chart, in snippet catalog order.
+const RESOURCE_COLORS: &[Color] = &[
+    Color::Rgb(45, 152, 218),
+    Color::Rgb(255, 127, 80),
+    Color::Rgb(136, 84, 208),
+    Color::Rgb(189, 197, 129),
+    Color::Rgb(46, 204, 113),
+    Color::Rgb(241, 196, 15),
+];
+
+fn resource_color(resource: &str) -> Color {
+    let index = SNIPPET_CATALOG
+        .iter()
+        .position(|def| def.key == resource)
+        .unwrap_or_default();
+    RESOURCE_COLORS[index % RESOURCE_COLORS.len()]
+}
+
+/// Renders the estimated monthly cost of the selected environments as stacked bars,
+/// one per environment with a segment per resource, priced from the offline catalog.
+pub fn render_cost_chart<B: Backend>(app_state: &App, frame: &mut Frame, area: Rect) {
+    let lines = match (&app_state.price_catalog, app_state.cost_estimate()) {
+        (Err(err), _) => vec![Line::from(Span::styled(
+            format!("Cannot estimate the cost: {}", err),
+            Style::default().fg(Color::LightRed),
+        ))],
+        (Ok(_), Some(estimate))
+            if estimate
+                .environments
+                .iter()
+                .any(|environment| !environment.resources.is_empty()) =>
+        {
+            cost_chart_lines(
+                estimate,
+                app_state.budget_checks(),
+                area.width.saturating_sub(2) as usize,
+            )
+        }
+        _ => vec![Line::from(Span::styled(
+            "Select environments and resources to see their estimated monthly cost.",
+            Style::default().fg(Color::Rgb(189, 197, 129)),
+        ))],
+    };
 
-    let review = Paragraph::new(styled_json).block(
+    let captured = match &app_state.price_catalog {
+        Ok(catalog) => catalog.captured(),
+        Err(_) => "n/a".to_string(),
+    };
+    let title = format!("Estimated Monthly Cost ({}, prices {})", CURRENCY, captured);
+    let chart = Paragraph::new(lines).block(
         Block::default()
-            .title("Config Review (JSON)")
-            .style(Style::default().fg(Color::Rgb(255, 127, 80)))
+            .title(Span::styled(
+                title,
+                Style::default()
+                    .fg(Color::Rgb(255, 127, 80))
+                    .add_modifier(Modifier::BOLD),
+            ))
             .borders(Borders::ALL)
             .border_style(Style::default().fg(Color::Rgb(112, 161, 255))),
     );
 
-    frame.render_widget(review, area);
+    frame.render_widget(chart, area);
 }
 
-fn styled_json(json: &str) -> Vec<Line<'static>> {
-    let mut styled_lines = Vec::new();
-
-    for line in json.lines() {
-        let mut spans = Vec::new();
-
-        if line.contains(':') {
-            let parts: Vec<&str> = line.splitn(2, ':').collect();
-            let key = parts[0];
-            let value = parts[1];
-
-            spans.push(Span::styled(
-                key.to_string(),
-                Style::default()
-                    .fg(Color::LightBlue)
-                    .add_modifier(Modifier::BOLD),
-            ));
-
-            spans.push(Span::raw(":"));
+/// One bar per environment scaled to the most expensive one, followed by a legend.
+/// Totals over budget are shown in red next to the budget.
+fn cost_chart_lines(
+    estimate: &CostEstimate,
+    checks: &[BudgetCheck],
+    width: usize,
+) -> Vec<Line<'static>> {
+    // Meters without a price make a total a lower bound
+    let totals: Vec<String> = estimate
+        .environments
+        .iter()
+        .map(|environment| {
+            let bound = match environment.unpriced().is_empty() {
+                true => "",
+                false => "≥ ",
+            };
+            match checks.iter().find(|check| check.env == environment.env) {
+                Some(check) => format!(
+                    "{}{} / {}",
+                    bound,
+                    format_amount(check.estimate),
+                    format_amount(check.budget)
+                ),
+                None => format!("{}{}", bound, format_amount(environment.total())),
+            }
+        })
+        .collect();
+    let label_width = estimate
+        .environments
+        .iter()
+        .map(|environment| environment.env.chars().count())
+        .max()
+        .unwrap_or_default();
+    let total_width = totals
+        .iter()
+        .map(|total| total.chars().count())
+        .max()
+        .unwrap_or_default();
+    let bar_width = width.saturating_sub(label_width + total_width + 2);
+    let max_total = estimate
+        .environments
+        .iter()
+        .map(EnvironmentCost::total)
+        .fold(0.0, f64::max);
+
+    let mut lines = Vec::new();
+    let mut legend: Vec<String> = Vec::new();
+    for (environment, total) in estimate.environments.iter().zip(totals) {
+        let mut spans = vec![Span::raw(format!(
+            "{:<width$} ",
+            environment.env,
+            width = label_width
+        ))];
+        let mut drawn = 0;
+        for resource in &environment.resources {
+            if !legend.contains(&resource.resource) {
+                legend.push(resource.resource.clone());
+            }
+            let segment = match max_total > 0.0 {
+                true => (resource.monthly / max_total * bar_width as f64).round() as usize,
+                false => 0,
+            };
+            let segment = segment.min(bar_width - drawn);
+            drawn += segment;
             spans.push(Span::styled(
-                value.to_string(),
-                Style::default().fg(Color::Rgb(255, 127, 80)),
+                "█".repeat(segment),
+                Style::default().fg(resource_color(&resource.resource)),
             ));
-        } else {
-            spans.push(Span::raw(line.to_string()));
         }
+        spans.push(Span::raw(" ".repeat(bar_width - drawn + 1)));
+
+        let over_budget = checks
+            .iter()
+            .any(|check| check.env == environment.env && check.is_over());
+        spans.push(Span::styled(
+            format!("{:>width$}", total, width = total_width),
+            match (over_budget, environment.unpriced().is_empty()) {
+                (true, _) => Style::default()
+                    .fg(Color::LightRed)
+                    .add_modifier(Modifier::BOLD),
+                (false, false) => Style::default().fg(Color::Yellow),
+                (false, true) => Style::default().fg(Color::Rgb(189, 197, 129)),
+            },
+        ));
+        lines.push(Line::from(spans));
+    }
 
-        styled_lines.push(Line::from(spans));
+    lines.push(Line::from(""));
+    lines.push(Line::from(
+        legend
+            .iter()
+            .flat_map(|resource| {
+                [
+                    Span::styled("■ ", Style::default().fg(resource_color(resource))),
+                    Span::raw(format!("{}  ", resource)),
+                ]
+            })
+            .collect::<Vec<_>>(),
+    ));
+    if !estimate.unpriced().is_empty() {
+        lines.push(Line::from(Span::styled(
+            "≥ Incomplete: the price catalog has no price for some meters.",
+            Style::default().fg(Color::Yellow),
+        )));
     }
-    styled_lines
+    lines
 }
 
 pub fn render_help_context<B: Backend>(
@@ -181,42 +295,53 @@ pub fn render_help_context<B: Backend>(
     area: Rect,
     error_message: Option<String>,
 ) {
-    let help_text = match app_state.focus {
-        0 => {
+    let environment_help;
+    let resource_range = app_state.resource_focus_range();
+    let resource_index = resource_range
+        .contains(&app_state.focus)
+        .then(|| app_state.focus - resource_range.start);
+    let help_text = match (app_state.focus, resource_index) {
+        (0, _) => {
             "Enter the application name.\n\n\
             This will be used as a base name for all resources.\n\
             Typically you would use some convention that I have to find out about."
         }
-        1 => {
+        (1, _) => {
             "Provide the owner's email.\n\n\
             Notifications/Alerts will be sent here.\n\
             This is typically a project lead or other. I need to confirm."
         }
-        3..=6 => {
-            "Select the environments where the application will be deployed.\n\n\
-            Each environment maps to a specific Azure Subscription by default.\n\n\
-            Dev = dev-01\n\
-            Test = non-prod\n\
-            Stage = preprod\n\
-            Prod = prod-01"
+        (focus, _) if app_state.environment_focus_range().contains(&focus) => {
+            environment_help = app_state.environment_options.iter().fold(
+                "Select the environments where the application will be deployed.\n\n\
+                Each environment maps to an Azure Subscription in the subscription registry.\n"
+                    .to_string(),
+                |help, option| {
+                    format!(
+                        "{}\n{} = {} ({}, {})",
+                        help, option.env, option.name, option.tier, option.location
+                    )
+                },
+            );
+            environment_help.as_str()
         }
-        8 => "Include Azure SQL resources in the project.\n\n\
+        (_, Some(0)) => "Include Azure SQL resources in the project.\n\n\
             - Azure SQL Server.\n\
             - Azure SQL Database.\n\
             - Managed relational database service for SQL Server.",
-        9 => "Include Azure Cache for Redis resource in the project.\n\n\
+        (_, Some(1)) => "Include Azure Cache for Redis resource in the project.\n\n\
             - Azure Cache for Redis.\n\
             - In-memory data structure store, used as a database, cache, and message broker.",
-        10 => "Include Azure Service Bus resource in the project.\n\n\
+        (_, Some(2)) => "Include Azure Service Bus resource in the project.\n\n\
             - Azure Service Bus.\n\
             - Fully managed enterprise message broker with message queues and publish-subscribe topics.",
-        11 => "Include Azure Storage resource in the project.\n\n\
+        (_, Some(3)) => "Include Azure Storage resource in the project.\n\n\
             - Azure Storage Account.\n\
             - Durable, highly available, and massively scalable cloud storage solution.",
-        12 => "Include Azure Key Vault resource in the project.\n\n\
+        (_, Some(4)) => "Include Azure Key Vault resource in the project.\n\n\
             - Azure Key Vault.\n\
             - Used for storing secrets, keys, and certificates securely.",
-        13 => " - Azure Container Registry.\n\
+        (_, Some(5)) => " - Azure Container Registry.\n\
                 - Used for storing and managing container images.",
         _ => "Use arrow keys to navigate. Press 'Enter' to confirm selections.",
     };
@@ -319,3 +444,88 @@ pub fn render_exit_screen<B: Backend>(frame: &mut Frame) {
 
     frame.render_widget(exit_chunk, chunks[0]);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::cost_helper::ResourceCost;
+
+    fn environment(env: &str, costs: &[(&str, f64)]) -> EnvironmentCost {
+        EnvironmentCost {
+            env: env.to_string(),
+            resources: costs
+                .iter()
+                .map(|(resource, monthly)| ResourceCost {
+                    resource: resource.to_string(),
+                    region: "eastus2".to_string(),
+                    sku: "Standard".to_string(),
+                    monthly: *monthly,
+                    unpriced: Vec::new(),
+                })
+                .collect(),
+        }
+    }
+
+    fn text(line: &Line) -> String {
+        line.spans
+            .iter()
+            .map(|span| span.content.as_ref())
+            .collect()
+    }
+
+    #[test]
+    fn test_cost_chart_lines() {
+        let estimate = CostEstimate {
+            environments: vec![
+                environment("dev", &[("database", 50.0), ("cache", 50.0)]),
+                environment("prod", &[("database", 150.0), ("cache", 50.0)]),
+            ],
+        };
+        let checks = [BudgetCheck {
+            env: "prod".to_string(),
+            budget: 150.0,
+            estimate: 200.0,
+            drivers: Vec::new(),
+            unpriced: Vec::new(),
+        }];
+
+        let lines = cost_chart_lines(&estimate, &checks, 41);
+
+        // Bars are scaled to the most expensive environment and share one width
+        assert_eq!(
+            text(&lines[0]),
+            format!("dev  {}{} {:>15}", "█".repeat(10), " ".repeat(10), "100.00")
+        );
+        assert_eq!(
+            text(&lines[1]),
+            format!("prod {} 200.00 / 150.00", "█".repeat(20))
+        );
+        assert_eq!(lines[1].spans[1].style.fg, Some(resource_color("database")));
+        assert_eq!(lines[1].spans[4].style.fg, Some(Color::LightRed));
+        assert_eq!(text(&lines[3]), "■ database  ■ cache  ");
+        assert_eq!(lines.len(), 4);
+    }
+
+    #[test]
+    fn test_cost_chart_lines_incomplete() {
+        let mut dev = environment("dev", &[("database", 50.0), ("cache", 0.0)]);
+        dev.resources[1].unpriced = vec!["C1 Cache Instance".to_string()];
+        let estimate = CostEstimate {
+            environments: vec![dev],
+        };
+        let checks = [BudgetCheck {
+            env: "dev".to_string(),
+            budget: 100.0,
+            estimate: 50.0,
+            drivers: Vec::new(),
+            unpriced: vec!["cache: C1 Cache Instance".to_string()],
+        }];
+
+        let lines = cost_chart_lines(&estimate, &checks, 41);
+
+        // The total is a lower bound, not a cost within budget
+        assert!(text(&lines[0]).ends_with("≥ 50.00 / 100.00"));
+        assert_eq!(lines[0].spans[4].style.fg, Some(Color::Yellow));
+        assert!(text(&lines[3]).starts_with("≥ Incomplete"));
+    }
+}
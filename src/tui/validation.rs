@@ -28,7 +28,5 @@ mod tests {
             validate_inputs(&app),
             Err("Owner email cannot be empty.".to_string())
         );
-
-        
     }
 }